@@ -0,0 +1,173 @@
+use crate::errors::*;
+use crate::commands::{self, Result};
+use crate::input::Key;
+use crate::models::application::{Application, Mode};
+use crate::util;
+use regex::{self, Regex};
+use scribe::buffer::{Position, Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+pub fn push_char(app: &mut Application) -> Result {
+    let c = match *app.view.last_key() {
+        Some(Key::Char(c)) => c,
+        _ => bail!("Last key press wasn't a character"),
+    };
+
+    if let Mode::Align(ref mut mode) = app.mode {
+        mode.input.push(c);
+    } else {
+        bail!("Cannot push char outside of align mode");
+    }
+
+    Ok(())
+}
+
+pub fn toggle_regex(app: &mut Application) -> Result {
+    if let Mode::Align(ref mut mode) = app.mode {
+        mode.regex = !mode.regex;
+    } else {
+        bail!("Cannot toggle regex outside of align mode");
+    }
+
+    Ok(())
+}
+
+pub fn pop_char(app: &mut Application) -> Result {
+    if let Mode::Align(ref mut mode) = app.mode {
+        mode.input.pop();
+    } else {
+        bail!("Cannot pop char outside of align mode");
+    }
+
+    Ok(())
+}
+
+/// Aligns the selected lines on the delimiter entered, as a single change.
+/// It's used as a regular expression, unless that's been toggled off or it
+/// isn't valid as one (e.g. a lone `(`), in which case it's matched literally;
+/// the prompt shows when that's the case.
+pub fn accept(app: &mut Application) -> Result {
+    let (start_line, end_line, delimiter) = match app.mode {
+        Mode::Align(ref mode) => {
+            if mode.input.is_empty() {
+                bail!("Please provide a delimiter to align on");
+            }
+            let pattern = if mode.literal() {
+                regex::quote(&mode.input)
+            } else {
+                mode.input.clone()
+            };
+            let delimiter = Regex::new(&pattern).chain_err(|| "Invalid delimiter")?;
+
+            (mode.start_line, mode.end_line, delimiter)
+        }
+        _ => bail!("Cannot align outside of align mode"),
+    };
+    commands::application::switch_to_normal_mode(app)?;
+
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let tab_width = app.preferences.borrow().tab_width(buffer.path.as_ref());
+    let data = buffer.data();
+    let lines: Vec<&str> = data.lines().skip(start_line).take(end_line + 1 - start_line).collect();
+    let aligned_lines = util::align::align(&lines, &delimiter, tab_width);
+
+    buffer.start_operation_group();
+    for (index, (line, aligned_line)) in lines.iter().zip(aligned_lines).enumerate() {
+        if *line == aligned_line {
+            continue;
+        }
+
+        let line_number = start_line + index;
+        buffer.delete_range(Range::new(
+            Position{ line: line_number, offset: 0 },
+            Position{ line: line_number, offset: line.graphemes(true).count() }
+        ));
+        buffer.cursor.move_to(Position{ line: line_number, offset: 0 });
+        buffer.insert(aligned_line);
+    }
+    buffer.end_operation_group();
+    buffer.cursor.move_to(Position{ line: start_line, offset: 0 });
+
+    commands::view::scroll_to_cursor(app)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::{self, app_with_buffer};
+    use crate::input::Key;
+    use crate::models::Application;
+    use crate::models::application::Mode;
+    use scribe::buffer::Position;
+
+    // Enters align mode with the first three lines selected, and types the delimiter.
+    fn prompt(data: &str, delimiter: &str) -> Application {
+        let mut app = app_with_buffer(data, Position::new());
+        commands::application::switch_to_select_line_mode(&mut app).unwrap();
+        commands::cursor::move_down(&mut app).unwrap();
+        commands::cursor::move_down(&mut app).unwrap();
+        commands::selection::align(&mut app).unwrap();
+        for c in delimiter.chars() {
+            app.view.last_key = Some(Key::Char(c));
+            super::push_char(&mut app).unwrap();
+        }
+
+        app
+    }
+
+    fn prompt_text(app: &Application) -> Option<String> {
+        match app.mode {
+            Mode::Align(ref mode) => Some(mode.to_string()),
+            _ => None,
+        }
+    }
+
+    fn align(data: &str, delimiter: &str) -> Application {
+        let mut app = prompt(data, delimiter);
+        super::accept(&mut app).unwrap();
+
+        app
+    }
+
+    #[test]
+    fn accept_aligns_selected_lines_as_one_change() {
+        let mut app = align("a => 1,\nlong => 2,\nb => 3,\nc => 4", "=>");
+
+        assert!(matches!(app.mode, Mode::Normal));
+        assert_eq!(
+            app.workspace.current_buffer().unwrap().data(),
+            "a    => 1,\nlong => 2,\nb    => 3,\nc => 4"
+        );
+
+        commands::buffer::undo(&mut app).unwrap();
+        assert_eq!(
+            app.workspace.current_buffer().unwrap().data(),
+            "a => 1,\nlong => 2,\nb => 3,\nc => 4"
+        );
+    }
+
+    #[test]
+    fn accept_matches_invalid_expressions_literally() {
+        let mut app = prompt("f(a)\nlong(b)\ng(c)", "(");
+        assert_eq!(prompt_text(&app), Some(String::from("Align on: ( [literal]")));
+        super::accept(&mut app).unwrap();
+
+        assert_eq!(
+            app.workspace.current_buffer().unwrap().data(),
+            "f   (a)\nlong(b)\ng   (c)"
+        );
+    }
+
+    #[test]
+    fn accept_matches_literally_when_regex_is_toggled_off() {
+        let mut app = prompt("a.b\nlong.c\nd.e", ".");
+        assert_eq!(prompt_text(&app), Some(String::from("Align on: .")));
+        super::toggle_regex(&mut app).unwrap();
+        assert_eq!(prompt_text(&app), Some(String::from("Align on: . [literal]")));
+        super::accept(&mut app).unwrap();
+
+        assert_eq!(
+            app.workspace.current_buffer().unwrap().data(),
+            "a   .b\nlong.c\nd   .e"
+        );
+    }
+}
@@ -1,29 +1,199 @@
 
 use crate::errors::*;
-use crate::commands::{self, Result};
-use crate::input::KeyMap;
-use scribe::Buffer;
+use crate::commands::{self, Command, Result};
+use crate::input::{Key, KeyMap};
+use scribe::{Buffer, Workspace};
+use scribe::buffer::{Position, Range};
+use smallvec::SmallVec;
+use std::collections::HashSet;
 use std::mem;
-use crate::models::application::{Application, Mode};
+use std::ptr;
+use crate::models::application::{Application, Mode, Snippet, MAX_COUNT};
 use crate::models::application::modes::*;
 use crate::util;
+use crate::util::token;
+use unicode_segmentation::UnicodeSegmentation;
+
+// Commands that modify buffers without editing them, whose
+// key presses are never kept as the last change.
+const UNREPEATABLE_COMMANDS: [Command; 4] = [
+    commands::buffer::save,
+    commands::buffer::reload,
+    commands::buffer::undo,
+    commands::buffer::redo,
+];
 
 pub fn handle_input(app: &mut Application) -> Result {
-    // Listen for and respond to user input.
-    let commands = app.view.last_key().as_ref().and_then(|key| {
+    // Keys are only recorded if a macro was being recorded before and after
+    // they're handled, so that those starting and stopping it are left out.
+    let recording = app.macros.recording().is_some();
+    let key = app.view.last_key().clone();
+
+    // Once a count has been started, any digit extends it,
+    // regardless of what it's bound to on its own.
+    let extends_count = match (&app.mode, app.count, &key) {
+        (Mode::Normal, Some(_), Some(Key::Char(c))) => c.is_ascii_digit(),
+        _ => false,
+    };
+
+    // Key presses are collected from normal mode until the application
+    // returns to it, and kept as the last change if they edited the buffer.
+    if matches!(app.mode, Mode::Normal) && !extends_count {
+        let revision = buffer_revision(app);
+        app.last_change.start(app.count, revision);
+    }
+
+    // Secondary cursors are only moved by edits made through them, so they're
+    // cleared if the buffer is edited any other way (e.g. deleting a line in
+    // normal mode, or undoing), rather than being left at stale positions.
+    let revision = buffer_revision(app);
+    let cursors_version = app.cursors.version();
+
+    let result = if extends_count {
+        push_count_digit(app)
+    } else {
+        let commands = bound_commands(app);
+        let unrepeatable = commands.iter().flatten().any(|command| {
+            UNREPEATABLE_COMMANDS.iter().any(|other| ptr::fn_addr_eq(*command, *other))
+        });
+        if unrepeatable {
+            app.last_change.ignore();
+        }
+
+        run_bound_commands(app, commands)
+    };
+
+    if app.cursors.version() == cursors_version && edited_since(app, revision) {
+        app.cursors.clear();
+    }
+
+    if let Some(key) = key {
+        // Replayed keys are left out, since the key that
+        // repeated the change has already been recorded.
+        if recording && !app.last_change.replaying() {
+            app.macros.record(key.clone());
+        }
+        app.last_change.record(key);
+    }
+    if app.last_change.pending() && matches!(app.mode, Mode::Normal) {
+        let revision = buffer_revision(app);
+        app.last_change.finish(revision);
+    }
+
+    // Edits are added to the buffer's undo history once they're complete,
+    // rather than after every key press made in insert mode.
+    if !matches!(app.mode, Mode::Insert) {
+        if let Some(buffer) = app.workspace.current_buffer() {
+            if let Some(revision) = app.view.buffer_revision(buffer) {
+                app.undo_history.record(buffer, Some(revision), app.view.take_changed_line(buffer));
+            }
+        }
+    }
+
+    result
+}
+
+/// Replays the key presses that made the last change to a buffer, starting
+/// at the cursor. A count replaces the one the change was originally made with.
+pub fn repeat_last_change(app: &mut Application) -> Result {
+    let count = app.count.take();
+    let (change_count, keys) = app.last_change.get().ok_or("There's no change to repeat")?;
+
+    app.last_change.start_replay();
+    app.count = count.or(change_count);
+    let mut result = Ok(());
+    for key in keys {
+        app.view.last_key = Some(key);
+        result = handle_input(app);
+        if result.is_err() {
+            break;
+        }
+    }
+    app.last_change.finish_replay();
+
+    // Don't leave a count behind if the change didn't use it.
+    app.count = None;
+
+    result
+}
+
+// The current buffer's ID and content revision, used to cheaply detect edits.
+fn buffer_revision(app: &mut Application) -> Option<(Option<usize>, usize)> {
+    let buffer = app.workspace.current_buffer()?;
+    let revision = app.view.buffer_revision(buffer)?;
+
+    Some((buffer.id, revision))
+}
+
+// Whether the current buffer has been edited since its revision was checked.
+fn edited_since(app: &mut Application, revision: Option<(Option<usize>, usize)>) -> bool {
+    match (revision, buffer_revision(app)) {
+        (Some((id, revision)), Some((current_id, current_revision))) => {
+            id == current_id && revision != current_revision
+        }
+        _ => false,
+    }
+}
+
+/// Adds the last key press to the pending count,
+/// which repeats the next command run in normal mode.
+pub fn push_count_digit(app: &mut Application) -> Result {
+    let digit = match *app.view.last_key() {
+        Some(Key::Char(c)) => c.to_digit(10).ok_or("Last key press wasn't a digit")?,
+        _ => bail!("Last key press wasn't a digit"),
+    };
+
+    let count = app.count
+        .unwrap_or(0)
+        .saturating_mul(10)
+        .saturating_add(digit as usize)
+        .min(MAX_COUNT);
+    app.count = Some(count).filter(|count| *count > 0);
+
+    Ok(())
+}
+
+pub fn reset_count(app: &mut Application) -> Result {
+    app.count = None;
+
+    Ok(())
+}
+
+// Runs the commands bound to the last key press, stopping at the first error
+// encountered, if any. They're repeated according to the pending count, unless
+// they've changed it (i.e. they're entering it) or left normal mode.
+// The commands bound to the last key press in the current mode.
+fn bound_commands(app: &Application) -> Option<SmallVec<[Command; 4]>> {
+    app.view.last_key().as_ref().and_then(|key| {
         app.mode_str().and_then(|mode| {
             app.preferences.borrow().keymap().commands_for(&mode, &key)
         })
-    });
+    })
+}
+
+fn run_bound_commands(app: &mut Application, commands: Option<SmallVec<[Command; 4]>>) -> Result {
+    let commands = match commands {
+        Some(commands) => commands,
+        None => {
+            app.count = None;
+            return Ok(());
+        }
+    };
 
-    if let Some(coms) = commands {
-        // Run all commands, stopping at the first error encountered, if any.
-        for com in coms {
-            com(app)?;
+    let count = app.count;
+    let mut result = commands.iter().try_for_each(|com| com(app));
+    if app.count == count {
+        app.count = None;
+
+        for _ in 1..count.unwrap_or(1) {
+            if result.is_err() || !matches!(app.mode, Mode::Normal) {
+                break;
+            }
+            result = commands.iter().try_for_each(|com| com(app));
         }
     }
 
-    Ok(())
+    result
 }
 
 pub fn switch_to_normal_mode(app: &mut Application) -> Result {
@@ -36,6 +206,7 @@ pub fn switch_to_normal_mode(app: &mut Application) -> Result {
 pub fn switch_to_insert_mode(app: &mut Application) -> Result {
     if app.workspace.current_buffer().is_some() {
         commands::buffer::start_command_group(app)?;
+        app.auto_pairs.clear();
         app.mode = Mode::Insert;
         commands::view::scroll_to_cursor(app)?;
     } else {
@@ -45,6 +216,62 @@ pub fn switch_to_insert_mode(app: &mut Application) -> Result {
     Ok(())
 }
 
+/// Lists identifiers from the open buffers that could complete the partial
+/// identifier preceding the cursor, in a popup that can be cycled through.
+pub fn switch_to_completion_mode(app: &mut Application) -> Result {
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    if app.cursors.active(buffer) {
+        bail!("Completion isn't available with multiple cursors");
+    }
+
+    let cursor = *buffer.cursor;
+    let data = buffer.data();
+    let line = data.lines().nth(cursor.line).unwrap_or("");
+    let prefix = token::identifier_prefix(line, cursor.offset);
+    if prefix.is_empty() {
+        bail!("No partial identifier before the cursor");
+    }
+    let start = Position{
+        line: cursor.line,
+        offset: cursor.offset - prefix.graphemes(true).count(),
+    };
+
+    let config = app.preferences.borrow().search_select_config();
+    let mut mode = CompletionMode::new(start, workspace_identifiers(&mut app.workspace), config);
+    mode.search(&prefix);
+    if mode.results().len() == 0 {
+        bail!("No completions found");
+    }
+    app.mode = Mode::Completion(mode);
+
+    Ok(())
+}
+
+// Identifiers from every open buffer, starting with the current one,
+// without duplicates. Buffers are visited by cycling through them.
+fn workspace_identifiers(workspace: &mut Workspace) -> Vec<String> {
+    let mut identifiers = Vec::new();
+    let mut seen = HashSet::new();
+    let current_id = workspace.current_buffer().map(|buffer| buffer.id);
+
+    loop {
+        if let Some(buffer) = workspace.current_buffer() {
+            for identifier in token::identifiers(&buffer.data()) {
+                if seen.insert(identifier.to_string()) {
+                    identifiers.push(identifier.to_string());
+                }
+            }
+        }
+
+        workspace.next_buffer();
+        if workspace.current_buffer().map(|buffer| buffer.id) == current_id {
+            break;
+        }
+    }
+
+    identifiers
+}
+
 pub fn switch_to_jump_mode(app: &mut Application) -> Result {
     let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
 
@@ -68,6 +295,11 @@ pub fn switch_to_jump_mode(app: &mut Application) -> Result {
                 mode.select_mode = jump::SelectModeOptions::SelectLine(select_mode);
             }
         }
+        Mode::SelectBlock(select_mode) => {
+            if let Mode::Jump(ref mut mode) = app.mode {
+                mode.select_mode = jump::SelectModeOptions::SelectBlock(select_mode);
+            }
+        }
         _ => (),
     };
 
@@ -95,6 +327,114 @@ pub fn switch_to_line_jump_mode(app: &mut Application) -> Result {
     Ok(())
 }
 
+pub fn switch_to_macro_record_mode(app: &mut Application) -> Result {
+    app.mode = Mode::Macro(MacroMode::new(MacroAction::Record));
+
+    Ok(())
+}
+
+pub fn switch_to_macro_replay_mode(app: &mut Application) -> Result {
+    app.mode = Mode::Macro(MacroMode::new(MacroAction::Replay));
+
+    Ok(())
+}
+
+pub fn switch_to_register_copy_mode(app: &mut Application) -> Result {
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let content = commands::selection::selected_content(buffer, &app.mode)?;
+    app.mode = Mode::Register(RegisterMode::new(RegisterAction::Copy(content)));
+
+    Ok(())
+}
+
+pub fn switch_to_register_paste_mode(app: &mut Application) -> Result {
+    if app.workspace.current_buffer().is_some() {
+        app.mode = Mode::Register(RegisterMode::new(RegisterAction::Paste));
+    } else {
+        bail!(BUFFER_MISSING);
+    }
+
+    Ok(())
+}
+
+/// Prompts for a delimiter pair to wrap the selection in, or
+/// outside of select mode, the token at the cursor.
+pub fn switch_to_surround_add_mode(app: &mut Application) -> Result {
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let range = match app.mode {
+        Mode::Select(ref mode) => Range::new(*buffer.cursor, mode.anchor),
+        _ => util::text_object::token(&buffer.data(), *buffer.cursor, false)
+            .ok_or("No token found at the cursor")?,
+    };
+    app.mode = Mode::Surround(SurroundMode::new(SurroundAction::Add(range)));
+
+    Ok(())
+}
+
+pub fn switch_to_surround_change_mode(app: &mut Application) -> Result {
+    if app.workspace.current_buffer().is_some() {
+        app.mode = Mode::Surround(SurroundMode::new(SurroundAction::Change(None)));
+    } else {
+        bail!(BUFFER_MISSING);
+    }
+
+    Ok(())
+}
+
+pub fn switch_to_surround_delete_mode(app: &mut Application) -> Result {
+    if app.workspace.current_buffer().is_some() {
+        app.mode = Mode::Surround(SurroundMode::new(SurroundAction::Delete));
+    } else {
+        bail!(BUFFER_MISSING);
+    }
+
+    Ok(())
+}
+
+pub fn switch_to_clipboard_history_mode(app: &mut Application) -> Result {
+    if app.workspace.current_buffer().is_none() {
+        bail!(BUFFER_MISSING);
+    }
+
+    let config = app.preferences.borrow().search_select_config();
+    app.mode = Mode::ClipboardHistory(
+        ClipboardHistoryMode::new(app.clipboard.history().cloned().collect(), config)
+    );
+    commands::search_select::search(app)?;
+
+    Ok(())
+}
+
+pub fn switch_to_text_object_inside_mode(app: &mut Application) -> Result {
+    if app.workspace.current_buffer().is_some() {
+        app.mode = Mode::TextObject(TextObjectMode::new(false));
+    } else {
+        bail!(BUFFER_MISSING);
+    }
+
+    Ok(())
+}
+
+pub fn switch_to_text_object_around_mode(app: &mut Application) -> Result {
+    if app.workspace.current_buffer().is_some() {
+        app.mode = Mode::TextObject(TextObjectMode::new(true));
+    } else {
+        bail!(BUFFER_MISSING);
+    }
+
+    Ok(())
+}
+
+pub fn switch_to_text_object_mode(app: &mut Application) -> Result {
+    if app.workspace.current_buffer().is_some() {
+        app.mode = Mode::TextObject(TextObjectMode::pending());
+    } else {
+        bail!(BUFFER_MISSING);
+    }
+
+    Ok(())
+}
+
 pub fn switch_to_open_mode(app: &mut Application) -> Result {
     let exclusions = app.preferences.borrow().open_mode_exclusions()?;
     let config = app.preferences.borrow().search_select_config();
@@ -104,6 +444,15 @@ pub fn switch_to_open_mode(app: &mut Application) -> Result {
     Ok(())
 }
 
+pub fn switch_to_grep_mode(app: &mut Application) -> Result {
+    let exclusions = app.preferences.borrow().open_mode_exclusions()?;
+    let config = app.preferences.borrow().search_select_config();
+    app.mode = Mode::Grep(GrepMode::new(app.workspace.path.clone(), exclusions, app.event_channel.clone(), config));
+    commands::search_select::search(app)?;
+
+    Ok(())
+}
+
 pub fn switch_to_command_mode(app: &mut Application) -> Result {
     let config = app.preferences.borrow().search_select_config();
     app.mode = Mode::Command(CommandMode::new(config));
@@ -160,10 +509,18 @@ pub fn switch_to_select_line_mode(app: &mut Application) -> Result {
     Ok(())
 }
 
+pub fn switch_to_select_block_mode(app: &mut Application) -> Result {
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let tab_width = app.preferences.borrow().tab_width(buffer.path.as_ref());
+    app.mode = Mode::SelectBlock(SelectBlockMode::new(*buffer.cursor, tab_width));
+
+    Ok(())
+}
+
 pub fn switch_to_search_mode(app: &mut Application) -> Result {
     if app.workspace.current_buffer().is_some() {
         app.mode = Mode::Search(
-            SearchMode::new(app.search_query.clone())
+            SearchMode::new(app.search_query.clone(), app.search_options)
         );
     } else {
         bail!(BUFFER_MISSING);
@@ -172,6 +529,29 @@ pub fn switch_to_search_mode(app: &mut Application) -> Result {
     Ok(())
 }
 
+pub fn switch_to_replace_mode(app: &mut Application) -> Result {
+    let mode = mem::replace(&mut app.mode, Mode::Normal);
+
+    if let Mode::Search(mut search_mode) = mode {
+        let has_results = search_mode.results
+            .as_ref()
+            .map(|results| !results.is_empty())
+            .unwrap_or(false);
+        if !has_results {
+            app.mode = Mode::Search(search_mode);
+            bail!(NO_SEARCH_RESULTS);
+        }
+
+        search_mode.insert = false;
+        app.mode = Mode::Replace(ReplaceMode::new(search_mode));
+    } else {
+        app.mode = mode;
+        bail!("Can't replace without search results");
+    }
+
+    Ok(())
+}
+
 pub fn switch_to_path_mode(app: &mut Application) -> Result {
     let path = app.workspace
         .current_buffer()
@@ -209,6 +589,24 @@ pub fn switch_to_syntax_mode(app: &mut Application) -> Result {
     Ok(())
 }
 
+pub fn switch_to_snippet_mode(app: &mut Application) -> Result {
+    let syntax_name = app.workspace
+        .current_buffer()
+        .ok_or(BUFFER_MISSING)?
+        .syntax_definition
+        .as_ref()
+        .map(|syntax| syntax.name.clone())
+        .ok_or("The current buffer doesn't have a syntax")?;
+
+    let config = app.preferences.borrow().search_select_config();
+    app.mode = Mode::Snippet(
+        SnippetMode::new(Snippet::load(&syntax_name)?, config)
+    );
+    commands::search_select::search(app)?;
+
+    Ok(())
+}
+
 pub fn display_default_keymap(app: &mut Application) -> Result {
     commands::workspace::new_buffer(app)?;
 
@@ -280,6 +678,7 @@ pub fn exit(app: &mut Application) -> Result {
 
 #[cfg(test)]
 mod tests {
+    use crate::input::Key;
     use crate::models::Application;
     use crate::models::application::Mode;
     use scribe::Buffer;
@@ -300,7 +699,7 @@ mod tests {
 
         let buffer_data = app.workspace.current_buffer().unwrap().data();
         let mut lines = buffer_data.lines();
-        assert_eq!(lines.nth(0), Some("application::display_available_commands"));
+        assert_eq!(lines.nth(0), Some("align::accept"));
         assert_eq!(lines.last(), Some("workspace::next_buffer"));
     }
 
@@ -373,4 +772,80 @@ mod tests {
 
         assert!(super::switch_to_path_mode(&mut app).is_err());
     }
-}
\ No newline at end of file
+
+    fn press_keys(app: &mut Application, keys: &str) {
+        for c in keys.chars() {
+            app.view.last_key = Some(Key::Char(c));
+            super::handle_input(app).unwrap();
+        }
+    }
+
+    #[test]
+    fn counts_repeat_the_next_command() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n");
+        app.workspace.add_buffer(buffer);
+
+        press_keys(&mut app, "10j");
+        assert_eq!(app.workspace.current_buffer().unwrap().cursor.line, 10);
+        assert_eq!(app.count, None);
+
+        press_keys(&mut app, "2;");
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n");
+    }
+
+    #[test]
+    fn zero_is_only_treated_as_a_count_digit_after_a_count_is_started() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        app.workspace.add_buffer(Buffer::new());
+
+        press_keys(&mut app, "2");
+        assert_eq!(app.count, Some(2));
+        app.view.last_key = Some(Key::Esc);
+        super::handle_input(&mut app).unwrap();
+        assert_eq!(app.count, None);
+
+        press_keys(&mut app, "0");
+        assert!(matches!(app.mode, Mode::Command(_)));
+    }
+
+    #[test]
+    fn counts_are_capped() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        app.workspace.add_buffer(Buffer::new());
+
+        press_keys(&mut app, "99999999999999999999999");
+        assert_eq!(app.count, Some(super::MAX_COUNT));
+    }
+
+    #[test]
+    fn repeat_last_change_replays_insert_mode_sessions_at_the_cursor() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("amp\neditor\n");
+        app.workspace.add_buffer(buffer);
+        app.view.initialize_buffer(app.workspace.current_buffer().unwrap()).unwrap();
+
+        press_keys(&mut app, "cscribe");
+        app.view.last_key = Some(Key::Esc);
+        super::handle_input(&mut app).unwrap();
+        press_keys(&mut app, "jb.");
+
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "scribe\nscribe\n");
+        assert!(matches!(app.mode, Mode::Normal));
+    }
+
+    #[test]
+    fn repeat_last_change_skips_movements_and_undo_and_accepts_a_count() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("amp editor");
+        app.workspace.add_buffer(buffer);
+        app.view.initialize_buffer(app.workspace.current_buffer().unwrap()).unwrap();
+
+        press_keys(&mut app, "xlxuh2.");
+
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), " editor");
+    }
+}
@@ -1,12 +1,20 @@
 use crate::errors::*;
 use crate::commands::{self, Result};
+use rand::Rng;
+use std::cmp::Ordering;
+use std::collections::HashSet;
 use std::mem;
 use crate::input::Key;
 use crate::util;
+use crate::util::columns;
+use crate::util::comments;
+use crate::util::indent::{self, IndentRules};
+use crate::util::number;
 use crate::util::token::{Direction, adjacent_token_position};
-use crate::models::application::{Application, ClipboardContent, Mode};
+use crate::models::application::{Application, AutoPairs, ClipboardContent, Mode};
 use crate::models::application::modes::ConfirmMode;
 use scribe::buffer::{Buffer, Position, Range};
+use unicode_segmentation::UnicodeSegmentation;
 
 pub fn save(app: &mut Application) -> Result {
     remove_trailing_whitespace(app)?;
@@ -23,11 +31,11 @@ pub fn save(app: &mut Application) -> Result {
         .path.is_some();
 
     if path_set {
-        app.workspace
-            .current_buffer()
-            .ok_or(BUFFER_MISSING)?
-            .save()
-            .chain_err(|| "Unable to save buffer")
+        let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+        buffer.save().chain_err(|| "Unable to save buffer")?;
+        app.undo_history.saved(buffer, app.view.buffer_revision(buffer), app.view.take_changed_line(buffer));
+
+        Ok(())
     } else {
         commands::application::switch_to_path_mode(app)?;
         if let Mode::Path(ref mut mode) = app.mode {
@@ -39,9 +47,11 @@ pub fn save(app: &mut Application) -> Result {
 }
 
 pub fn reload(app: &mut Application) -> Result {
-    app.workspace.current_buffer().ok_or(BUFFER_MISSING)?.reload().chain_err(|| {
-        "Unable to reload buffer."
-    })
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    buffer.reload().chain_err(|| "Unable to reload buffer.")?;
+    app.undo_history.reset(buffer);
+
+    Ok(())
 }
 
 pub fn delete(app: &mut Application) -> Result {
@@ -150,6 +160,252 @@ pub fn merge_next_line(app: &mut Application) -> Result {
     Ok(())
 }
 
+/// Moves the current line (or selected lines) above the preceding
+/// line, keeping the cursor and selection on the moved lines.
+pub fn move_lines_up(app: &mut Application) -> Result {
+    let (first, last) = target_lines(app)?;
+    if first == 0 {
+        bail!("No line above the current line");
+    }
+
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let mut lines = read_lines(buffer, first - 1, last);
+    lines.rotate_left(1);
+    replace_lines(buffer, first - 1, last, &lines);
+
+    shift_line_selection(app, -1)
+}
+
+/// Moves the current line (or selected lines) below the following
+/// line, keeping the cursor and selection on the moved lines.
+pub fn move_lines_down(app: &mut Application) -> Result {
+    let (first, last) = target_lines(app)?;
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    if buffer.data().lines().nth(last + 1).is_none() {
+        bail!("No line below the current line");
+    }
+
+    let mut lines = read_lines(buffer, first, last + 1);
+    lines.rotate_right(1);
+    replace_lines(buffer, first, last + 1, &lines);
+
+    shift_line_selection(app, 1)
+}
+
+/// Inserts a copy of the current line (or selected lines) below
+/// it, moving the cursor and selection to the copied lines.
+pub fn duplicate_lines(app: &mut Application) -> Result {
+    let (first, last) = target_lines(app)?;
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let mut lines = read_lines(buffer, first, last);
+    lines.extend(lines.clone());
+    replace_lines(buffer, first, last, &lines);
+
+    shift_line_selection(app, (last - first + 1) as isize)
+}
+
+pub fn sort_lines(app: &mut Application) -> Result {
+    transform_selected_lines(app, |lines| lines.sort())
+}
+
+pub fn sort_lines_case_insensitively(app: &mut Application) -> Result {
+    transform_selected_lines(app, |lines| lines.sort_by_key(|line| line.to_lowercase()))
+}
+
+/// Sorts the selected lines by the number at the start of each
+/// line, placing lines without one before those with numbers.
+pub fn sort_lines_numerically(app: &mut Application) -> Result {
+    transform_selected_lines(app, |lines| {
+        lines.sort_by(|a, b| {
+            leading_number(a).partial_cmp(&leading_number(b)).unwrap_or(Ordering::Equal)
+        })
+    })
+}
+
+/// Removes repeated lines from the selection, keeping the first of each.
+pub fn unique_lines(app: &mut Application) -> Result {
+    transform_selected_lines(app, |lines| {
+        let mut seen = HashSet::new();
+        lines.retain(|line| seen.insert(line.clone()));
+    })
+}
+
+pub fn reverse_lines(app: &mut Application) -> Result {
+    transform_selected_lines(app, |lines| lines.reverse())
+}
+
+pub fn shuffle_lines(app: &mut Application) -> Result {
+    transform_selected_lines(app, |lines| rand::thread_rng().shuffle(lines))
+}
+
+// The first and last lines targeted by line commands: the selected
+// lines in select line mode, or the cursor's line otherwise.
+fn target_lines(app: &mut Application) -> std::result::Result<(usize, usize), Error> {
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let cursor_line = buffer.cursor.line;
+    let (first, last) = match app.mode {
+        Mode::SelectLine(ref mode) => (mode.anchor.min(cursor_line), mode.anchor.max(cursor_line)),
+        _ => (cursor_line, cursor_line),
+    };
+
+    // The empty line following a trailing newline can't be targeted.
+    let line_count = buffer.data().lines().count();
+    if first >= line_count {
+        bail!(CURRENT_LINE_MISSING);
+    }
+
+    Ok((first, last.min(line_count - 1)))
+}
+
+// Applies a transformation to the selected lines as a single operation.
+// If lines are removed, the selection is trimmed to those that remain.
+fn transform_selected_lines<F>(app: &mut Application, transform: F) -> Result
+    where F: FnOnce(&mut Vec<String>)
+{
+    match app.mode {
+        Mode::SelectLine(_) => (),
+        _ => bail!("A line selection is required."),
+    }
+
+    let (first, last) = target_lines(app)?;
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let cursor = *buffer.cursor;
+    let mut lines = read_lines(buffer, first, last);
+    transform(&mut lines);
+    replace_lines(buffer, first, last, &lines);
+
+    let new_last = first + lines.len().max(1) - 1;
+    if cursor.line > new_last {
+        buffer.cursor.move_to(Position{ line: new_last, offset: 0 });
+    }
+    if let Mode::SelectLine(ref mut mode) = app.mode {
+        mode.anchor = mode.anchor.min(new_last);
+    }
+
+    Ok(())
+}
+
+// Moves the cursor and line selection by the specified number of lines.
+fn shift_line_selection(app: &mut Application, distance: isize) -> Result {
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let position = Position {
+        line: (buffer.cursor.line as isize + distance) as usize,
+        offset: buffer.cursor.offset,
+    };
+    buffer.cursor.move_to(position);
+
+    if let Mode::SelectLine(ref mut mode) = app.mode {
+        mode.anchor = (mode.anchor as isize + distance) as usize;
+    }
+
+    commands::view::scroll_to_cursor(app)
+}
+
+// The content of the specified (inclusive) range of lines.
+fn read_lines(buffer: &Buffer, first: usize, last: usize) -> Vec<String> {
+    buffer.data()
+        .lines()
+        .skip(first)
+        .take(last + 1 - first)
+        .map(String::from)
+        .collect()
+}
+
+// Replaces the specified (inclusive) range of lines as a single
+// operation, leaving the cursor on the line it was on.
+fn replace_lines(buffer: &mut Buffer, first: usize, last: usize, lines: &[String]) {
+    let cursor = *buffer.cursor;
+    let last_line_length = buffer.data()
+        .lines()
+        .nth(last)
+        .map(|line| line.graphemes(true).count())
+        .unwrap_or(0);
+
+    buffer.start_operation_group();
+    buffer.delete_range(Range::new(
+        Position{ line: first, offset: 0 },
+        Position{ line: last, offset: last_line_length }
+    ));
+    buffer.cursor.move_to(Position{ line: first, offset: 0 });
+    buffer.insert(lines.join("\n"));
+    buffer.end_operation_group();
+
+    // The cursor's line may have been replaced with a shorter one.
+    if !buffer.cursor.move_to(cursor) {
+        buffer.cursor.move_to(Position{ line: cursor.line, offset: 0 });
+    }
+}
+
+// The number at the start of a line, ignoring leading whitespace.
+fn leading_number(line: &str) -> Option<f64> {
+    let content = line.trim_start();
+    let length = content
+        .char_indices()
+        .take_while(|&(index, c)| {
+            c.is_ascii_digit() || c == '.' || (index == 0 && (c == '-' || c == '+'))
+        })
+        .count();
+
+    content[..length].parse().ok()
+}
+
+/// Adds the count (or one) to the number at or after the cursor. In select
+/// line mode, the first number on each selected line is incremented by a
+/// multiple of the count instead, producing a sequence (e.g. 1, 2, 3).
+pub fn increment_number(app: &mut Application) -> Result {
+    adjust_number(app, 1)
+}
+
+pub fn decrement_number(app: &mut Application) -> Result {
+    adjust_number(app, -1)
+}
+
+// Applies the count as a single operation, rather than being repeated.
+fn adjust_number(app: &mut Application, direction: i128) -> Result {
+    let amount = direction * app.count.take().unwrap_or(1) as i128;
+    let line_selection = matches!(app.mode, Mode::SelectLine(_));
+    let (first, last) = target_lines(app)?;
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let cursor = *buffer.cursor;
+    let offset = if line_selection { 0 } else { cursor.offset };
+
+    let mut replacements = Vec::new();
+    for (index, line) in read_lines(buffer, first, last).iter().enumerate() {
+        if let Some(literal) = number::find(line, offset) {
+            let multiple = replacements.len() as i128 + 1;
+            let content = amount
+                .checked_mul(multiple)
+                .and_then(|amount| literal.add(amount))
+                .ok_or("Number is too large to adjust")?;
+            replacements.push((first + index, literal.start, literal.end, content));
+        }
+    }
+    if replacements.is_empty() {
+        bail!("No number found");
+    }
+
+    buffer.start_operation_group();
+    for &(line, start, end, ref content) in replacements.iter() {
+        buffer.delete_range(Range::new(
+            Position{ line, offset: start },
+            Position{ line, offset: end }
+        ));
+        buffer.cursor.move_to(Position{ line, offset: start });
+        buffer.insert(content.as_str());
+    }
+    buffer.end_operation_group();
+
+    // Leave the cursor on the last character of the updated
+    // number, or where it was, when updating a selection.
+    if line_selection {
+        buffer.cursor.move_to(cursor);
+    } else if let Some(&(line, start, _, ref content)) = replacements.first() {
+        buffer.cursor.move_to(Position{ line, offset: start + content.len() - 1 });
+    }
+
+    commands::view::scroll_to_cursor(app)
+}
+
 pub fn close(app: &mut Application) -> Result {
     // Build confirmation check conditions.
     let (unmodified, empty) =
@@ -166,24 +422,28 @@ pub fn close(app: &mut Application) -> Result {
         };
 
     if unmodified || empty || confirm_mode {
-        // Clean up view-related data for the buffer.
-        app.view.forget_buffer(
-            app.workspace.current_buffer().ok_or(BUFFER_MISSING)?
-        )?;
+        // Clean up view-related data for the buffer, and persist its history.
+        // The buffer is closed regardless of whether its history was saved.
+        let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+        app.view.forget_buffer(buffer)?;
+        let saved = app.undo_history.save(buffer);
         app.workspace.close_current_buffer();
+
+        saved
     } else {
         // Display a confirmation prompt before closing a modified buffer.
         let confirm_mode = ConfirmMode::new(close);
         app.mode = Mode::Confirm(confirm_mode);
-    }
 
-    Ok(())
+        Ok(())
+    }
 }
 
 pub fn close_others(app: &mut Application) -> Result {
     // Get the current buffer's ID so we know what *not* to close.
     let id = app.workspace.current_buffer().map(|b| b.id).ok_or(BUFFER_MISSING)?;
     let mut modified_buffer = false;
+    let mut save_errors = Vec::new();
 
     loop {
         // Try to advance to the next buffer. Handles two important states:
@@ -208,6 +468,9 @@ pub fn close_others(app: &mut Application) -> Result {
                 modified_buffer = true;
             } else {
                 app.view.forget_buffer(buf)?;
+                if let Err(error) = app.undo_history.save(buf) {
+                    save_errors.push(error.to_string());
+                }
             }
         }
 
@@ -223,38 +486,67 @@ pub fn close_others(app: &mut Application) -> Result {
         app.workspace.close_current_buffer();
     }
 
+    // Buffers are closed regardless of whether their histories were saved.
+    if !save_errors.is_empty() {
+        bail!("Failed to save undo history: {}", save_errors.join("; "));
+    }
+
     Ok(())
 }
 
 pub fn close_others_confirm(app: &mut Application) -> Result {
+    let mut saved = Ok(());
     if let Some(buf) = app.workspace.current_buffer() {
         app.view.forget_buffer(buf)?;
+        saved = app.undo_history.save(buf);
     }
     app.workspace.close_current_buffer();
     commands::application::switch_to_normal_mode(app)?;
 
-    Ok(())
+    saved
 }
 
 pub fn backspace(app: &mut Application) -> Result {
     let mut outdent = false;
 
     if let Some(buffer) = app.workspace.current_buffer() {
-        if buffer.cursor.offset == 0 {
+        if app.cursors.active(buffer) {
+            // Cursors are edited in reverse order, which never changes the
+            // content preceding those yet to be visited, so one copy will do.
+            let data = buffer.data();
+            app.cursors.edit(buffer, |_, selection| {
+                preceding_character_range(&data, selection.start())
+                    .map(|range| (range, String::new()))
+            });
+        } else if buffer.cursor.offset == 0 {
             buffer.cursor.move_up();
             buffer.cursor.move_to_end_of_line();
             buffer.delete();
+            app.auto_pairs.clear();
         } else {
             let data = buffer.data();
             let current_line = data
                 .lines()
                 .nth(buffer.cursor.line)
                 .ok_or(CURRENT_LINE_MISSING)?;
+            let pairs = app.preferences.borrow().auto_pairs(buffer.path.as_ref());
+
             if current_line.chars().all(|c| c.is_whitespace()) {
-                outdent = true
+                outdent = true;
+                app.auto_pairs.clear();
+            } else if within_empty_pair(buffer, &pairs) {
+                // Remove both halves of the pair.
+                buffer.cursor.move_left();
+                let start = *buffer.cursor;
+                buffer.delete_range(Range::new(
+                    start,
+                    Position{ line: start.line, offset: start.offset + 2 }
+                ));
+                app.auto_pairs.deleted(start, 2);
             } else {
                 buffer.cursor.move_left();
                 buffer.delete();
+                app.auto_pairs.deleted(*buffer.cursor, 1);
             }
         }
     } else {
@@ -270,9 +562,57 @@ pub fn backspace(app: &mut Application) -> Result {
 pub fn insert_char(app: &mut Application) -> Result {
     if let Some(buffer) = app.workspace.current_buffer() {
         if let Some(Key::Char(character)) = *app.view.last_key() {
-            // TODO: Drop explicit call to to_string().
-            buffer.insert(character.to_string());
-            buffer.cursor.move_right();
+            if app.cursors.active(buffer) {
+                app.cursors.edit(buffer, |_, selection| {
+                    let position = selection.start();
+                    Some((Range::new(position, position), character.to_string()))
+                });
+            } else {
+                let pairs = app.preferences.borrow().auto_pairs(buffer.path.as_ref());
+                let character = character.to_string();
+                let position = *buffer.cursor;
+                let (previous, next) = if pairs.is_empty() {
+                    (None, None)
+                } else {
+                    graphemes_around(buffer, position)
+                };
+                let closing = pairs.iter().any(|(_, close)| *close == character);
+
+                if closing && next == Some(character.clone()) && app.auto_pairs.take(buffer, position) {
+                    // Step over the closing character we inserted earlier.
+                    buffer.cursor.move_right();
+                    return commands::view::scroll_to_cursor(app);
+                } else if let Some((open, close)) = pairs.iter().find(|(open, _)| *open == character) {
+                    let pairable = match next {
+                        Some(ref next) => {
+                            next.trim().is_empty() || pairs.iter().any(|(_, close)| close == next)
+                        },
+                        None => true,
+                    };
+                    let within_word = previous.iter().any(|previous| {
+                        previous.chars().all(|c| c.is_alphanumeric() || c == '_')
+                    });
+
+                    if pairable && !(open == close && within_word) {
+                        buffer.insert(format!("{}{}", open, close));
+                        app.auto_pairs.inserted(position, 2);
+                        buffer.cursor.move_right();
+                        app.auto_pairs.track(buffer, *buffer.cursor);
+                    } else {
+                        buffer.insert(character);
+                        app.auto_pairs.inserted(position, 1);
+                        buffer.cursor.move_right();
+                    }
+                } else {
+                    buffer.insert(character);
+                    app.auto_pairs.inserted(position, 1);
+                    buffer.cursor.move_right();
+                }
+
+                let indent_rules = app.preferences.borrow().indent_rules(buffer.path.as_ref());
+                let tab_content = app.preferences.borrow().tab_content(buffer.path.as_ref());
+                outdent_closing_line(buffer, &mut app.auto_pairs, &indent_rules, &tab_content);
+            }
         } else {
             bail!("No character to insert");
         }
@@ -302,9 +642,43 @@ pub fn display_current_scope(app: &mut Application) -> Result {
 
 /// Inserts a newline character at the current cursor position.
 /// Also performs automatic indentation, basing the indent off
-/// of the previous line's leading whitespace.
+/// of the previous line's leading whitespace and, if one is
+/// configured for the buffer's type, its indent rules.
 pub fn insert_newline(app: &mut Application) -> Result {
     if let Some(buffer) = app.workspace.current_buffer() {
+        let indent_rules = app.preferences.borrow().indent_rules(buffer.path.as_ref());
+        let tab_content = app.preferences.borrow().tab_content(buffer.path.as_ref());
+
+        if app.cursors.active(buffer) {
+            // Cursors are edited in reverse order, which never changes the
+            // content preceding those yet to be visited, so one copy will do.
+            let data = buffer.data();
+            app.cursors.edit(buffer, |_, selection| {
+                let position = selection.start();
+                let indentation = indentation_at(&data, position, &indent_rules, &tab_content);
+                Some((Range::new(position, position), format!("\n{}", indentation)))
+            });
+            return commands::view::scroll_to_cursor(app);
+        }
+
+        // Enter between brackets (or an opening and closing token) opens
+        // an indented block, so check for that before the newline
+        // separates them.
+        let brackets: Vec<(String, String)> = app.preferences
+            .borrow()
+            .auto_pairs(buffer.path.as_ref())
+            .into_iter()
+            .filter(|(open, close)| open != close)
+            .collect();
+        let data = buffer.data();
+        let current_line = data.lines().nth(buffer.cursor.line).unwrap_or("");
+        let line_prefix: String = current_line.graphemes(true).take(buffer.cursor.offset).collect();
+        let line_suffix: String = current_line.graphemes(true).skip(buffer.cursor.offset).collect();
+        let opening_block = within_empty_pair(buffer, &brackets) || (
+            indent_rules.increases_after(&line_prefix) && indent_rules.decreases(&line_suffix)
+        );
+        app.auto_pairs.clear();
+
         // Insert the newline character.
         buffer.insert("\n");
 
@@ -323,11 +697,16 @@ pub fn insert_newline(app: &mut Application) -> Result {
         let offset = end_of_current_line - (data.as_str().as_ptr() as usize);
         let (previous_content, _) = data.split_at(offset);
 
-        // Searching backwards, copy the nearest non-blank line's indent content.
+        // Searching backwards, base the indent content on the nearest non-blank
+        // line, indenting further if it opens a block.
         let nearest_non_blank_line = previous_content.lines().rev().find(|line| !line.is_empty());
-        let indent_content = match nearest_non_blank_line {
-            Some(line) => line.chars().take_while(|&c| c.is_whitespace()).collect(),
-            None => String::new(),
+        let (indent_content, closing_indent_content) = match nearest_non_blank_line {
+            Some(line) if opening_block => (
+                format!("{}{}", indent::leading_whitespace(line), tab_content),
+                indent::leading_whitespace(line).to_string()
+            ),
+            Some(line) => (indent_rules.indentation_after(line, &tab_content), String::new()),
+            None => (String::new(), String::new()),
         };
 
         // Insert and move to the end of the indent content.
@@ -337,6 +716,11 @@ pub fn insert_newline(app: &mut Application) -> Result {
             line: position.line + 1,
             offset: indent_length,
         });
+
+        // Move the closing half of the block to its own line.
+        if opening_block {
+            buffer.insert(format!("\n{}", closing_indent_content));
+        }
     } else {
         bail!(BUFFER_MISSING);
     }
@@ -345,6 +729,117 @@ pub fn insert_newline(app: &mut Application) -> Result {
     Ok(())
 }
 
+// The range covering the character preceding the specified position,
+// which is the previous line's newline when at the start of a line.
+fn preceding_character_range(data: &str, position: Position) -> Option<Range> {
+    let start = if position.offset > 0 {
+        Position{ line: position.line, offset: position.offset - 1 }
+    } else if position.line > 0 {
+        let previous_line = data.lines().nth(position.line - 1)?.graphemes(true).count();
+        Position{ line: position.line - 1, offset: previous_line }
+    } else {
+        return None;
+    };
+
+    Some(Range::new(start, position))
+}
+
+// The graphemes immediately preceding and following the specified
+// position, on the same line, read without copying the whole buffer.
+fn graphemes_around(buffer: &Buffer, position: Position) -> (Option<String>, Option<String>) {
+    let previous = position.offset.checked_sub(1).and_then(|offset| {
+        buffer.read(&Range::new(Position{ line: position.line, offset }, position))
+    });
+    let next = buffer.read(&Range::new(
+        position,
+        Position{ line: position.line, offset: position.offset + 1 }
+    ));
+
+    (previous, next)
+}
+
+// Whether the cursor sits between the opening and closing halves of a pair.
+fn within_empty_pair(buffer: &Buffer, pairs: &[(String, String)]) -> bool {
+    if pairs.is_empty() {
+        return false;
+    }
+
+    match graphemes_around(buffer, *buffer.cursor) {
+        (Some(previous), Some(next)) => {
+            pairs.iter().any(|(open, close)| *open == previous && *close == next)
+        },
+        _ => false,
+    }
+}
+
+// Outdents the cursor's line when the character preceding the cursor
+// completes a closing token (e.g. "}"), as defined by the indent rules.
+fn outdent_closing_line(buffer: &mut Buffer, auto_pairs: &mut AutoPairs, indent_rules: &IndentRules, tab_content: &str) {
+    let line_number = buffer.cursor.line;
+    let start = Position{ line: line_number, offset: 0 };
+    let line = match buffer.read(&Range::new(start, *buffer.cursor)) {
+        Some(line) => line,
+        None => return,
+    };
+
+    // Only outdent when the token is completed, rather than on every
+    // character typed after it, which would continually outdent it.
+    let typed: Vec<&str> = line.graphemes(true).collect();
+    let preceding = typed[..typed.len().saturating_sub(1)].concat();
+    if !indent_rules.decreases(&line) || indent_rules.decreases(&preceding) {
+        return;
+    }
+
+    let previous_line = (0..line_number)
+        .rev()
+        .filter_map(|line_number| read_line(buffer, line_number))
+        .find(|line| !line.trim().is_empty());
+    let indentation = match previous_line {
+        Some(previous_line) => indent_rules.closing_indentation(&line, &previous_line, tab_content),
+        None => None,
+    };
+    if let Some(indentation) = indentation {
+        let current_length = indent::leading_whitespace(&line).graphemes(true).count();
+        let length = indentation.graphemes(true).count();
+        let offset = buffer.cursor.offset + length - current_length;
+
+        buffer.delete_range(Range::new(start, Position{ line: line_number, offset: current_length }));
+        buffer.cursor.move_to(start);
+        buffer.insert(indentation);
+        buffer.cursor.move_to(Position{ line: line_number, offset });
+        auto_pairs.deleted(start, current_length);
+        auto_pairs.inserted(start, length);
+    }
+}
+
+// Reads a line that's followed by another, without its newline.
+fn read_line(buffer: &Buffer, line: usize) -> Option<String> {
+    let range = Range::new(Position{ line, offset: 0 }, Position{ line: line + 1, offset: 0 });
+
+    buffer.read(&range).map(|line| line.trim_end_matches(&['\r', '\n'][..]).to_string())
+}
+
+// The indentation for a line inserted at the specified position, based on
+// the nearest non-blank line at or above it (considering only the content
+// that precedes the position) and the indent rules.
+fn indentation_at(data: &str, position: Position, indent_rules: &IndentRules, tab_content: &str) -> String {
+    let mut lines: Vec<String> = data
+        .lines()
+        .take(position.line + 1)
+        .map(|line| line.to_string())
+        .collect();
+    if let Some(line) = lines.get_mut(position.line) {
+        *line = line.graphemes(true).take(position.offset).collect();
+    }
+
+    lines
+        .iter()
+        .rev()
+        .find(|line| !line.is_empty())
+        .map(|line| indent_rules.indentation_after(line, tab_content))
+        .unwrap_or_default()
+}
+
 pub fn indent_line(app: &mut Application) -> Result {
     let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
     let tab_content = app.preferences.borrow().tab_content(buffer.path.as_ref());
@@ -471,9 +966,13 @@ pub fn toggle_line_comment(app: &mut Application) -> Result {
     let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
     let original_cursor = *buffer.cursor.clone();
 
+    // Fall back to the syntax definition's prefix if one isn't configured.
     let comment_prefix = {
-        let path = buffer.path.as_ref().ok_or(BUFFER_PATH_MISSING)?;
-        let prefix = app.preferences.borrow().line_comment_prefix(path)
+        let preferences = app.preferences.borrow();
+        let prefix = buffer.path
+            .as_ref()
+            .and_then(|path| preferences.line_comment_prefix(path))
+            .or_else(|| buffer.syntax_definition.as_ref().and_then(comments::line_comment_prefix))
             .ok_or("No line comment prefix for the current buffer")?;
 
         prefix + " " // implicitly add trailing space
@@ -489,6 +988,13 @@ pub fn toggle_line_comment(app: &mut Application) -> Result {
                 mode.anchor..buffer.cursor.line + 1
             }
         }
+        Mode::Select(ref mode) => {
+            if mode.anchor.line >= buffer.cursor.line {
+                buffer.cursor.line..mode.anchor.line + 1
+            } else {
+                mode.anchor.line..buffer.cursor.line + 1
+            }
+        }
         _ => buffer.cursor.line..buffer.cursor.line + 1,
     };
 
@@ -563,6 +1069,75 @@ fn remove_line_comment(buffer: &mut Buffer, lines: &[(usize, &str)], prefix: &st
     }
 }
 
+/// Wraps the selection in block comment delimiters, or removes them if it's
+/// already wrapped in them. In select line mode, whole lines are wrapped.
+pub fn toggle_block_comment(app: &mut Application) -> Result {
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let original_cursor = *buffer.cursor;
+
+    // Fall back to the syntax definition's delimiters if they aren't configured.
+    let (start, end) = {
+        let preferences = app.preferences.borrow();
+        buffer.path
+            .as_ref()
+            .and_then(|path| preferences.block_comment_delimiters(path))
+            .or_else(|| buffer.syntax_definition.as_ref().and_then(comments::block_comment_delimiters))
+            .ok_or("No block comment delimiters for the current buffer")?
+    };
+
+    let range = match app.mode {
+        Mode::Select(ref mode) => Range::new(mode.anchor, original_cursor),
+        Mode::SelectLine(ref mode) => {
+            let first_line = mode.anchor.min(original_cursor.line);
+            let last_line = mode.anchor.max(original_cursor.line);
+            let last_line_length = buffer.data()
+                .lines()
+                .nth(last_line)
+                .map(|line| line.graphemes(true).count())
+                .unwrap_or(0);
+
+            Range::new(
+                Position{ line: first_line, offset: 0 },
+                Position{ line: last_line, offset: last_line_length }
+            )
+        }
+        _ => bail!("A selection is required."),
+    };
+    let content = buffer.read(&range).ok_or("Couldn't read selected data from buffer")?;
+
+    // Leave any surrounding whitespace (e.g. indentation) outside of the comment.
+    let leading_whitespace = indent::leading_whitespace(&content);
+    let commented_content = content.trim();
+    let trailing_whitespace = &content[leading_whitespace.len() + commented_content.len()..];
+    let commented = commented_content.len() >= start.len() + end.len() &&
+        commented_content.starts_with(&start) &&
+        commented_content.ends_with(&end);
+
+    let replacement = if commented {
+        let inner = &commented_content[start.len()..commented_content.len() - end.len()];
+        let inner = inner.strip_prefix(' ').unwrap_or(inner);
+        let inner = inner.strip_suffix(' ').unwrap_or(inner);
+
+        format!("{}{}{}", leading_whitespace, inner, trailing_whitespace)
+    } else {
+        format!("{}{} {} {}{}", leading_whitespace, start, commented_content, end, trailing_whitespace)
+    };
+
+    // Replace the selection as a single operation.
+    buffer.start_operation_group();
+    buffer.delete_range(range.clone());
+    buffer.cursor.move_to(range.start());
+    buffer.insert(replacement);
+    buffer.end_operation_group();
+
+    // Lines are preserved, so keep the line selection intact.
+    if let Mode::SelectLine(_) = app.mode {
+        buffer.cursor.move_to(original_cursor);
+    }
+
+    Ok(())
+}
+
 pub fn change_token(app: &mut Application) -> Result {
     commands::buffer::delete_token(app)?;
     commands::application::switch_to_insert_mode(app)?;
@@ -577,12 +1152,19 @@ pub fn delete_rest_of_line(app: &mut Application) -> Result {
     // cursor's current position to the next line.
     let starting_position = *buffer.cursor;
     let target_line = buffer.cursor.line + 1;
+    let range = Range::new(starting_position,
+                           Position {
+                               line: target_line,
+                               offset: 0,
+                           });
+
+    // The deleted content isn't copied, but is kept in the clipboard history.
+    if let Some(content) = buffer.read(&range) {
+        app.clipboard.record(ClipboardContent::Inline(content.trim_end_matches('\n').to_string()));
+    }
+
     buffer.start_operation_group();
-    buffer.delete_range(Range::new(starting_position,
-                                   Position {
-                                       line: target_line,
-                                       offset: 0,
-                                   }));
+    buffer.delete_range(range);
 
     // Since we've removed a newline as part of the range, re-add it.
     buffer.insert("\n");
@@ -630,8 +1212,17 @@ pub fn redo(app: &mut Application) -> Result {
 }
 
 pub fn paste(app: &mut Application) -> Result {
+    let content = app.clipboard.get_content().clone();
+    paste_content(app, &content)
+}
+
+/// Pastes the specified content in place of the current selection,
+/// if there is one. Otherwise, inline content is inserted at the cursor,
+/// block content is inserted on the line below it, and rectangle content
+/// is inserted at the cursor's column on it and the lines that follow.
+pub fn paste_content(app: &mut Application, content: &ClipboardContent) -> Result {
     let insert_below = match app.mode {
-        Mode::Select(_) | Mode::SelectLine(_) | Mode::Search(_) => {
+        Mode::Select(_) | Mode::SelectLine(_) | Mode::SelectBlock(_) | Mode::Search(_) => {
             commands::selection::delete(app).chain_err(|| {
                 "Couldn't delete selection prior to pasting."
             })?;
@@ -642,7 +1233,7 @@ pub fn paste(app: &mut Application) -> Result {
 
     // TODO: Clean up duplicate buffer.insert(content.clone()) calls.
     if let Some(buffer) = app.workspace.current_buffer() {
-        match *app.clipboard.get_content() {
+        match *content {
             ClipboardContent::Inline(ref content) => buffer.insert(content.clone()),
             ClipboardContent::Block(ref content) => {
                 let original_cursor_position = *buffer.cursor.clone();
@@ -676,6 +1267,10 @@ pub fn paste(app: &mut Application) -> Result {
                     buffer.insert(content.clone());
                 }
             }
+            ClipboardContent::Rectangle(ref content) => {
+                let tab_width = app.preferences.borrow().tab_width(buffer.path.as_ref());
+                paste_rectangle(buffer, content, tab_width);
+            }
             ClipboardContent::None => (),
         }
     } else {
@@ -686,6 +1281,43 @@ pub fn paste(app: &mut Application) -> Result {
     Ok(())
 }
 
+// Inserts each line of a rectangle at the cursor's column, starting on its
+// line. Lines ending before that column are padded with spaces to reach it,
+// and lines are added to the end of the buffer to fit the rectangle, if need be.
+fn paste_rectangle(buffer: &mut Buffer, content: &str, tab_width: usize) {
+    let cursor = *buffer.cursor;
+    let data = buffer.data();
+    let lines: Vec<&str> = data.split('\n').collect();
+    let column = columns::column(lines.get(cursor.line).cloned().unwrap_or(""), cursor.offset, tab_width);
+
+    buffer.start_operation_group();
+    let mut added_lines = String::new();
+    for (index, rectangle_line) in content.split('\n').enumerate() {
+        match lines.get(cursor.line + index) {
+            Some(line) if !rectangle_line.is_empty() => {
+                let (offset, padding) = columns::insertion_point(line, column, tab_width);
+                buffer.cursor.move_to(Position{ line: cursor.line + index, offset });
+                buffer.insert(format!("{}{}", " ".repeat(padding), rectangle_line));
+            },
+            Some(_) => (),
+            None if rectangle_line.is_empty() => added_lines.push('\n'),
+            None => added_lines.push_str(&format!("\n{}{}", " ".repeat(column), rectangle_line)),
+        }
+    }
+    if !added_lines.is_empty() {
+        let data = buffer.data();
+        let last_line = data.rsplit('\n').next().unwrap_or("");
+        buffer.cursor.move_to(Position{
+            line: lines.len() - 1,
+            offset: last_line.graphemes(true).count(),
+        });
+        buffer.insert(added_lines);
+    }
+    buffer.end_operation_group();
+
+    buffer.cursor.move_to(cursor);
+}
+
 pub fn paste_above(app: &mut Application) -> Result {
     let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
 
@@ -818,10 +1450,15 @@ pub fn insert_tab(app: &mut Application) -> Result {
 mod tests {
     use crate::commands;
     use crate::models::Application;
-    use crate::models::application::{ClipboardContent, Mode};
+    use crate::input::Key;
+    use crate::models::application::{ClipboardContent, Mode, Preferences, UndoHistory};
+    use crate::models::application::modes::SelectMode;
+    use crate::yaml::yaml::YamlLoader;
     use scribe::Buffer;
     use scribe::buffer::Position;
-    use std::path::Path;
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
 
     #[test]
     fn insert_newline_uses_current_line_indentation() {
@@ -1630,6 +2267,22 @@ mod tests {
                    original_position);
     }
 
+    #[test]
+    fn delete_rest_of_line_records_deleted_content_in_clipboard_history() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("amp editor\nscribe");
+        buffer.cursor.move_to(Position{ line: 0, offset: 3 });
+        app.workspace.add_buffer(buffer);
+        commands::buffer::delete_rest_of_line(&mut app).unwrap();
+
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "amp\nscribe");
+        assert_eq!(
+            app.clipboard.history().next(),
+            Some(&ClipboardContent::Inline(" editor".to_string()))
+        );
+    }
+
     #[test]
     fn close_displays_confirmation_when_buffer_is_modified() {
         let mut app = Application::new(&Vec::new()).unwrap();
@@ -1679,6 +2332,32 @@ mod tests {
         assert!(app.workspace.current_buffer().is_none());
     }
 
+    #[test]
+    fn close_reports_undo_history_errors_after_closing_the_buffer() {
+        let directory = env::temp_dir().join("amp-close-undo-history");
+        fs::create_dir_all(&directory).unwrap();
+        let file_path = directory.join("file.txt");
+        fs::write(&file_path, "amp").unwrap();
+
+        // Histories can't be written to a directory that doesn't exist.
+        let mut app = Application::new(&Vec::new()).unwrap();
+        app.undo_history = UndoHistory::new(Some(directory.join("missing")));
+        app.workspace.close_current_buffer();
+        app.workspace.open_buffer(&file_path).unwrap();
+        {
+            let buffer = app.workspace.current_buffer().unwrap();
+            app.undo_history.restore(buffer).unwrap();
+            buffer.insert("the ");
+            buffer.save().unwrap();
+            app.undo_history.saved(buffer, None, None);
+        }
+
+        assert!(commands::buffer::close(&mut app).is_err());
+        assert!(app.workspace.current_buffer().is_none());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
     #[test]
     fn close_others_skips_confirmation_when_all_other_buffers_are_empty_or_unmodified() {
         let mut app = Application::new(&Vec::new()).unwrap();
@@ -1828,6 +2507,24 @@ mod tests {
                    Position { line: 1, offset: 1 });
     }
 
+    #[test]
+    fn toggle_line_comment_add_multiple_in_select_mode() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("amp\neditor\ntext\n");
+        buffer.cursor.move_to(Position { line: 1, offset: 3 });
+        buffer.path = Some("test.rs".into());
+
+        app.workspace.add_buffer(buffer);
+        commands::application::switch_to_select_mode(&mut app).unwrap();
+        app.workspace.current_buffer().unwrap().cursor.move_to(Position { line: 0, offset: 1 });
+
+        super::toggle_line_comment(&mut app).unwrap();
+
+        assert_eq!(app.workspace.current_buffer().unwrap().data(),
+                   "// amp\n// editor\ntext\n");
+    }
+
     #[test]
     fn toggle_line_comment_remove_single_in_normal_mode() {
         let mut app = Application::new(&Vec::new()).unwrap();
@@ -1961,4 +2658,353 @@ mod tests {
         assert_eq!(app.workspace.current_buffer().unwrap().cursor.position,
                    Position { line: 2, offset: 0 });
     }
+
+    #[test]
+    fn toggle_line_comment_falls_back_to_the_syntax_definition_prefix() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("amp\n");
+        buffer.path = Some("test.java".into());
+
+        app.workspace.add_buffer(buffer);
+        super::toggle_line_comment(&mut app).unwrap();
+
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "// amp\n");
+    }
+
+    #[test]
+    fn toggle_block_comment_wraps_and_unwraps_the_selection() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("a b c");
+        buffer.path = Some("test.css".into());
+        app.workspace.add_buffer(buffer);
+
+        app.mode = Mode::Select(SelectMode::new(Position{ line: 0, offset: 2 }));
+        app.workspace.current_buffer().unwrap().cursor.move_to(Position{ line: 0, offset: 3 });
+        super::toggle_block_comment(&mut app).unwrap();
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "a /* b */ c");
+
+        app.workspace.current_buffer().unwrap().cursor.move_to(Position{ line: 0, offset: 9 });
+        super::toggle_block_comment(&mut app).unwrap();
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "a b c");
+    }
+
+    #[test]
+    fn toggle_block_comment_wraps_whole_lines_in_select_line_mode() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("  a\n  b\nc\n");
+        buffer.path = Some("test.html".into());
+        app.workspace.add_buffer(buffer);
+
+        commands::application::switch_to_select_line_mode(&mut app).unwrap();
+        app.workspace.current_buffer().unwrap().cursor.move_to(Position{ line: 1, offset: 0 });
+        super::toggle_block_comment(&mut app).unwrap();
+
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "  <!-- a\n  b -->\nc\n");
+        assert_eq!(*app.workspace.current_buffer().unwrap().cursor, Position{ line: 1, offset: 0 });
+    }
+
+    #[test]
+    fn move_lines_keeps_the_selection_on_the_moved_lines() {
+        let mut app = line_selection_app("a\nb\nc\nd\n", 0, 1);
+
+        super::move_lines_down(&mut app).unwrap();
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "c\na\nb\nd\n");
+        assert_eq!(app.workspace.current_buffer().unwrap().cursor.line, 2);
+        match app.mode {
+            Mode::SelectLine(ref mode) => assert_eq!(mode.anchor, 1),
+            _ => panic!("Not in select line mode"),
+        }
+
+        super::move_lines_up(&mut app).unwrap();
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "a\nb\nc\nd\n");
+        assert_eq!(app.workspace.current_buffer().unwrap().cursor.line, 1);
+        assert!(super::move_lines_up(&mut app).is_err());
+    }
+
+    #[test]
+    fn duplicate_lines_moves_the_cursor_to_the_copy() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("amp\neditor");
+        buffer.cursor.move_to(Position{ line: 0, offset: 1 });
+        app.workspace.add_buffer(buffer);
+
+        super::duplicate_lines(&mut app).unwrap();
+
+        let buffer = app.workspace.current_buffer().unwrap();
+        assert_eq!(buffer.data(), "amp\namp\neditor");
+        assert_eq!(*buffer.cursor, Position{ line: 1, offset: 1 });
+    }
+
+    #[test]
+    fn sort_lines_variants_sort_the_selection_as_a_single_step() {
+        let mut app = line_selection_app("b\n10\nC\n9\na\nz", 0, 4);
+
+        super::sort_lines(&mut app).unwrap();
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "10\n9\nC\na\nb\nz");
+
+        super::sort_lines_case_insensitively(&mut app).unwrap();
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "10\n9\na\nb\nC\nz");
+
+        super::sort_lines_numerically(&mut app).unwrap();
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "a\nb\nC\n9\n10\nz");
+
+        app.workspace.current_buffer().unwrap().undo();
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "10\n9\na\nb\nC\nz");
+    }
+
+    #[test]
+    fn unique_lines_trims_the_selection_to_the_remaining_lines() {
+        let mut app = line_selection_app("a\nb\na\nb\nc", 0, 3);
+
+        super::unique_lines(&mut app).unwrap();
+
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "a\nb\nc");
+        assert_eq!(app.workspace.current_buffer().unwrap().cursor.line, 1);
+    }
+
+    #[test]
+    fn reverse_lines_requires_a_line_selection() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("a\nb");
+        app.workspace.add_buffer(buffer);
+
+        assert!(super::reverse_lines(&mut app).is_err());
+    }
+
+    #[test]
+    fn paste_content_aligns_rectangles_with_the_cursor_column() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("ab\n\tc\n");
+        buffer.cursor.move_to(Position{ line: 0, offset: 1 });
+        app.workspace.add_buffer(buffer);
+
+        let content = ClipboardContent::Rectangle(String::from("12\n34\n56\n78"));
+        super::paste_content(&mut app, &content).unwrap();
+
+        let buffer = app.workspace.current_buffer().unwrap();
+        assert_eq!(buffer.data(), "a12b\n\t34c\n 56\n 78");
+        assert_eq!(*buffer.cursor, Position{ line: 0, offset: 1 });
+    }
+
+    #[test]
+    fn increment_number_adds_the_count_to_the_next_number_on_the_line() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("let amp = 0x09;");
+        app.workspace.add_buffer(buffer);
+        app.count = Some(7);
+
+        super::increment_number(&mut app).unwrap();
+
+        let buffer = app.workspace.current_buffer().unwrap();
+        assert_eq!(buffer.data(), "let amp = 0x10;");
+        assert_eq!(*buffer.cursor, Position{ line: 0, offset: 13 });
+        assert!(app.count.is_none());
+    }
+
+    #[test]
+    fn increment_number_produces_a_sequence_across_selected_lines() {
+        let mut app = line_selection_app("a 0\nb\nc 0\nd 0", 0, 3);
+
+        super::increment_number(&mut app).unwrap();
+
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "a 1\nb\nc 2\nd 3");
+    }
+
+    #[test]
+    fn decrement_number_fails_without_a_number() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("amp 1\namp");
+        buffer.cursor.move_to(Position{ line: 1, offset: 0 });
+        app.workspace.add_buffer(buffer);
+
+        assert!(super::decrement_number(&mut app).is_err());
+    }
+
+    #[test]
+    fn increment_number_leaves_numbers_that_overflow_unchanged() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("amp 999999999999999999999999999999999999999999");
+        app.workspace.add_buffer(buffer);
+
+        assert!(super::increment_number(&mut app).is_err());
+        assert_eq!(
+            app.workspace.current_buffer().unwrap().data(),
+            "amp 999999999999999999999999999999999999999999"
+        );
+    }
+
+    // An application selecting lines from the anchor to the cursor line.
+    fn line_selection_app(data: &str, anchor: usize, cursor_line: usize) -> Application {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert(data);
+        buffer.cursor.move_to(Position{ line: anchor, offset: 0 });
+        app.workspace.add_buffer(buffer);
+        commands::application::switch_to_select_line_mode(&mut app).unwrap();
+        app.workspace.current_buffer().unwrap().cursor.move_to(Position{ line: cursor_line, offset: 0 });
+
+        app
+    }
+
+    #[test]
+    fn insert_char_auto_pairs_brackets_and_steps_over_their_closers() {
+        let mut app = auto_pairing_app("");
+
+        for character in "(a)".chars() {
+            app.view.last_key = Some(Key::Char(character));
+            super::insert_char(&mut app).unwrap();
+        }
+
+        let buffer = app.workspace.current_buffer().unwrap();
+        assert_eq!(buffer.data(), "(a)");
+        assert_eq!(*buffer.cursor, Position{ line: 0, offset: 3 });
+    }
+
+    #[test]
+    fn insert_char_does_not_pair_quotes_following_words() {
+        let mut app = auto_pairing_app("don");
+
+        app.view.last_key = Some(Key::Char('\''));
+        super::insert_char(&mut app).unwrap();
+
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "don'");
+    }
+
+    #[test]
+    fn insert_char_only_pairs_when_enabled() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        app.workspace.add_buffer(Buffer::new());
+
+        app.view.last_key = Some(Key::Char('('));
+        super::insert_char(&mut app).unwrap();
+
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "(");
+    }
+
+    #[test]
+    fn graphemes_around_only_reads_the_position_line() {
+        let mut buffer = Buffer::new();
+        buffer.insert("a日\nb");
+        let around = |line, offset| super::graphemes_around(&buffer, Position{ line, offset });
+
+        assert_eq!(around(0, 0), (None, Some(String::from("a"))));
+        assert_eq!(around(0, 2), (Some(String::from("日")), None));
+        assert_eq!(around(1, 1), (Some(String::from("b")), None));
+    }
+
+    #[test]
+    fn backspace_removes_both_characters_of_an_empty_pair() {
+        let mut app = auto_pairing_app("a[]");
+        app.workspace.current_buffer().unwrap().cursor.move_to(Position{ line: 0, offset: 2 });
+
+        super::backspace(&mut app).unwrap();
+
+        let buffer = app.workspace.current_buffer().unwrap();
+        assert_eq!(buffer.data(), "a");
+        assert_eq!(*buffer.cursor, Position{ line: 0, offset: 1 });
+    }
+
+    #[test]
+    fn insert_newline_between_braces_opens_an_indented_block() {
+        let mut app = auto_pairing_app("  a {}");
+        app.workspace.current_buffer().unwrap().cursor.move_to(Position{ line: 0, offset: 5 });
+
+        super::insert_newline(&mut app).unwrap();
+
+        let buffer = app.workspace.current_buffer().unwrap();
+        assert_eq!(buffer.data(), "  a {\n    \n  }");
+        assert_eq!(*buffer.cursor, Position{ line: 1, offset: 4 });
+    }
+
+    #[test]
+    fn insert_newline_indents_lines_following_block_openers() {
+        let mut app = rust_app("fn a() {");
+
+        super::insert_newline(&mut app).unwrap();
+
+        let buffer = app.workspace.current_buffer().unwrap();
+        assert_eq!(buffer.data(), "fn a() {\n  ");
+        assert_eq!(*buffer.cursor, Position{ line: 1, offset: 2 });
+    }
+
+    #[test]
+    fn insert_newline_between_block_tokens_opens_an_indented_block() {
+        let mut app = rust_app("a {}");
+        app.workspace.current_buffer().unwrap().cursor.move_to(Position{ line: 0, offset: 3 });
+
+        super::insert_newline(&mut app).unwrap();
+
+        let buffer = app.workspace.current_buffer().unwrap();
+        assert_eq!(buffer.data(), "a {\n  \n}");
+        assert_eq!(*buffer.cursor, Position{ line: 1, offset: 2 });
+    }
+
+    #[test]
+    fn insert_char_outdents_closing_tokens_typed_at_the_start_of_a_line() {
+        let mut app = rust_app("a {\n  b\n  ");
+        app.workspace.current_buffer().unwrap().cursor.move_to(Position{ line: 2, offset: 2 });
+
+        for character in "};".chars() {
+            app.view.last_key = Some(Key::Char(character));
+            super::insert_char(&mut app).unwrap();
+        }
+
+        let buffer = app.workspace.current_buffer().unwrap();
+        assert_eq!(buffer.data(), "a {\n  b\n};");
+        assert_eq!(*buffer.cursor, Position{ line: 2, offset: 2 });
+    }
+
+    #[test]
+    fn insert_char_outdents_closing_tokens_after_blank_lines() {
+        let mut app = rust_app("a {\n  b\n\n  ");
+        app.workspace.current_buffer().unwrap().cursor.move_to(Position{ line: 3, offset: 2 });
+        app.view.last_key = Some(Key::Char('}'));
+        super::insert_char(&mut app).unwrap();
+
+        let buffer = app.workspace.current_buffer().unwrap();
+        assert_eq!(buffer.data(), "a {\n  b\n\n}");
+        assert_eq!(*buffer.cursor, Position{ line: 3, offset: 1 });
+    }
+
+    #[test]
+    fn invalid_indent_patterns_fall_back_to_plain_indentation() {
+        let mut app = rust_app("  a {");
+        let preferences = YamlLoader::load_from_str("types:\n  rs:\n    increase_indent_pattern: '('").unwrap();
+        *app.preferences.borrow_mut() = Preferences::new(preferences.into_iter().next());
+
+        super::insert_newline(&mut app).unwrap();
+        app.view.last_key = Some(Key::Char('}'));
+        super::insert_char(&mut app).unwrap();
+
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "  a {\n  }");
+    }
+
+    // An application editing a Rust buffer (which has default
+    // indent rules) with the specified data and its cursor at the end.
+    fn rust_app(data: &str) -> Application {
+        let mut app = commands::app_with_buffer(data, Position{ line: 0, offset: data.len() });
+        app.workspace.current_buffer().unwrap().path = Some(PathBuf::from("amp.rs"));
+        app.workspace.update_current_syntax().unwrap();
+
+        app
+    }
+
+    // An application with auto-pairing enabled, editing a
+    // buffer with the specified data and its cursor at the end.
+    fn auto_pairing_app(data: &str) -> Application {
+        let app = commands::app_with_buffer(data, Position{ line: 0, offset: data.len() });
+        let preferences = YamlLoader::load_from_str("auto_pairs: true").unwrap();
+        *app.preferences.borrow_mut() = Preferences::new(preferences.into_iter().next());
+
+        app
+    }
 }
@@ -0,0 +1,85 @@
+use crate::errors::*;
+use crate::commands::{self, Result};
+use crate::models::application::{Application, Mode};
+use crate::util;
+use crate::util::case::{self, Case};
+use scribe::buffer::{LineRange, Range};
+
+pub fn upper_case(app: &mut Application) -> Result {
+    convert(app, Case::Upper)
+}
+
+pub fn lower_case(app: &mut Application) -> Result {
+    convert(app, Case::Lower)
+}
+
+pub fn title_case(app: &mut Application) -> Result {
+    convert(app, Case::Title)
+}
+
+pub fn snake_case(app: &mut Application) -> Result {
+    convert(app, Case::Snake)
+}
+
+pub fn camel_case(app: &mut Application) -> Result {
+    convert(app, Case::Camel)
+}
+
+pub fn pascal_case(app: &mut Application) -> Result {
+    convert(app, Case::Pascal)
+}
+
+pub fn kebab_case(app: &mut Application) -> Result {
+    convert(app, Case::Kebab)
+}
+
+// Converts the selection (or the token at the cursor, outside of select
+// modes) as a single operation, leaving the cursor at the start of it.
+fn convert(app: &mut Application, case: Case) -> Result {
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let range = match app.mode {
+        Mode::Select(ref mode) => Range::new(mode.anchor, *buffer.cursor),
+        Mode::SelectLine(ref mode) => {
+            util::inclusive_range(&LineRange::new(mode.anchor, buffer.cursor.line), buffer)
+        },
+        _ => {
+            util::token::identifier_at(&buffer.data(), *buffer.cursor)
+                .ok_or("No token found at the cursor")?
+        },
+    };
+    let content = buffer.read(&range).ok_or("Couldn't read the text to convert")?;
+
+    buffer.start_operation_group();
+    buffer.delete_range(range.clone());
+    buffer.cursor.move_to(range.start());
+    buffer.insert(case::convert(&content, case));
+    buffer.end_operation_group();
+
+    commands::view::scroll_to_cursor(app)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::app_with_buffer;
+    use crate::models::application::Mode;
+    use crate::models::application::modes::SelectMode;
+    use scribe::buffer::Position;
+
+    #[test]
+    fn commands_convert_the_token_at_the_cursor_outside_of_select_modes() {
+        let mut app = app_with_buffer("let amp_editor = 1;", Position{ line: 0, offset: 8 });
+        super::camel_case(&mut app).unwrap();
+
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "let ampEditor = 1;");
+        assert_eq!(*app.workspace.current_buffer().unwrap().cursor, Position{ line: 0, offset: 4 });
+    }
+
+    #[test]
+    fn commands_convert_the_selection_in_select_mode() {
+        let mut app = app_with_buffer("ampEditor scribeBuffer", Position{ line: 0, offset: 22 });
+        app.mode = Mode::Select(SelectMode::new(Position{ line: 0, offset: 0 }));
+        super::kebab_case(&mut app).unwrap();
+
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "amp-editor scribe-buffer");
+    }
+}
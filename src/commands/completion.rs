@@ -0,0 +1,150 @@
+use crate::errors::*;
+use crate::commands::{self, Result};
+use crate::models::application::{Application, Mode};
+use crate::util::token;
+use scribe::buffer::{Position, Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+pub fn select_next(app: &mut Application) -> Result {
+    if let Mode::Completion(ref mut mode) = app.mode {
+        mode.select_next();
+    }
+
+    Ok(())
+}
+
+pub fn select_previous(app: &mut Application) -> Result {
+    if let Mode::Completion(ref mut mode) = app.mode {
+        mode.select_previous();
+    }
+
+    Ok(())
+}
+
+/// Replaces the partial identifier with the selected completion,
+/// and returns to insert mode, with the cursor following it.
+pub fn accept(app: &mut Application) -> Result {
+    let (start, completion) = match app.mode {
+        Mode::Completion(ref mode) => {
+            (mode.start, mode.selection().cloned().ok_or("No completion selected")?)
+        },
+        _ => bail!("Can't accept a completion outside of completion mode"),
+    };
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+
+    // This is part of the insert mode command group, so it isn't grouped here.
+    buffer.delete_range(Range::new(start, *buffer.cursor));
+    buffer.cursor.move_to(start);
+    buffer.insert(completion.as_str());
+    buffer.cursor.move_to(Position{
+        line: start.line,
+        offset: start.offset + completion.graphemes(true).count(),
+    });
+    app.mode = Mode::Insert;
+
+    commands::view::scroll_to_cursor(app)
+}
+
+/// Returns to insert mode without completing the partial identifier. This
+/// doesn't use application::switch_to_insert_mode, which would otherwise
+/// start a new command group, splitting the insertion's undo history.
+pub fn cancel(app: &mut Application) -> Result {
+    app.mode = Mode::Insert;
+
+    Ok(())
+}
+
+pub fn insert_char(app: &mut Application) -> Result {
+    commands::buffer::insert_char(app)?;
+    refresh(app)
+}
+
+pub fn backspace(app: &mut Application) -> Result {
+    commands::buffer::backspace(app)?;
+    refresh(app)
+}
+
+// Updates the completions using the partial identifier, returning to insert
+// mode if it's been removed or completed, or if there are no completions left.
+fn refresh(app: &mut Application) -> Result {
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let cursor = *buffer.cursor;
+    let completing = match app.mode {
+        Mode::Completion(ref mut mode) => {
+            let data = buffer.data();
+            let line = data.lines().nth(cursor.line).unwrap_or("");
+            let prefix = token::identifier_prefix(line, cursor.offset);
+            let prefix_length = prefix.graphemes(true).count();
+
+            if cursor.line == mode.start.line && cursor.offset == mode.start.offset + prefix_length && prefix_length > 0 {
+                mode.search(&prefix);
+                mode.results().len() > 0
+            } else {
+                false
+            }
+        },
+        _ => false,
+    };
+
+    if !completing {
+        app.mode = Mode::Insert;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands;
+    use crate::input::Key;
+    use crate::models::application::{Application, Mode};
+    use scribe::Buffer;
+
+    fn completing_app(data: &str, other_data: &str) -> Application {
+        let length = data.lines().last().unwrap_or("").len();
+        let mut app = commands::app_with_buffer(
+            data,
+            scribe::buffer::Position{ line: data.lines().count() - 1, offset: length }
+        );
+
+        // Open the other buffer alongside it, leaving this one current.
+        let mut other_buffer = Buffer::new();
+        other_buffer.insert(other_data);
+        app.workspace.add_buffer(other_buffer);
+        app.workspace.previous_buffer();
+        commands::application::switch_to_insert_mode(&mut app).unwrap();
+
+        app
+    }
+
+    #[test]
+    fn accept_replaces_the_partial_identifier_with_the_selection() {
+        let mut app = completing_app("scribe_buffer\namp", "amplifier");
+        commands::application::switch_to_completion_mode(&mut app).unwrap();
+        super::accept(&mut app).unwrap();
+
+        assert!(matches!(app.mode, Mode::Insert));
+        let buffer = app.workspace.current_buffer().unwrap();
+        assert_eq!(buffer.data(), "scribe_buffer\namplifier");
+        assert_eq!(buffer.cursor.offset, 9);
+    }
+
+    #[test]
+    fn typing_refines_completions_and_leaves_when_none_remain() {
+        let mut app = completing_app("amp_editor\nscribe_buffer\n_", "");
+        commands::application::switch_to_completion_mode(&mut app).unwrap();
+
+        app.view.last_key = Some(Key::Char('b'));
+        super::insert_char(&mut app).unwrap();
+        match app.mode {
+            Mode::Completion(ref mode) => {
+                assert_eq!(mode.results().collect::<Vec<&String>>(), vec!["scribe_buffer"]);
+            },
+            _ => panic!("Application isn't in completion mode."),
+        }
+
+        app.view.last_key = Some(Key::Char('x'));
+        super::insert_char(&mut app).unwrap();
+        assert!(matches!(app.mode, Mode::Insert));
+    }
+}
@@ -44,6 +44,15 @@ pub fn move_to_end_of_line(app: &mut Application) -> Result {
     commands::view::scroll_to_cursor(app).chain_err(|| SCROLL_TO_CURSOR_FAILED)
 }
 
+pub fn move_to_matching_bracket(app: &mut Application) -> Result {
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let position = app.view
+        .matching_bracket(buffer)
+        .ok_or("No matching bracket found for the cursor")?;
+    buffer.cursor.move_to(position);
+    commands::view::scroll_to_cursor(app).chain_err(|| SCROLL_TO_CURSOR_FAILED)
+}
+
 pub fn move_to_first_line(app: &mut Application) -> Result {
     app.workspace
         .current_buffer()
@@ -197,14 +206,13 @@ pub fn append_to_current_token(app: &mut Application) -> Result {
 
 #[cfg(test)]
 mod tests {
-    use scribe::Buffer;
+    use crate::commands::app_with_buffer;
     use scribe::buffer::Position;
-    use crate::models::application::Application;
 
     #[test]
     fn move_to_first_word_of_line_works() {
         // Set up the application.
-        let mut app = set_up_application("    amp");
+        let mut app = app_with_buffer("    amp", Position::new());
 
         // Move to the end of the line.
         let position = Position {
@@ -227,7 +235,7 @@ mod tests {
     #[test]
     fn move_to_start_of_previous_token_works() {
         // Set up the application.
-        let mut app = set_up_application("\namp editor");
+        let mut app = app_with_buffer("\namp editor", Position::new());
 
         // Move past the first non-whitespace token.
         app.workspace.current_buffer().unwrap().cursor.move_to(Position {
@@ -249,7 +257,7 @@ mod tests {
     #[test]
     fn move_to_start_of_previous_token_skips_whitespace() {
         // Set up the application.
-        let mut app = set_up_application("\namp editor");
+        let mut app = app_with_buffer("\namp editor", Position::new());
 
         // Move to the start of the second non-whitespace word.
         app.workspace.current_buffer().unwrap().cursor.move_to(Position {
@@ -271,7 +279,7 @@ mod tests {
     #[test]
     fn move_to_start_of_next_token_works() {
         // Set up the application.
-        let mut app = set_up_application("\namp editor");
+        let mut app = app_with_buffer("\namp editor", Position::new());
 
         // Move to the start of the first non-whitespace word.
         app.workspace.current_buffer().unwrap().cursor.move_to(Position {
@@ -293,7 +301,7 @@ mod tests {
     #[test]
     fn move_to_end_of_current_token_works() {
         // Set up the application and run the command.
-        let mut app = set_up_application("\namp editor");
+        let mut app = app_with_buffer("\namp editor", Position::new());
 
         // Move to the start of the first non-whitespace word.
         app.workspace.current_buffer().unwrap().cursor.move_to(Position {
@@ -315,7 +323,7 @@ mod tests {
     #[test]
     fn append_to_current_token_works() {
         // Set up the application.
-        let mut app = set_up_application("\namp editor");
+        let mut app = app_with_buffer("\namp editor", Position::new());
 
         // Move to the start of the first non-whitespace word.
         app.workspace.current_buffer().unwrap().cursor.move_to(Position {
@@ -343,7 +351,7 @@ mod tests {
     #[test]
     fn insert_with_newline_above_finds_nearest_non_blank_indent() {
         // Set up the application.
-        let mut app = set_up_application("    amp editor\n");
+        let mut app = app_with_buffer("    amp editor\n", Position::new());
 
         // Move to the start of the first non-whitespace word.
         app.workspace
@@ -374,16 +382,27 @@ mod tests {
         });
     }
 
-    fn set_up_application(content: &str) -> Application {
-        let mut app = Application::new(&Vec::new()).unwrap();
-        let mut buffer = Buffer::new();
-
-        // Insert data with indentation and move to the end of the line.
-        buffer.insert(content);
+    #[test]
+    fn move_to_matching_bracket_extends_selections() {
+        let mut app = app_with_buffer("amp(\n    editor()\n)", Position::new());
+        crate::commands::application::switch_to_select_mode(&mut app).unwrap();
+        app.workspace.current_buffer().unwrap().cursor.move_to(Position {
+            line: 2,
+            offset: 0,
+        });
 
-        // Now that we've set up the buffer, add it to the application.
-        app.workspace.add_buffer(buffer);
+        super::move_to_matching_bracket(&mut app).unwrap();
+        assert_eq!(*app.workspace.current_buffer().unwrap().cursor,
+                   Position {
+                       line: 0,
+                       offset: 3,
+                   });
 
-        app
+        // Brackets aren't matched when the cursor isn't on one.
+        app.workspace.current_buffer().unwrap().cursor.move_to(Position {
+            line: 1,
+            offset: 4,
+        });
+        assert!(super::move_to_matching_bracket(&mut app).is_err());
     }
 }
\ No newline at end of file
@@ -57,6 +57,9 @@ fn switch_to_previous_mode(app: &mut Application) {
             jump::SelectModeOptions::SelectLine(select_mode) => {
                 app.mode = Mode::SelectLine(select_mode);
             }
+            jump::SelectModeOptions::SelectBlock(select_mode) => {
+                app.mode = Mode::SelectBlock(select_mode);
+            }
         }
     }
 }
@@ -2,7 +2,7 @@
 use crate::errors::*;
 use crate::input::Key;
 use crate::commands::{self, Result};
-use crate::models::application::{Application, Mode};
+use crate::models::application::{Application, HistoryKind, Mode};
 use scribe::buffer::Position;
 
 pub fn accept_input(app: &mut Application) -> Result {
@@ -12,6 +12,7 @@ pub fn accept_input(app: &mut Application) -> Result {
             .input
             .parse::<usize>()
             .chain_err(|| "Couldn't parse a line number from the provided input.")?;
+        app.history.add(HistoryKind::LineJump, mode.input.as_str());
 
         // Ignore zero-value line numbers.
         if line_number > 0 {
@@ -52,6 +53,32 @@ pub fn accept_input(app: &mut Application) -> Result {
     Ok(())
 }
 
+pub fn previous_history_entry(app: &mut Application) -> Result {
+    if let Mode::LineJump(ref mut mode) = app.mode {
+        let entries = app.history.entries(HistoryKind::LineJump);
+        if let Some(entry) = mode.history.previous(entries, &mode.input) {
+            mode.input = entry;
+        }
+    } else {
+        bail!("Can't browse line jump history outside of line jump mode")
+    }
+
+    Ok(())
+}
+
+pub fn next_history_entry(app: &mut Application) -> Result {
+    if let Mode::LineJump(ref mut mode) = app.mode {
+        let entries = app.history.entries(HistoryKind::LineJump);
+        if let Some(entry) = mode.history.next(entries) {
+            mode.input = entry;
+        }
+    } else {
+        bail!("Can't browse line jump history outside of line jump mode")
+    }
+
+    Ok(())
+}
+
 pub fn push_search_char(app: &mut Application) -> Result {
     let key = app.view.last_key().as_ref().ok_or("View hasn't tracked a key press")?;
 
@@ -83,7 +110,7 @@ mod tests {
     use crate::commands;
     use scribe::Buffer;
     use scribe::buffer::Position;
-    use crate::models::application::{Application, Mode};
+    use crate::models::application::{Application, History, HistoryKind, Mode};
 
     #[test]
     fn accept_input_moves_cursor_to_requested_line_and_changes_modes() {
@@ -182,4 +209,32 @@ mod tests {
             _ => false,
         });
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn accept_input_adds_input_to_history_which_can_be_browsed() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("amp\neditor\neditor");
+        app.workspace.add_buffer(buffer);
+        app.history = History::new(None);
+
+        commands::application::switch_to_line_jump_mode(&mut app).unwrap();
+        if let Mode::LineJump(ref mut mode) = app.mode {
+            mode.input = "3".to_string();
+        }
+        commands::line_jump::accept_input(&mut app).unwrap();
+        assert_eq!(app.history.entries(HistoryKind::LineJump), &["3"]);
+
+        commands::application::switch_to_line_jump_mode(&mut app).unwrap();
+        commands::line_jump::previous_history_entry(&mut app).unwrap();
+        assert!(match app.mode {
+            Mode::LineJump(ref mode) => mode.input == "3",
+            _ => false,
+        });
+        commands::line_jump::next_history_entry(&mut app).unwrap();
+        assert!(match app.mode {
+            Mode::LineJump(ref mode) => mode.input.is_empty(),
+            _ => false,
+        });
+    }
+}
@@ -0,0 +1,147 @@
+use crate::commands::{self, Result};
+use crate::input::Key;
+use crate::models::application::{Application, Mode};
+use crate::models::application::modes::MacroAction;
+
+/// Stops recording the current macro, or prompts
+/// for the name of a new one to start recording.
+pub fn toggle_recording(app: &mut Application) -> Result {
+    if app.macros.recording().is_some() {
+        app.macros.stop_recording()
+    } else {
+        commands::application::switch_to_macro_record_mode(app)
+    }
+}
+
+/// Handles a key press in the macro prompt. Replays can be preceded by
+/// digits to specify a count; any other character names the macro.
+pub fn accept_key(app: &mut Application) -> Result {
+    let character = match *app.view.last_key() {
+        Some(Key::Char(c)) => c,
+        _ => bail!("Last key press wasn't a character"),
+    };
+
+    let (action, count) = if let Mode::Macro(ref mut mode) = app.mode {
+        let leading_zero = character == '0' && mode.count.is_empty();
+        if mode.action == MacroAction::Replay && character.is_ascii_digit() && !leading_zero {
+            mode.count.push(character);
+            return Ok(());
+        }
+
+        (mode.action, mode.count())
+    } else {
+        bail!("Can't accept macro name outside of macro mode");
+    };
+
+    commands::application::switch_to_normal_mode(app)?;
+    match action {
+        MacroAction::Record => {
+            app.macros.start_recording(character.to_string());
+            Ok(())
+        }
+        MacroAction::Replay => replay(app, &character.to_string(), count),
+    }
+}
+
+/// Replays the macro bound to the last key press in the keymap.
+pub fn run_bound_macro(app: &mut Application) -> Result {
+    let name = {
+        let key = app.view.last_key().as_ref().ok_or("View hasn't tracked a key press")?;
+        let mode = app.mode_str().ok_or("No keymap for the current mode")?;
+        app.preferences
+            .borrow()
+            .keymap()
+            .macro_for(mode, key)
+            .map(|name| name.to_string())
+            .ok_or("No macro is bound to the last key press")?
+    };
+
+    replay(app, &name, 1)
+}
+
+// Feeds the macro's keys through the regular input handler,
+// stopping at the first error encountered, if any.
+fn replay(app: &mut Application, name: &str, count: usize) -> Result {
+    let keys = app.macros
+        .get(name)
+        .ok_or_else(|| format!("There's no macro named \"{}\"", name))?
+        .to_vec();
+
+    app.macros.start_replay()?;
+    let mut result = Ok(());
+    'replay: for _ in 0..count {
+        for key in keys.iter() {
+            app.view.last_key = Some(key.clone());
+            result = commands::application::handle_input(app);
+            if result.is_err() {
+                break 'replay;
+            }
+        }
+    }
+    app.macros.finish_replay();
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::{self, app_with_buffer};
+    use crate::input::Key;
+    use crate::models::Application;
+    use crate::models::application::Mode;
+    use scribe::buffer::Position;
+
+    fn press_keys(app: &mut Application, keys: &[Key]) {
+        for key in keys {
+            app.view.last_key = Some(key.clone());
+            commands::application::handle_input(app).unwrap();
+        }
+    }
+
+    #[test]
+    fn recorded_macros_exclude_keys_used_to_start_and_stop_recording() {
+        let mut app = app_with_buffer("amp", Position::new());
+        press_keys(&mut app, &[Key::Char('!'), Key::Char('a')]);
+        assert_eq!(app.macros.recording(), Some("a"));
+
+        press_keys(&mut app, &[Key::Char('i'), Key::Char('x'), Key::Esc, Key::Char('!')]);
+        assert_eq!(app.macros.recording(), None);
+        assert_eq!(
+            app.macros.get("a"),
+            Some(&[Key::Char('i'), Key::Char('x'), Key::Esc][..])
+        );
+    }
+
+    #[test]
+    fn replaying_a_macro_with_a_count_repeats_it() {
+        let mut app = app_with_buffer("amp", Position::new());
+        press_keys(&mut app, &[Key::Char('!'), Key::Char('a')]);
+        press_keys(&mut app, &[Key::Char('i'), Key::Char('x'), Key::Esc, Key::Char('!')]);
+
+        press_keys(&mut app, &[Key::Char('@'), Key::Char('3'), Key::Char('a')]);
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "xxxxamp");
+        assert!(matches!(app.mode, Mode::Normal));
+    }
+
+    #[test]
+    fn recorded_macros_exclude_keys_replayed_by_repeating_the_last_change() {
+        let mut app = app_with_buffer("amp editor", Position::new());
+        press_keys(&mut app, &[Key::Char('!'), Key::Char('a')]);
+        press_keys(&mut app, &[Key::Char('x'), Key::Char('.'), Key::Char('!')]);
+        assert_eq!(app.macros.get("a"), Some(&[Key::Char('x'), Key::Char('.')][..]));
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "p editor");
+
+        press_keys(&mut app, &[Key::Char('@'), Key::Char('a')]);
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "editor");
+    }
+
+    #[test]
+    fn replaying_a_missing_macro_fails() {
+        let mut app = app_with_buffer("amp", Position::new());
+        app.view.last_key = Some(Key::Char('@'));
+        commands::application::handle_input(&mut app).unwrap();
+        app.view.last_key = Some(Key::Char('z'));
+
+        assert!(commands::application::handle_input(&mut app).is_err());
+    }
+}
@@ -3,18 +3,28 @@ use crate::errors;
 use crate::models::application::Application;
 use std::collections::HashMap;
 
+pub mod align;
 pub mod application;
 pub mod buffer;
+pub mod case;
+pub mod completion;
 pub mod confirm;
 pub mod cursor;
 pub mod git;
 pub mod jump;
 pub mod line_jump;
+pub mod macros;
+pub mod multi_cursor;
 pub mod path;
 pub mod preferences;
+pub mod register;
+pub mod replace;
 pub mod search;
 pub mod selection;
+pub mod snippet;
 pub mod search_select;
+pub mod surround;
+pub mod text_object;
 pub mod view;
 pub mod workspace;
 
@@ -24,3 +34,25 @@ pub type Result = errors::Result<()>;
 pub fn hash_map() -> HashMap<&'static str, Command> {
     include!(concat!(env!("OUT_DIR"), "/hash_map"))
 }
+
+/// Builds an application for command tests, with a single buffer holding the
+/// data, its cursor at the position, and its view initialized (so that its
+/// revision is tracked, as it would be for a buffer opened from a file).
+/// Macros, undo histories and search options start out empty and at their
+/// defaults, rather than being loaded from the user's data and preferences.
+#[cfg(test)]
+pub fn app_with_buffer(data: &str, position: scribe::buffer::Position) -> Application {
+    use crate::models::application::{Macros, UndoHistory};
+    use crate::models::application::modes::SearchOptions;
+
+    let mut app = Application::new(&Vec::new()).unwrap();
+    app.macros = Macros::new(None);
+    app.undo_history = UndoHistory::new(None);
+    app.search_options = SearchOptions::default();
+    let mut buffer = scribe::Buffer::new();
+    buffer.insert(data);
+    buffer.cursor.move_to(position);
+    crate::util::add_buffer(buffer, &mut app).unwrap();
+
+    app
+}
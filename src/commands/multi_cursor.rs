@@ -0,0 +1,318 @@
+use crate::errors::*;
+use crate::commands::{self, Result};
+use crate::models::application::{Application, Mode};
+use crate::models::application::modes::{SearchMode, SearchOptions};
+use crate::util::text_object;
+use scribe::buffer::{Position, Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+pub fn add_cursors_to_search_results(app: &mut Application) -> Result {
+    if let Mode::Search(ref mode) = app.mode {
+        let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+        let results = mode.results.as_ref().ok_or(NO_SEARCH_RESULTS)?;
+        if results.is_empty() {
+            bail!(NO_SEARCH_RESULTS);
+        }
+
+        app.cursors.set(buffer, results.to_vec());
+    } else {
+        bail!("Can't add cursors to search results outside of search mode");
+    }
+
+    Ok(())
+}
+
+pub fn add_cursors_to_selected_lines(app: &mut Application) -> Result {
+    if let Mode::SelectLine(ref mode) = app.mode {
+        let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+        let (first_line, last_line) = if mode.anchor < buffer.cursor.line {
+            (mode.anchor, buffer.cursor.line)
+        } else {
+            (buffer.cursor.line, mode.anchor)
+        };
+
+        // Line the cursors up with the current one, where possible.
+        let offset = buffer.cursor.offset;
+        let selections = buffer
+            .data()
+            .lines()
+            .enumerate()
+            .skip(first_line)
+            .take(last_line - first_line + 1)
+            .map(|(line, content)| {
+                let position = Position{
+                    line,
+                    offset: offset.min(content.graphemes(true).count()),
+                };
+                Range::new(position, position)
+            })
+            .collect();
+
+        app.cursors.set(buffer, selections);
+    } else {
+        bail!("Can't add cursors to selected lines outside of select line mode");
+    }
+
+    Ok(())
+}
+
+/// Adds a cursor at the start of each line of a rectangular selection,
+/// skipping lines that end before it, so that text can be inserted before it.
+pub fn add_cursors_to_block_start(app: &mut Application) -> Result {
+    add_cursors_to_block(app, Range::start)
+}
+
+/// Adds a cursor at the end of each line of a rectangular selection, skipping
+/// lines that end before it, so that text can be appended to the rectangle.
+pub fn add_cursors_to_block_end(app: &mut Application) -> Result {
+    add_cursors_to_block(app, Range::end)
+}
+
+fn add_cursors_to_block(app: &mut Application, edge: fn(&Range) -> Position) -> Result {
+    if let Mode::SelectBlock(ref mode) = app.mode {
+        let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+        let positions: Vec<Position> = mode.ranges(&buffer.data(), &buffer.cursor)
+            .iter()
+            .filter(|range| range.start() != range.end())
+            .map(edge)
+            .collect();
+        let first_position = *positions.first().ok_or("The selection doesn't include any characters")?;
+
+        buffer.cursor.move_to(first_position);
+        app.cursors.set(
+            buffer,
+            positions.into_iter().map(|position| Range::new(position, position)).collect()
+        );
+    } else {
+        bail!("Can't add cursors to a rectangle outside of select block mode");
+    }
+
+    Ok(())
+}
+
+/// Selects the next occurrence of the token under the cursor, moving the
+/// cursor to it. If the cursor doesn't already have a selection, the token
+/// under it is selected first, replacing any existing cursors.
+pub fn add_cursor_to_next_token_match(app: &mut Application) -> Result {
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let current_selection = app.cursors
+        .selections(buffer)
+        .iter()
+        .find(|selection| selection.start() == *buffer.cursor && selection.start() != selection.end())
+        .cloned();
+    let token = match current_selection {
+        Some(selection) => selection,
+        None => {
+            let token = text_object::token(&buffer.data(), *buffer.cursor, false)
+                .ok_or("No token under the cursor")?;
+            buffer.cursor.move_to(token.start());
+            app.cursors.set(buffer, vec![token.clone()]);
+            token
+        }
+    };
+
+    let query = buffer.read(&token).ok_or("Couldn't read the selected token")?;
+    let mut search = SearchMode::new(
+        Some(query),
+        SearchOptions{ whole_word: true, ..Default::default() }
+    );
+    search.search(buffer)?;
+
+    let selections = app.cursors.selections(buffer);
+    let unselected_results: Vec<&Range> = search.results
+        .as_ref()
+        .ok_or(NO_SEARCH_RESULTS)?
+        .iter()
+        .filter(|result| !selections.iter().any(|selection| selection.start() == result.start()))
+        .collect();
+    let next_match = unselected_results
+        .iter()
+        .find(|result| result.start() > token.start())
+        .or_else(|| unselected_results.first())
+        .map(|result| (*result).clone())
+        .ok_or("No other matches for the selected token")?;
+
+    buffer.cursor.move_to(next_match.start());
+    app.cursors.add(buffer, next_match);
+
+    commands::view::scroll_to_cursor(app)
+}
+
+/// Deletes every cursor's selection, and switches to insert mode so that
+/// their replacements can be entered. All of it is undone as one change.
+pub fn change_selections(app: &mut Application) -> Result {
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    if !app.cursors.active(buffer) {
+        bail!("There are no other cursors to change");
+    }
+
+    buffer.start_operation_group();
+    app.cursors.edit(buffer, |_, selection| {
+        if selection.start() == selection.end() {
+            None
+        } else {
+            Some((selection.clone(), String::new()))
+        }
+    });
+
+    commands::application::switch_to_insert_mode(app)
+}
+
+pub fn clear(app: &mut Application) -> Result {
+    app.cursors.clear();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::{self, app_with_buffer};
+    use crate::input::Key;
+    use crate::models::Application;
+    use crate::models::application::Mode;
+    use scribe::buffer::Position;
+
+    fn type_characters(app: &mut Application, characters: &str) {
+        for c in characters.chars() {
+            app.view.last_key = Some(Key::Char(c));
+            commands::buffer::insert_char(app).unwrap();
+        }
+    }
+
+    #[test]
+    fn changing_search_results_edits_every_match_as_a_single_operation() {
+        let mut app = app_with_buffer("amp editor\namp\nnot amp", Position::new());
+        app.search_query = Some(String::from("amp"));
+        commands::application::switch_to_search_mode(&mut app).unwrap();
+        commands::search::accept_query(&mut app).unwrap();
+
+        super::add_cursors_to_search_results(&mut app).unwrap();
+        super::change_selections(&mut app).unwrap();
+        assert!(matches!(app.mode, Mode::Insert));
+
+        type_characters(&mut app, "scribe");
+        commands::buffer::backspace(&mut app).unwrap();
+        commands::application::switch_to_normal_mode(&mut app).unwrap();
+        assert_eq!(
+            app.workspace.current_buffer().unwrap().data(),
+            "scrib editor\nscrib\nnot scrib"
+        );
+
+        commands::buffer::undo(&mut app).unwrap();
+        assert_eq!(
+            app.workspace.current_buffer().unwrap().data(),
+            "amp editor\namp\nnot amp"
+        );
+    }
+
+    #[test]
+    fn cursors_are_cleared_by_edits_made_without_them() {
+        let mut app = app_with_buffer("amp\namp\namp", Position::new());
+        commands::application::switch_to_select_line_mode(&mut app).unwrap();
+        app.workspace.current_buffer().unwrap().cursor.move_to(Position{ line: 2, offset: 0 });
+        super::add_cursors_to_selected_lines(&mut app).unwrap();
+        commands::application::switch_to_insert_mode(&mut app).unwrap();
+
+        for key in &[Key::Char('-'), Key::Esc] {
+            app.view.last_key = Some(key.clone());
+            commands::application::handle_input(&mut app).unwrap();
+        }
+        let buffer = app.workspace.current_buffer().unwrap();
+        assert_eq!(buffer.data(), "-amp\n-amp\n-amp");
+        assert!(app.cursors.active(buffer));
+
+        app.view.last_key = Some(Key::Char(';'));
+        commands::application::handle_input(&mut app).unwrap();
+        let buffer = app.workspace.current_buffer().unwrap();
+        assert!(!app.cursors.active(buffer));
+    }
+
+    #[test]
+    fn selected_line_cursors_are_aligned_with_the_current_cursor() {
+        let mut app = app_with_buffer("amp\nx\neditor\n", Position::new());
+        commands::application::switch_to_select_line_mode(&mut app).unwrap();
+        app.workspace.current_buffer().unwrap().cursor.move_to(Position{ line: 2, offset: 2 });
+
+        super::add_cursors_to_selected_lines(&mut app).unwrap();
+        commands::application::switch_to_insert_mode(&mut app).unwrap();
+        type_characters(&mut app, "-");
+
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "am-p\nx-\ned-itor\n");
+    }
+
+    #[test]
+    fn block_cursors_insert_and_append_on_lines_reaching_the_rectangle() {
+        let mut app = app_with_buffer("amp\nx\neditor", Position::new());
+        app.workspace.current_buffer().unwrap().cursor.move_to(Position{ line: 0, offset: 1 });
+        commands::application::switch_to_select_block_mode(&mut app).unwrap();
+        app.workspace.current_buffer().unwrap().cursor.move_to(Position{ line: 2, offset: 2 });
+
+        super::add_cursors_to_block_end(&mut app).unwrap();
+        commands::application::switch_to_insert_mode(&mut app).unwrap();
+        type_characters(&mut app, "-");
+        commands::application::switch_to_normal_mode(&mut app).unwrap();
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "amp-\nx\nedi-tor");
+
+        app.workspace.current_buffer().unwrap().cursor.move_to(Position{ line: 0, offset: 1 });
+        commands::application::switch_to_select_block_mode(&mut app).unwrap();
+        app.workspace.current_buffer().unwrap().cursor.move_to(Position{ line: 2, offset: 1 });
+
+        super::add_cursors_to_block_start(&mut app).unwrap();
+        commands::application::switch_to_insert_mode(&mut app).unwrap();
+        type_characters(&mut app, "+");
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "a+mp-\nx\ne+di-tor");
+    }
+
+    #[test]
+    fn add_cursor_to_next_token_match_selects_whole_word_matches_in_turn() {
+        let mut app = app_with_buffer("amp ample\namp\namp", Position::new());
+        app.workspace.current_buffer().unwrap().cursor.move_to(Position{ line: 1, offset: 1 });
+
+        super::add_cursor_to_next_token_match(&mut app).unwrap();
+        assert_eq!(
+            *app.workspace.current_buffer().unwrap().cursor,
+            Position{ line: 2, offset: 0 }
+        );
+
+        // Matches wrap around to the start of the buffer.
+        super::add_cursor_to_next_token_match(&mut app).unwrap();
+        assert_eq!(
+            *app.workspace.current_buffer().unwrap().cursor,
+            Position{ line: 0, offset: 0 }
+        );
+        assert!(super::add_cursor_to_next_token_match(&mut app).is_err());
+
+        super::change_selections(&mut app).unwrap();
+        type_characters(&mut app, "x");
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "x ample\nx\nx");
+    }
+
+    #[test]
+    fn insert_newline_indents_at_every_cursor() {
+        let mut app = app_with_buffer("  amp\n    editor", Position::new());
+        app.workspace.current_buffer().unwrap().cursor.move_to(Position{ line: 0, offset: 5 });
+        let buffer = app.workspace.current_buffer().unwrap();
+        let end_of_last_line = Position{ line: 1, offset: 10 };
+        app.cursors.set(buffer, vec![
+            scribe::buffer::Range::new(end_of_last_line, end_of_last_line)
+        ]);
+
+        commands::buffer::insert_newline(&mut app).unwrap();
+        type_characters(&mut app, "x");
+
+        assert_eq!(
+            app.workspace.current_buffer().unwrap().data(),
+            "  amp\n  x\n    editor\n    x"
+        );
+    }
+
+    #[test]
+    fn clear_removes_every_cursor() {
+        let mut app = app_with_buffer("amp amp", Position::new());
+        super::add_cursor_to_next_token_match(&mut app).unwrap();
+        super::clear(&mut app).unwrap();
+
+        let buffer = app.workspace.current_buffer().unwrap();
+        assert!(app.cursors.selections(buffer).is_empty());
+    }
+}
@@ -1,7 +1,7 @@
 use crate::errors::*;
 use crate::commands::{self, Result};
 use crate::input::Key;
-use crate::models::application::{Application, Mode};
+use crate::models::application::{Application, HistoryKind, Mode};
 use std::path::PathBuf;
 
 pub fn push_char(app: &mut Application) -> Result {
@@ -27,6 +27,30 @@ pub fn pop_char(app: &mut Application) -> Result {
     Ok(())
 }
 
+pub fn previous_history_entry(app: &mut Application) -> Result {
+    if let Mode::Path(ref mut mode) = app.mode {
+        let entries = app.history.entries(HistoryKind::Path);
+        if let Some(entry) = mode.history.previous(entries, &mode.input) {
+            mode.input = entry;
+        }
+    } else {
+        bail!("Cannot browse path history outside of path mode");
+    }
+    Ok(())
+}
+
+pub fn next_history_entry(app: &mut Application) -> Result {
+    if let Mode::Path(ref mut mode) = app.mode {
+        let entries = app.history.entries(HistoryKind::Path);
+        if let Some(entry) = mode.history.next(entries) {
+            mode.input = entry;
+        }
+    } else {
+        bail!("Cannot browse path history outside of path mode");
+    }
+    Ok(())
+}
+
 pub fn accept_path(app: &mut Application) -> Result {
     let save_on_accept =
         if let Mode::Path(ref mut mode) = app.mode {
@@ -35,7 +59,8 @@ pub fn accept_path(app: &mut Application) -> Result {
             if path_name.is_empty() {
                 bail!("Please provide a non-empty path")
             }
-            current_buffer.path = Some(PathBuf::from(path_name));
+            current_buffer.path = Some(PathBuf::from(&path_name));
+            app.history.add(HistoryKind::Path, path_name);
             mode.save_on_accept
         } else {
             bail!("Cannot accept path outside of path mode");
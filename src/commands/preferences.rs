@@ -8,5 +8,11 @@ pub fn edit(app: &mut Application) -> Result {
 }
 
 pub fn reload(app: &mut Application) -> Result {
-    app.preferences.borrow_mut().reload()
+    let mut preferences = app.preferences.borrow_mut();
+    preferences.reload()?;
+
+    match preferences.take_indent_rules_error() {
+        Some(error) => bail!(error),
+        None => Ok(()),
+    }
 }
@@ -0,0 +1,89 @@
+use crate::commands::{self, Result};
+use crate::input::Key;
+use crate::models::application::{Application, Mode};
+use crate::models::application::modes::RegisterAction;
+use std::mem;
+
+/// Handles a key press in the register prompt, which names the register
+/// to copy the captured selection into, or paste from.
+pub fn accept_key(app: &mut Application) -> Result {
+    let name = match *app.view.last_key() {
+        Some(Key::Char(c)) => c,
+        _ => bail!("Last key press wasn't a character"),
+    };
+
+    let action = match mem::replace(&mut app.mode, Mode::Normal) {
+        Mode::Register(mode) => mode.action,
+        mode => {
+            app.mode = mode;
+            bail!("Can't accept register name outside of register mode");
+        }
+    };
+
+    commands::application::switch_to_normal_mode(app)?;
+    match action {
+        RegisterAction::Copy(content) => {
+            app.clipboard.set_register(name, content);
+            Ok(())
+        }
+        RegisterAction::Paste => {
+            let content = app.clipboard
+                .register(name)
+                .cloned()
+                .ok_or_else(|| format!("Register \"{}\" is empty", name))?;
+            commands::buffer::paste_content(app, &content)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::{self, app_with_buffer};
+    use crate::input::Key;
+    use crate::models::application::{ClipboardContent, Mode};
+    use scribe::buffer::Position;
+
+    #[test]
+    fn copied_lines_are_pasted_from_their_register_as_a_block() {
+        let mut app = app_with_buffer("amp\neditor\n", Position::new());
+        commands::application::switch_to_select_line_mode(&mut app).unwrap();
+        commands::application::switch_to_register_copy_mode(&mut app).unwrap();
+        app.view.last_key = Some(Key::Char('a'));
+        super::accept_key(&mut app).unwrap();
+        assert!(matches!(app.mode, Mode::Normal));
+        assert_eq!(app.clipboard.register('a'), Some(&ClipboardContent::Block(String::from("amp\n"))));
+
+        app.workspace.current_buffer().unwrap().cursor.move_to(Position{ line: 1, offset: 2 });
+        commands::application::switch_to_register_paste_mode(&mut app).unwrap();
+        super::accept_key(&mut app).unwrap();
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "amp\neditor\namp\n");
+    }
+
+    #[test]
+    fn pasting_from_an_empty_register_fails() {
+        let mut app = app_with_buffer("amp", Position::new());
+        commands::application::switch_to_register_paste_mode(&mut app).unwrap();
+        app.view.last_key = Some(Key::Char('z'));
+
+        assert!(super::accept_key(&mut app).is_err());
+        assert!(matches!(app.mode, Mode::Normal));
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "amp");
+    }
+
+    #[test]
+    fn deleted_lines_can_be_pasted_from_the_clipboard_history() {
+        let mut app = app_with_buffer("amp\neditor\nscribe", Position::new());
+        commands::buffer::delete_current_line(&mut app).unwrap();
+        app.workspace.current_buffer().unwrap().cursor.move_to(Position{ line: 0, offset: 0 });
+        commands::application::switch_to_select_mode(&mut app).unwrap();
+        commands::cursor::move_right(&mut app).unwrap();
+        commands::selection::copy(&mut app).unwrap();
+
+        // Select the older, deleted line, rather than the copied character.
+        commands::application::switch_to_clipboard_history_mode(&mut app).unwrap();
+        commands::search_select::select_next(&mut app).unwrap();
+        commands::search_select::accept(&mut app).unwrap();
+
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "editor\namp\nscribe");
+    }
+}
@@ -0,0 +1,272 @@
+use crate::errors::*;
+use crate::input::Key;
+use crate::commands::{self, Result};
+use crate::models::application::{Application, Mode};
+use scribe::buffer::{Distance, Position, Range};
+
+pub fn push_char(app: &mut Application) -> Result {
+    let key = app.view.last_key().as_ref().ok_or("View hasn't tracked a key press")?;
+
+    if let Key::Char(c) = *key {
+        if let Mode::Replace(ref mut mode) = app.mode {
+            mode.input.get_or_insert(String::new()).push(c);
+        } else {
+            bail!("Can't push replacement character outside of replace mode");
+        }
+    } else {
+        bail!("Last key press wasn't a character")
+    }
+
+    Ok(())
+}
+
+pub fn pop_char(app: &mut Application) -> Result {
+    if let Mode::Replace(ref mut mode) = app.mode {
+        mode.input.as_mut().ok_or("No replacement to modify")?.pop();
+    } else {
+        bail!("Can't pop replacement character outside of replace mode");
+    }
+
+    Ok(())
+}
+
+pub fn accept_replacement(app: &mut Application) -> Result {
+    if let Mode::Replace(ref mut mode) = app.mode {
+        // An empty replacement is a perfectly valid way to delete matches.
+        mode.input.get_or_insert(String::new());
+        mode.insert = false;
+        mode.remaining = mode.search.results.as_ref().ok_or(NO_SEARCH_RESULTS)?.len();
+    } else {
+        bail!("Can't accept replacement outside of replace mode");
+    }
+
+    // Group every replacement made from here on, so
+    // that they can be undone as a single operation.
+    commands::buffer::start_command_group(app)?;
+
+    move_to_current_match(app)
+}
+
+pub fn replace_current(app: &mut Application) -> Result {
+    let replacement_end = if let Mode::Replace(ref mut mode) = app.mode {
+        let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+        let template = mode.input.as_ref().ok_or("No replacement to apply")?;
+        let range = mode.search.results
+            .as_ref()
+            .and_then(|results| results.selection())
+            .cloned()
+            .ok_or(NO_SEARCH_RESULTS)?;
+        let replacement = mode.search
+            .replacements(buffer, std::slice::from_ref(&range), template)?
+            .pop()
+            .ok_or(NO_SEARCH_RESULTS)?;
+
+        buffer.delete_range(range.clone());
+        buffer.cursor.move_to(range.start());
+        buffer.insert(replacement.as_str());
+        mode.remaining -= 1;
+
+        // Don't match the replacement itself (e.g. replacing
+        // "amp" with "amped") by skipping over its content.
+        range.start() + Distance::of_str(&replacement)
+    } else {
+        bail!("Can't replace match outside of replace mode");
+    };
+
+    advance(app, replacement_end)
+}
+
+pub fn skip(app: &mut Application) -> Result {
+    let skipped_position = if let Mode::Replace(ref mut mode) = app.mode {
+        let range = mode.search.results
+            .as_ref()
+            .and_then(|results| results.selection())
+            .ok_or(NO_SEARCH_RESULTS)?;
+        mode.remaining -= 1;
+
+        let mut position = range.start();
+        position.offset += 1;
+        position
+    } else {
+        bail!("Can't skip match outside of replace mode");
+    };
+
+    advance(app, skipped_position)
+}
+
+pub fn replace_all(app: &mut Application) -> Result {
+    if let Mode::Replace(ref mut mode) = app.mode {
+        let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+        let template = mode.input.as_ref().ok_or("No replacement to apply")?;
+        let results = mode.search.results.as_ref().ok_or(NO_SEARCH_RESULTS)?;
+
+        // Gather the remaining matches, starting from the current one and
+        // wrapping, in reverse order, so that replacing each one in turn
+        // leaves the ranges of those before it intact.
+        let mut indices: Vec<usize> = (0..mode.remaining)
+            .map(|i| (results.selected_index() + i) % results.len())
+            .collect();
+        indices.sort_unstable_by(|a, b| b.cmp(a));
+        let ranges: Vec<Range> = indices.into_iter().map(|i| results[i].clone()).collect();
+        let replacements = mode.search.replacements(buffer, &ranges, template)?;
+
+        for (range, replacement) in ranges.into_iter().zip(replacements) {
+            buffer.delete_range(range.clone());
+            buffer.cursor.move_to(range.start());
+            buffer.insert(replacement);
+        }
+        mode.remaining = 0;
+    } else {
+        bail!("Can't replace matches outside of replace mode");
+    }
+
+    quit(app)
+}
+
+pub fn quit(app: &mut Application) -> Result {
+    if let Mode::Replace(_) = app.mode {
+        // Switching to normal mode closes the command group
+        // opened when the replacement text was accepted.
+        commands::application::switch_to_normal_mode(app)?;
+    } else {
+        bail!("Can't quit replace mode outside of replace mode");
+    }
+
+    commands::view::scroll_to_cursor(app)
+}
+
+// Re-runs the search to account for the changes made so far, and selects
+// the first match at or beyond the specified position, wrapping if needed.
+// Finishes the replacement once every match has been considered.
+fn advance(app: &mut Application, position: Position) -> Result {
+    if let Mode::Replace(ref mut mode) = app.mode {
+        let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+        mode.search.search(buffer)?;
+
+        let results = mode.search.results.as_mut().ok_or(NO_SEARCH_RESULTS)?;
+        if mode.remaining == 0 || results.is_empty() {
+            return quit(app);
+        }
+
+        let skip_count = results
+            .iter()
+            .filter(|r| r.start() < position)
+            .count();
+        for _ in 0..skip_count {
+            results.select_next();
+        }
+    } else {
+        bail!("Can't advance to next match outside of replace mode");
+    }
+
+    move_to_current_match(app)
+}
+
+fn move_to_current_match(app: &mut Application) -> Result {
+    if let Mode::Replace(ref mode) = app.mode {
+        let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+        let range = mode.search.results
+            .as_ref()
+            .and_then(|results| results.selection())
+            .ok_or(NO_SEARCH_RESULTS)?;
+        buffer.cursor.move_to(range.start());
+    } else {
+        bail!("Can't move to match outside of replace mode");
+    }
+
+    commands::view::scroll_cursor_to_center(app)
+        .chain_err(|| SCROLL_TO_CURSOR_FAILED)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands;
+    use crate::models::Application;
+    use crate::models::application::Mode;
+    use crate::models::application::modes::SearchOptions;
+    use scribe::Buffer;
+    use scribe::buffer::Position;
+
+    fn replace_mode_app(data: &str, query: &str, options: SearchOptions, replacement: &str) -> Application {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert(data);
+        app.workspace.add_buffer(buffer);
+
+        app.search_query = Some(String::from(query));
+        app.search_options = options;
+        commands::application::switch_to_search_mode(&mut app).unwrap();
+        commands::search::accept_query(&mut app).unwrap();
+        commands::application::switch_to_replace_mode(&mut app).unwrap();
+        if let Mode::Replace(ref mut mode) = app.mode {
+            mode.input = Some(String::from(replacement));
+        }
+        super::accept_replacement(&mut app).unwrap();
+
+        app
+    }
+
+    #[test]
+    fn replace_current_replaces_match_and_moves_to_the_next_one() {
+        let mut app = replace_mode_app("amp\namp\namp", "amp", SearchOptions::default(), "amped");
+        super::replace_current(&mut app).unwrap();
+
+        // Search starts with the first match after the cursor.
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "amp\namped\namp");
+        assert_eq!(*app.workspace.current_buffer().unwrap().cursor,
+                   Position{ line: 2, offset: 0 });
+    }
+
+    #[test]
+    fn skip_moves_to_the_next_match_without_replacing() {
+        let mut app = replace_mode_app("amp\namp\namp", "amp", SearchOptions::default(), "editor");
+        super::skip(&mut app).unwrap();
+        super::replace_current(&mut app).unwrap();
+
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "amp\namp\neditor");
+    }
+
+    #[test]
+    fn replacing_every_match_switches_to_normal_mode() {
+        let mut app = replace_mode_app("amp amp", "amp", SearchOptions::default(), "amp amp");
+        super::replace_current(&mut app).unwrap();
+        super::replace_current(&mut app).unwrap();
+
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "amp amp amp amp");
+        assert!(matches!(app.mode, Mode::Normal));
+    }
+
+    #[test]
+    fn replace_all_expands_capture_groups_and_can_be_undone_as_one_operation() {
+        let options = SearchOptions { regex: true, ..Default::default() };
+        let mut app = replace_mode_app("amp editor\nscribe buffer", r"(\w+) (\w+)", options, "$2 $1");
+        super::replace_all(&mut app).unwrap();
+
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "editor amp\nbuffer scribe");
+        assert!(matches!(app.mode, Mode::Normal));
+
+        commands::buffer::undo(&mut app).unwrap();
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "amp editor\nscribe buffer");
+    }
+
+    #[test]
+    fn replace_all_only_replaces_remaining_matches_wrapping_at_the_end_of_the_document() {
+        let mut app = replace_mode_app("amp\namp\namp", "amp", SearchOptions::default(), "editor");
+        super::skip(&mut app).unwrap();
+        super::replace_all(&mut app).unwrap();
+
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "editor\namp\neditor");
+    }
+
+    #[test]
+    fn quit_groups_previous_replacements_into_a_single_undo_operation() {
+        let mut app = replace_mode_app("amp\namp\namp", "amp", SearchOptions::default(), "editor");
+        super::replace_current(&mut app).unwrap();
+        super::replace_current(&mut app).unwrap();
+        super::quit(&mut app).unwrap();
+
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "amp\neditor\neditor");
+        commands::buffer::undo(&mut app).unwrap();
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "amp\namp\namp");
+    }
+}
@@ -2,7 +2,8 @@
 use crate::errors::*;
 use crate::input::Key;
 use crate::commands::{self, Result};
-use crate::models::application::{Application, Mode};
+use crate::models::application::{Application, HistoryKind, Mode};
+use crate::models::application::modes::SearchOptions;
 
 pub fn move_to_previous_result(app: &mut Application) -> Result {
     if let Mode::Search(ref mut mode) = app.mode {
@@ -50,12 +51,73 @@ pub fn move_to_current_result(app: &mut Application) -> Result {
 
 pub fn accept_query(app: &mut Application) -> Result {
     if let Mode::Search(ref mut mode) = app.mode {
-        // Disable insert sub-mode.
+        // Search before disabling insert sub-mode, so that
+        // an invalid query can be corrected without re-entry.
+        let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+        mode.search(buffer)?;
         mode.insert = false;
+        app.highlight_search_results = true;
+
+        if let Some(ref query) = mode.input {
+            app.history.add(HistoryKind::Search, query.as_str());
+        }
     } else {
         bail!("Can't accept search query outside of search mode");
     }
-    run(app)?;
+
+    select_closest_result(app)?;
+    move_to_current_result(app)
+}
+
+pub fn previous_history_entry(app: &mut Application) -> Result {
+    if let Mode::Search(ref mut mode) = app.mode {
+        let current = mode.input.clone().unwrap_or_default();
+        let entries = app.history.entries(HistoryKind::Search);
+
+        if let Some(entry) = mode.history.previous(entries, &current) {
+            mode.input = Some(entry.clone());
+            app.search_query = Some(entry);
+        }
+    } else {
+        bail!("Can't browse search history outside of search mode");
+    }
+
+    update_results(app)
+}
+
+pub fn next_history_entry(app: &mut Application) -> Result {
+    if let Mode::Search(ref mut mode) = app.mode {
+        let entries = app.history.entries(HistoryKind::Search);
+
+        if let Some(entry) = mode.history.next(entries) {
+            mode.input = Some(entry.clone());
+            app.search_query = Some(entry);
+        }
+    } else {
+        bail!("Can't browse search history outside of search mode");
+    }
+
+    update_results(app)
+}
+
+pub fn toggle_regex(app: &mut Application) -> Result {
+    toggle_option(app, |options| options.regex = !options.regex)
+}
+
+pub fn toggle_case_insensitive(app: &mut Application) -> Result {
+    toggle_option(app, |options| options.case_insensitive = !options.case_insensitive)
+}
+
+pub fn toggle_smart_case(app: &mut Application) -> Result {
+    toggle_option(app, |options| options.smart_case = !options.smart_case)
+}
+
+pub fn toggle_whole_word(app: &mut Application) -> Result {
+    toggle_option(app, |options| options.whole_word = !options.whole_word)
+}
+
+pub fn clear_highlights(app: &mut Application) -> Result {
+    app.highlight_search_results = false;
 
     Ok(())
 }
@@ -63,6 +125,7 @@ pub fn accept_query(app: &mut Application) -> Result {
 pub fn clear_query(app: &mut Application) -> Result {
     if let Mode::Search(ref mut mode) = app.mode {
         mode.input = None;
+        mode.results = None;
         app.search_query = None;
     } else {
         bail!("Can't clear search outside of search mode");
@@ -86,7 +149,7 @@ pub fn push_search_char(app: &mut Application) -> Result {
         bail!("Last key press wasn't a character")
     }
 
-    Ok(())
+    update_results(app)
 }
 
 pub fn pop_search_char(app: &mut Application) -> Result {
@@ -99,7 +162,7 @@ pub fn pop_search_char(app: &mut Application) -> Result {
         bail!("Can't pop search character outside of search mode");
     };
 
-    Ok(())
+    update_results(app)
 }
 
 pub fn run(app: &mut Application) -> Result {
@@ -115,6 +178,39 @@ pub fn run(app: &mut Application) -> Result {
     move_to_current_result(app)
 }
 
+// Applies a change to the current search options, keeping them around
+// at the application level so that they outlive the search mode instance.
+fn toggle_option<F>(app: &mut Application, toggle: F) -> Result
+    where F: FnOnce(&mut SearchOptions)
+{
+    if let Mode::Search(ref mut mode) = app.mode {
+        toggle(&mut mode.options);
+        app.search_options = mode.options;
+    } else {
+        bail!("Can't toggle search options outside of search mode");
+    }
+
+    update_results(app)
+}
+
+// Searches using the query as it's being entered, selecting the result
+// that would be moved to if the query were accepted. Unlike a regular
+// search, this doesn't move the cursor or report errors, which are
+// expected of incomplete queries (e.g. an unterminated regex group).
+fn update_results(app: &mut Application) -> Result {
+    if let Mode::Search(ref mut mode) = app.mode {
+        let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+        if mode.search(buffer).is_err() {
+            mode.results = None;
+            return Ok(());
+        }
+    } else {
+        bail!("Can't update search results outside of search mode");
+    }
+
+    select_closest_result(app)
+}
+
 fn select_closest_result(app: &mut Application) -> Result {
     if let Mode::Search(ref mut mode) = app.mode {
         let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
@@ -138,8 +234,10 @@ mod tests {
     use scribe::Buffer;
     use scribe::buffer::Position;
     use crate::models::Application;
-    use crate::models::application::Mode;
+    use crate::models::application::{History, Mode};
+    use crate::models::application::modes::SearchOptions;
     use crate::commands;
+    use crate::input::Key;
 
     #[test]
     fn move_to_previous_result_moves_cursor_to_previous_result() {
@@ -286,4 +384,180 @@ mod tests {
                        offset: 0,
                    });
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn accept_query_keeps_insert_sub_mode_when_query_is_an_invalid_regex() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("amp editor");
+        app.workspace.add_buffer(buffer);
+
+        app.search_query = Some(String::from("(ed"));
+        app.search_options.regex = true;
+        commands::application::switch_to_search_mode(&mut app).unwrap();
+
+        assert!(commands::search::accept_query(&mut app).is_err());
+        assert!(match app.mode {
+            Mode::Search(ref mode) => mode.insert_mode(),
+            _ => false,
+        });
+    }
+
+    #[test]
+    fn move_to_next_result_moves_cursor_to_next_regex_result() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("amp editor\nedit\nediting");
+        app.workspace.add_buffer(buffer);
+
+        app.search_query = Some(String::from(r"edit\w+"));
+        app.search_options.regex = true;
+        commands::application::switch_to_search_mode(&mut app).unwrap();
+        commands::search::accept_query(&mut app).unwrap();
+        commands::search::move_to_next_result(&mut app).unwrap();
+
+        assert_eq!(*app.workspace.current_buffer().unwrap().cursor,
+                   Position {
+                       line: 2,
+                       offset: 0,
+                   });
+    }
+
+    #[test]
+    fn toggle_regex_updates_mode_and_application_options() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        app.workspace.add_buffer(Buffer::new());
+        app.search_options = SearchOptions::default();
+        commands::application::switch_to_search_mode(&mut app).unwrap();
+
+        commands::search::toggle_regex(&mut app).unwrap();
+        assert!(app.search_options.regex);
+        assert!(match app.mode {
+            Mode::Search(ref mode) => mode.options.regex,
+            _ => false,
+        });
+
+        commands::search::toggle_regex(&mut app).unwrap();
+        assert!(!app.search_options.regex);
+    }
+
+    #[test]
+    fn toggle_commands_update_their_respective_options() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        app.workspace.add_buffer(Buffer::new());
+        app.search_options = SearchOptions::default();
+        commands::application::switch_to_search_mode(&mut app).unwrap();
+
+        commands::search::toggle_case_insensitive(&mut app).unwrap();
+        commands::search::toggle_smart_case(&mut app).unwrap();
+        commands::search::toggle_whole_word(&mut app).unwrap();
+
+        let expected_options = SearchOptions {
+            regex: false,
+            case_insensitive: true,
+            smart_case: true,
+            whole_word: true,
+        };
+        assert_eq!(app.search_options, expected_options);
+        assert!(match app.mode {
+            Mode::Search(ref mode) => mode.options == expected_options,
+            _ => false,
+        });
+    }
+
+    #[test]
+    fn push_search_char_updates_results_without_moving_cursor() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("amp editor\nedit\nedit");
+        app.workspace.add_buffer(buffer);
+        app.search_options = SearchOptions::default();
+
+        commands::application::switch_to_search_mode(&mut app).unwrap();
+        commands::search::clear_query(&mut app).unwrap();
+        for c in "ed".chars() {
+            app.view.last_key = Some(Key::Char(c));
+            commands::search::push_search_char(&mut app).unwrap();
+        }
+
+        if let Mode::Search(ref mode) = app.mode {
+            let results = mode.results.as_ref().unwrap();
+            assert_eq!(results.len(), 3);
+            assert_eq!(results.selected_index(), 0);
+        } else {
+            panic!("Not in search mode");
+        }
+        assert_eq!(*app.workspace.current_buffer().unwrap().cursor,
+                   Position{ line: 0, offset: 0 });
+    }
+
+    #[test]
+    fn push_search_char_clears_results_for_incomplete_regex_queries() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("(amp)");
+        app.workspace.add_buffer(buffer);
+        app.search_query = Some(String::from("amp"));
+        app.search_options = SearchOptions { regex: true, ..Default::default() };
+
+        commands::application::switch_to_search_mode(&mut app).unwrap();
+        app.view.last_key = Some(Key::Char('('));
+        commands::search::push_search_char(&mut app).unwrap();
+
+        assert!(match app.mode {
+            Mode::Search(ref mode) => mode.results.is_none(),
+            _ => false,
+        });
+    }
+
+    #[test]
+    fn accept_query_enables_highlighting_until_cleared() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("amp editor");
+        app.workspace.add_buffer(buffer);
+
+        app.search_query = Some(String::from("ed"));
+        commands::application::switch_to_search_mode(&mut app).unwrap();
+        commands::search::accept_query(&mut app).unwrap();
+        assert!(app.highlight_search_results);
+
+        commands::search::clear_highlights(&mut app).unwrap();
+        assert!(!app.highlight_search_results);
+    }
+
+    #[test]
+    fn history_entries_can_be_browsed_and_include_accepted_queries() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("amp editor");
+        app.workspace.add_buffer(buffer);
+        app.history = History::new(None);
+        app.search_options = SearchOptions::default();
+
+        for query in &["amp", "editor"] {
+            app.search_query = Some(query.to_string());
+            commands::application::switch_to_search_mode(&mut app).unwrap();
+            commands::search::accept_query(&mut app).unwrap();
+        }
+
+        // Start a new query, and browse back through previous ones.
+        commands::application::switch_to_search_mode(&mut app).unwrap();
+        commands::search::clear_query(&mut app).unwrap();
+        app.view.last_key = Some(Key::Char('e'));
+        commands::search::push_search_char(&mut app).unwrap();
+        commands::search::previous_history_entry(&mut app).unwrap();
+        commands::search::previous_history_entry(&mut app).unwrap();
+        assert_eq!(app.search_query, Some(String::from("amp")));
+
+        // Browse forward beyond the newest entry, restoring the original query.
+        commands::search::next_history_entry(&mut app).unwrap();
+        assert_eq!(app.search_query, Some(String::from("editor")));
+        commands::search::next_history_entry(&mut app).unwrap();
+        assert_eq!(app.search_query, Some(String::from("e")));
+        assert!(match app.mode {
+            Mode::Search(ref mode) => mode.results.as_ref().unwrap().len() == 1,
+            _ => false,
+        });
+    }
+}
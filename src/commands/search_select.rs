@@ -3,15 +3,17 @@ use crate::errors::*;
 use crate::commands::{self, application, Result};
 use crate::input::Key;
 use std::mem;
+use std::path::Path;
 use crate::models::application::modes::open::DisplayablePath;
 use crate::models::application::{Application, Mode};
-use crate::models::application::modes::SearchSelectMode;
+use crate::models::application::modes::{ClipboardEntry, SearchSelectMode};
 
 pub fn accept(app: &mut Application) -> Result {
     // Consume the application mode. This is necessary because the selection in
     // command mode needs to run against the application, but we can't hold the
     // reference to the selection and lend the app mutably to it at the time.
     let mut app_mode = mem::replace(&mut app.mode, Mode::Normal);
+    let mut restore_error = None;
 
     match app_mode {
         Mode::Command(ref mode) => {
@@ -20,30 +22,22 @@ pub fn accept(app: &mut Application) -> Result {
             // Run the selected command.
             (selection.command)(app)?;
         },
+        Mode::Grep(ref mut mode) => {
+            let result = mode
+                .selection()
+                .ok_or("Couldn't find a selected line to open")?;
+            restore_error = open_buffer(app, &result.path)?;
+
+            let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+            if !buffer.cursor.move_to(result.position) {
+                bail!("Couldn't move to the selected line's position");
+            }
+        },
         Mode::Open(ref mut mode) => {
             let &DisplayablePath(ref path) = mode
                 .selection()
                 .ok_or("Couldn't find a selected path to open")?;
-
-            let syntax_definition =
-                app.preferences.borrow().syntax_definition_name(&path).and_then(|name| {
-                    app.workspace.syntax_set.find_syntax_by_name(&name).cloned()
-                });
-
-            app.workspace
-                .open_buffer(&path)
-                .chain_err(|| "Couldn't open a buffer for the specified path.")?;
-
-            let buffer = app.workspace.current_buffer().unwrap();
-
-            // Only override the default syntax definition if the user provided
-            // a valid one in their preferences.
-            if syntax_definition.is_some() {
-                buffer.syntax_definition = syntax_definition;
-            }
-
-            app.view.initialize_buffer(buffer)?;
-
+            restore_error = open_buffer(app, path)?;
         },
         Mode::Theme(ref mut mode) => {
             let theme_key = mode.selection().ok_or("No theme selected")?;
@@ -69,21 +63,68 @@ pub fn accept(app: &mut Application) -> Result {
             let mut buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
             buffer.syntax_definition = syntax;
         },
+        Mode::ClipboardHistory(ref mut mode) => {
+            let ClipboardEntry(content) = mode.selection().ok_or("No clipboard entry selected")?;
+            commands::buffer::paste_content(app, content)?;
+        },
+        Mode::Snippet(ref mut mode) => {
+            let snippet = mode.selection().ok_or("No snippet selected")?;
+            application::switch_to_insert_mode(app)?;
+            commands::snippet::insert(app, snippet)?;
+        },
         _ => bail!("Can't accept selection outside of search select mode."),
     }
 
     commands::view::scroll_cursor_to_center(app).ok();
 
-    Ok(())
+    match restore_error {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+// Opens a buffer for the specified path, applying any
+// user-defined syntax definition for the file type. A history
+// that can't be restored shouldn't stop the file from opening,
+// so its error is returned once the buffer has been opened.
+fn open_buffer(app: &mut Application, path: &Path) -> crate::errors::Result<Option<Error>> {
+    let syntax_definition =
+        app.preferences.borrow().syntax_definition_name(&path).and_then(|name| {
+            app.workspace.syntax_set.find_syntax_by_name(&name).cloned()
+        });
+
+    let already_open = app.workspace.contains_buffer_with_path(path);
+    app.workspace
+        .open_buffer(&path)
+        .chain_err(|| "Couldn't open a buffer for the specified path.")?;
+
+    let buffer = app.workspace.current_buffer().unwrap();
+    let mut restore_error = None;
+    if !already_open {
+        restore_error = app.undo_history.restore(buffer).err();
+    }
+
+    // Only override the default syntax definition if the user provided
+    // a valid one in their preferences.
+    if syntax_definition.is_some() {
+        buffer.syntax_definition = syntax_definition;
+    }
+
+    app.view.initialize_buffer(buffer)?;
+
+    Ok(restore_error)
 }
 
 pub fn search(app: &mut Application) -> Result {
     match app.mode {
         Mode::Command(ref mut mode) => mode.search(),
+        Mode::Grep(ref mut mode) => mode.search(),
+        Mode::ClipboardHistory(ref mut mode) => mode.search(),
         Mode::Open(ref mut mode) => mode.search(),
         Mode::Theme(ref mut mode) => mode.search(),
         Mode::SymbolJump(ref mut mode) => mode.search(),
         Mode::Syntax(ref mut mode) => mode.search(),
+        Mode::Snippet(ref mut mode) => mode.search(),
         _ => bail!("Can't search outside of search select mode."),
     };
 
@@ -93,10 +134,13 @@ pub fn search(app: &mut Application) -> Result {
 pub fn select_next(app: &mut Application) -> Result {
     match app.mode {
         Mode::Command(ref mut mode) => mode.select_next(),
+        Mode::Grep(ref mut mode) => mode.select_next(),
+        Mode::ClipboardHistory(ref mut mode) => mode.select_next(),
         Mode::Open(ref mut mode) => mode.select_next(),
         Mode::Theme(ref mut mode) => mode.select_next(),
         Mode::SymbolJump(ref mut mode) => mode.select_next(),
         Mode::Syntax(ref mut mode) => mode.select_next(),
+        Mode::Snippet(ref mut mode) => mode.select_next(),
         _ => bail!("Can't change selection outside of search select mode."),
     }
 
@@ -106,10 +150,13 @@ pub fn select_next(app: &mut Application) -> Result {
 pub fn select_previous(app: &mut Application) -> Result {
     match app.mode {
         Mode::Command(ref mut mode) => mode.select_previous(),
+        Mode::Grep(ref mut mode) => mode.select_previous(),
+        Mode::ClipboardHistory(ref mut mode) => mode.select_previous(),
         Mode::Open(ref mut mode) => mode.select_previous(),
         Mode::Theme(ref mut mode) => mode.select_previous(),
         Mode::SymbolJump(ref mut mode) => mode.select_previous(),
         Mode::Syntax(ref mut mode) => mode.select_previous(),
+        Mode::Snippet(ref mut mode) => mode.select_previous(),
         _ => bail!("Can't change selection outside of search select mode."),
     }
 
@@ -119,10 +166,13 @@ pub fn select_previous(app: &mut Application) -> Result {
 pub fn enable_insert(app: &mut Application) -> Result {
     match app.mode {
         Mode::Command(ref mut mode) => mode.set_insert_mode(true),
+        Mode::Grep(ref mut mode) => mode.set_insert_mode(true),
+        Mode::ClipboardHistory(ref mut mode) => mode.set_insert_mode(true),
         Mode::Open(ref mut mode) => mode.set_insert_mode(true),
         Mode::Theme(ref mut mode) => mode.set_insert_mode(true),
         Mode::SymbolJump(ref mut mode) => mode.set_insert_mode(true),
         Mode::Syntax(ref mut mode) => mode.set_insert_mode(true),
+        Mode::Snippet(ref mut mode) => mode.set_insert_mode(true),
         _ => bail!("Can't change search insert state outside of search select mode"),
     }
 
@@ -132,10 +182,13 @@ pub fn enable_insert(app: &mut Application) -> Result {
 pub fn disable_insert(app: &mut Application) -> Result {
     match app.mode {
         Mode::Command(ref mut mode) => mode.set_insert_mode(false),
+        Mode::Grep(ref mut mode) => mode.set_insert_mode(false),
+        Mode::ClipboardHistory(ref mut mode) => mode.set_insert_mode(false),
         Mode::Open(ref mut mode) => mode.set_insert_mode(false),
         Mode::Theme(ref mut mode) => mode.set_insert_mode(false),
         Mode::SymbolJump(ref mut mode) => mode.set_insert_mode(false),
         Mode::Syntax(ref mut mode) => mode.set_insert_mode(false),
+        Mode::Snippet(ref mut mode) => mode.set_insert_mode(false),
         _ => bail!("Can't change search insert state outside of search select mode"),
     }
 
@@ -146,10 +199,13 @@ pub fn push_search_char(app: &mut Application) -> Result {
     if let Some(Key::Char(c)) = *app.view.last_key() {
         match app.mode {
             Mode::Command(ref mut mode) => mode.push_search_char(c),
+            Mode::Grep(ref mut mode) => mode.push_search_char(c),
+            Mode::ClipboardHistory(ref mut mode) => mode.push_search_char(c),
             Mode::Open(ref mut mode) => mode.push_search_char(c),
             Mode::Theme(ref mut mode) => mode.push_search_char(c),
             Mode::SymbolJump(ref mut mode) => mode.push_search_char(c),
             Mode::Syntax(ref mut mode) => mode.push_search_char(c),
+            Mode::Snippet(ref mut mode) => mode.push_search_char(c),
             _ => bail!("Can't push search character outside of search select mode"),
         }
     }
@@ -161,10 +217,13 @@ pub fn push_search_char(app: &mut Application) -> Result {
 pub fn pop_search_token(app: &mut Application) -> Result {
     match app.mode {
         Mode::Command(ref mut mode) => mode.pop_search_token(),
+        Mode::Grep(ref mut mode) => mode.pop_search_token(),
+        Mode::ClipboardHistory(ref mut mode) => mode.pop_search_token(),
         Mode::Open(ref mut mode) => mode.pop_search_token(),
         Mode::Theme(ref mut mode) => mode.pop_search_token(),
         Mode::SymbolJump(ref mut mode) => mode.pop_search_token(),
         Mode::Syntax(ref mut mode) => mode.pop_search_token(),
+        Mode::Snippet(ref mut mode) => mode.pop_search_token(),
         _ => bail!("Can't pop search token outside of search select mode"),
     }
 
@@ -175,10 +234,13 @@ pub fn pop_search_token(app: &mut Application) -> Result {
 pub fn step_back(app: &mut Application) -> Result {
     let result_count = match app.mode {
         Mode::Command(ref mut mode) => mode.results().count(),
+        Mode::Grep(ref mut mode) => mode.results().count(),
+        Mode::ClipboardHistory(ref mut mode) => mode.results().count(),
         Mode::Open(ref mut mode) => mode.results().count(),
         Mode::Theme(ref mut mode) => mode.results().count(),
         Mode::SymbolJump(ref mut mode) => mode.results().count(),
         Mode::Syntax(ref mut mode) => mode.results().count(),
+        Mode::Snippet(ref mut mode) => mode.results().count(),
         _ => bail!("Can't pop search token outside of search select mode"),
     };
 
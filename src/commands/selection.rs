@@ -1,5 +1,7 @@
 
 use crate::models::application::{Application, ClipboardContent, Mode};
+use crate::models::application::modes::AlignMode;
+use scribe::Buffer;
 use scribe::buffer::{LineRange, Range};
 use super::application;
 use crate::errors::*;
@@ -8,6 +10,23 @@ use crate::util;
 use crate::util::reflow::Reflow;
 
 pub fn delete(app: &mut Application) -> Result {
+    if let Mode::SelectBlock(ref mode) = app.mode {
+        let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+        let ranges = mode.ranges(&buffer.data(), &buffer.cursor);
+
+        // Delete from the bottom up, so that earlier ranges aren't affected.
+        buffer.start_operation_group();
+        for range in ranges.iter().rev() {
+            buffer.delete_range(range.clone());
+        }
+        buffer.end_operation_group();
+        if let Some(range) = ranges.first() {
+            buffer.cursor.move_to(range.start());
+        }
+
+        return Ok(());
+    }
+
     let rng = sel_to_range(app)?;
     let buf = app.workspace.current_buffer().unwrap();
     buf.delete_range(rng.clone());
@@ -23,6 +42,26 @@ pub fn copy_and_delete(app: &mut Application) -> Result {
 
 pub fn change(app: &mut Application) -> Result {
     let _ = copy_to_clipboard(app);
+
+    // Rectangular selections are changed using a cursor on each of
+    // their lines, so that the replacement is entered on all of them.
+    if let Mode::SelectBlock(ref mode) = app.mode {
+        let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+        // Lines that end before the rectangle are left alone.
+        let ranges: Vec<Range> = mode.ranges(&buffer.data(), &buffer.cursor)
+            .into_iter()
+            .filter(|range| range.start() != range.end())
+            .collect();
+        let first_range = ranges.first().ok_or("The selection doesn't include any characters")?;
+        buffer.cursor.move_to(first_range.start());
+        app.cursors.set(buffer, ranges);
+
+        buffer.start_operation_group();
+        app.cursors.edit(buffer, |_, selection| Some((selection.clone(), String::new())));
+        application::switch_to_insert_mode(app)?;
+        return commands::view::scroll_to_cursor(app);
+    }
+
     delete(app)?;
     application::switch_to_insert_mode(app)?;
     commands::view::scroll_to_cursor(app)
@@ -51,15 +90,23 @@ pub fn select_all(app: &mut Application) -> Result {
 
 fn copy_to_clipboard(app: &mut Application) -> Result {
     let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let content = selected_content(buffer, &app.mode)?;
+    app.clipboard.set_content(content)?;
 
-    match app.mode {
+    Ok(())
+}
+
+/// Reads the current selection, qualified as inline, block, or rectangle
+/// content based on the select mode it was captured in.
+pub fn selected_content(buffer: &mut Buffer, mode: &Mode) -> std::result::Result<ClipboardContent, Error> {
+    match *mode {
         Mode::Select(ref select_mode) => {
             let cursor_position = *buffer.cursor.clone();
             let selected_range = Range::new(cursor_position, select_mode.anchor);
 
             let data = buffer.read(&selected_range.clone())
                 .ok_or("Couldn't read selected data from buffer")?;
-            app.clipboard.set_content(ClipboardContent::Inline(data))?;
+            Ok(ClipboardContent::Inline(data))
         }
         Mode::SelectLine(ref mode) => {
             let selected_range = util::inclusive_range(
@@ -73,12 +120,18 @@ fn copy_to_clipboard(app: &mut Application) -> Result {
 
             let data = buffer.read(&selected_range.clone())
                 .ok_or("Couldn't read selected data from buffer")?;
-            app.clipboard.set_content(ClipboardContent::Block(data))?;
+            Ok(ClipboardContent::Block(data))
         }
-        _ => bail!("Can't copy data to clipboard outside of select modes"),
-    };
+        Mode::SelectBlock(ref mode) => {
+            let lines: Vec<String> = mode.ranges(&buffer.data(), &buffer.cursor)
+                .iter()
+                .map(|range| buffer.read(range).unwrap_or_default())
+                .collect();
 
-    Ok(())
+            Ok(ClipboardContent::Rectangle(lines.join("\n")))
+        }
+        _ => bail!("Can't copy data to clipboard outside of select modes"),
+    }
 }
 
 pub fn justify(app: &mut Application) -> Result {
@@ -97,6 +150,21 @@ pub fn justify(app: &mut Application) -> Result {
     Ok(())
 }
 
+/// Prompts for a delimiter to align the selected lines on.
+pub fn align(app: &mut Application) -> Result {
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let (start_line, end_line) = match app.mode {
+        Mode::SelectLine(ref mode) => (
+            mode.anchor.min(buffer.cursor.line),
+            mode.anchor.max(buffer.cursor.line),
+        ),
+        _ => bail!("Aligning requires a line selection."),
+    };
+    app.mode = Mode::Align(AlignMode::new(start_line, end_line));
+
+    Ok(())
+}
+
 fn sel_to_range(app: &mut Application) -> std::result::Result<Range, Error> {
     let buf = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
 
@@ -130,7 +198,8 @@ fn sel_to_range(app: &mut Application) -> std::result::Result<Range, Error> {
 #[cfg(test)]
 mod tests {
     use crate::commands;
-    use crate::models::application::{Application, Mode};
+    use crate::input::Key;
+    use crate::models::application::{Application, ClipboardContent, Mode};
     use scribe::Buffer;
     use scribe::buffer::Position;
 
@@ -219,6 +288,43 @@ mod tests {
         )
     }
 
+    #[test]
+    fn copy_and_delete_removes_the_rectangle_in_select_block_mode() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("amp editor\nscribe\nx");
+        buffer.cursor.move_to(Position{ line: 0, offset: 1 });
+        app.workspace.add_buffer(buffer);
+        commands::application::switch_to_select_block_mode(&mut app).unwrap();
+        app.workspace.current_buffer().unwrap().cursor.move_to(Position{ line: 1, offset: 3 });
+
+        let buffer = app.workspace.current_buffer().unwrap();
+        let content = super::selected_content(buffer, &app.mode).unwrap();
+        assert_eq!(content, ClipboardContent::Rectangle(String::from("mp \ncri")));
+
+        super::delete(&mut app).unwrap();
+        let buffer = app.workspace.current_buffer().unwrap();
+        assert_eq!(buffer.data(), "aeditor\nsbe\nx");
+        assert_eq!(*buffer.cursor, Position{ line: 0, offset: 1 });
+    }
+
+    #[test]
+    fn change_skips_lines_ending_before_the_rectangle_in_select_block_mode() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("amp editor\nab\nscribe");
+        buffer.cursor.move_to(Position{ line: 0, offset: 3 });
+        app.workspace.add_buffer(buffer);
+        commands::application::switch_to_select_block_mode(&mut app).unwrap();
+        app.workspace.current_buffer().unwrap().cursor.move_to(Position{ line: 2, offset: 5 });
+
+        super::change(&mut app).unwrap();
+        app.view.last_key = Some(Key::Char('X'));
+        commands::buffer::insert_char(&mut app).unwrap();
+
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "ampXitor\nab\nscrX");
+    }
+
     #[test]
     fn delete_removes_the_current_result_in_search_mode() {
         let mut app = Application::new(&Vec::new()).unwrap();
@@ -0,0 +1,316 @@
+use crate::errors::*;
+use crate::commands::{self, Result};
+use crate::input::Key;
+use crate::models::application::{Application, Mode, Snippet};
+use crate::models::application::modes::TabStopMode;
+use scribe::buffer::{Position, Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Replaces the snippet trigger preceding the cursor with its snippet,
+/// using the longest trigger defined for the current buffer's syntax.
+pub fn expand(app: &mut Application) -> Result {
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let syntax_name = buffer.syntax_definition
+        .as_ref()
+        .map(|syntax| syntax.name.clone())
+        .ok_or("The current buffer doesn't have a syntax")?;
+    let cursor = *buffer.cursor;
+    let data = buffer.data();
+    let preceding: String = data
+        .lines()
+        .nth(cursor.line)
+        .unwrap_or("")
+        .graphemes(true)
+        .take(cursor.offset)
+        .collect();
+
+    let snippet = Snippet::load(&syntax_name)?
+        .into_iter()
+        .filter(|snippet| is_trigger(&preceding, &snippet.trigger))
+        .max_by_key(|snippet| snippet.trigger.len())
+        .ok_or_else(|| format!("No {} snippet trigger found before the cursor", syntax_name))?;
+
+    let start = Position{
+        line: cursor.line,
+        offset: cursor.offset - snippet.trigger.graphemes(true).count(),
+    };
+    buffer.delete_range(Range::new(start, cursor));
+    buffer.cursor.move_to(start);
+
+    insert(app, &snippet)
+}
+
+/// Inserts a snippet at the cursor, indenting its lines to match the
+/// current one, and visits its first tab stop (if it has any).
+pub fn insert(app: &mut Application, snippet: &Snippet) -> Result {
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    if app.cursors.active(buffer) {
+        bail!("Snippets can't be inserted with multiple cursors");
+    }
+
+    let start = *buffer.cursor;
+    let data = buffer.data();
+    let indent: String = data
+        .lines()
+        .nth(start.line)
+        .unwrap_or("")
+        .graphemes(true)
+        .take(start.offset)
+        .take_while(|grapheme| grapheme.trim().is_empty())
+        .collect();
+    buffer.insert(snippet.content.replace('\n', &format!("\n{}", indent)));
+
+    // Maps a byte index in the snippet's content to its inserted position.
+    let position = |index: usize| -> Position {
+        let preceding = &snippet.content[..index];
+
+        match preceding.rfind('\n') {
+            Some(line_start) => Position{
+                line: start.line + preceding.matches('\n').count(),
+                offset: indent.graphemes(true).count() +
+                    preceding[line_start + 1..].graphemes(true).count(),
+            },
+            None => Position{
+                line: start.line,
+                offset: start.offset + preceding.graphemes(true).count(),
+            },
+        }
+    };
+
+    let mut numbers: Vec<usize> = snippet.placeholders
+        .iter()
+        .map(|placeholder| placeholder.number)
+        .filter(|number| *number != 0)
+        .collect();
+    numbers.sort();
+    numbers.dedup();
+
+    let mut tab_stops: Vec<Vec<Range>> = numbers
+        .iter()
+        .map(|number| {
+            snippet.placeholders
+                .iter()
+                .filter(|placeholder| placeholder.number == *number)
+                .map(|placeholder| Range::new(position(placeholder.start), position(placeholder.end)))
+                .collect()
+        })
+        .collect();
+
+    // Finish at the $0 placeholder, falling back to the end of the snippet.
+    let end = snippet.placeholders
+        .iter()
+        .find(|placeholder| placeholder.number == 0)
+        .map(|placeholder| position(placeholder.start))
+        .unwrap_or_else(|| position(snippet.content.len()));
+    tab_stops.push(vec![Range::new(end, end)]);
+
+    let mode = TabStopMode::new(tab_stops);
+    let range = mode.ranges().first().cloned().ok_or("Snippet has no tab stops")?;
+    buffer.cursor.move_to(range.end());
+    app.mode = if mode.is_final() {
+        Mode::Insert
+    } else {
+        Mode::TabStop(mode)
+    };
+
+    commands::view::scroll_to_cursor(app)
+}
+
+pub fn next_tab_stop(app: &mut Application) -> Result {
+    if let Mode::TabStop(ref mut mode) = app.mode {
+        mode.select_next();
+    }
+
+    select_tab_stop(app)
+}
+
+pub fn previous_tab_stop(app: &mut Application) -> Result {
+    if let Mode::TabStop(ref mut mode) = app.mode {
+        mode.select_previous();
+    }
+
+    select_tab_stop(app)
+}
+
+/// Stops visiting tab stops, leaving the cursor where it is. This doesn't
+/// use application::switch_to_insert_mode, which would otherwise start a
+/// new command group, splitting the insertion's undo history.
+pub fn finish(app: &mut Application) -> Result {
+    app.mode = Mode::Insert;
+
+    Ok(())
+}
+
+pub fn insert_char(app: &mut Application) -> Result {
+    let character = match *app.view.last_key() {
+        Some(Key::Char(character)) => character,
+        _ => bail!("No character to insert"),
+    };
+
+    edit_tab_stop(app, |mut preceding, following| {
+        preceding.push(character);
+        (preceding, following)
+    })
+}
+
+pub fn backspace(app: &mut Application) -> Result {
+    edit_tab_stop(app, |preceding, following| {
+        let mut graphemes: Vec<&str> = preceding.graphemes(true).collect();
+        graphemes.pop();
+        (graphemes.concat(), following)
+    })
+}
+
+// Moves the cursor to the end of the selected tab stop's placeholder,
+// returning to insert mode once the last tab stop has been reached.
+fn select_tab_stop(app: &mut Application) -> Result {
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let finished = match app.mode {
+        Mode::TabStop(ref mode) => {
+            if let Some(range) = mode.ranges().first() {
+                buffer.cursor.move_to(range.end());
+            }
+
+            mode.is_final()
+        },
+        _ => bail!("Not visiting snippet tab stops"),
+    };
+
+    if finished {
+        app.mode = Mode::Insert;
+    }
+
+    commands::view::scroll_to_cursor(app)
+}
+
+// Applies an edit to the current tab stop's placeholder, which is split at
+// the cursor, and copies the result to its mirrors. A placeholder holding
+// its default content is replaced entirely.
+fn edit_tab_stop<F>(app: &mut Application, edit: F) -> Result
+    where F: FnOnce(String, String) -> (String, String)
+{
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let mode = match app.mode {
+        Mode::TabStop(ref mut mode) => mode,
+        _ => bail!("Not visiting snippet tab stops"),
+    };
+    let placeholder = mode.ranges().first().cloned().ok_or("No tab stop selected")?;
+    let cursor = *buffer.cursor;
+    if cursor.line != placeholder.start().line ||
+        cursor.offset < placeholder.start().offset ||
+        cursor.offset > placeholder.end().offset {
+        bail!("The cursor isn't within the current tab stop");
+    }
+
+    let (preceding, following) = if mode.pristine {
+        (String::new(), String::new())
+    } else {
+        let split = Position{ line: cursor.line, offset: cursor.offset };
+        (
+            buffer.read(&Range::new(placeholder.start(), split)).unwrap_or_default(),
+            buffer.read(&Range::new(split, placeholder.end())).unwrap_or_default(),
+        )
+    };
+    let (preceding, following) = edit(preceding, following);
+    let content = format!("{}{}", preceding, following);
+    let length = content.graphemes(true).count();
+
+    for index in 0..mode.ranges().len() {
+        let range = mode.ranges()[index].clone();
+        buffer.delete_range(range.clone());
+        buffer.cursor.move_to(range.start());
+        buffer.insert(content.as_str());
+        mode.resize_range(index, length);
+    }
+    mode.pristine = false;
+
+    buffer.cursor.move_to(Position{
+        line: placeholder.start().line,
+        offset: placeholder.start().offset + preceding.graphemes(true).count(),
+    });
+
+    commands::view::scroll_to_cursor(app)
+}
+
+// Whether the text preceding the cursor ends with the trigger,
+// without it being the tail end of a longer identifier.
+fn is_trigger(preceding: &str, trigger: &str) -> bool {
+    if trigger.is_empty() || !preceding.ends_with(trigger) {
+        return false;
+    }
+
+    let is_identifier = |c: char| c.is_alphanumeric() || c == '_';
+    let previous = preceding[..preceding.len() - trigger.len()].chars().last();
+    let first = trigger.chars().next();
+
+    match (previous, first) {
+        (Some(previous), Some(first)) => !(is_identifier(previous) && is_identifier(first)),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands;
+    use crate::input::Key;
+    use crate::models::application::{Application, Mode, Snippet};
+
+    fn snippet_app(data: &str) -> Application {
+        let mut app = commands::app_with_buffer(data, scribe::buffer::Position{ line: 0, offset: data.len() });
+        commands::application::switch_to_insert_mode(&mut app).unwrap();
+
+        app
+    }
+
+    fn type_text(app: &mut Application, text: &str) {
+        for character in text.chars() {
+            app.view.last_key = Some(Key::Char(character));
+            super::insert_char(app).unwrap();
+        }
+    }
+
+    #[test]
+    fn insert_indents_snippet_and_updates_mirrors() {
+        let mut app = snippet_app("    ");
+        let snippet = Snippet::parse("fn", "fn ${1:name}(${2}) {\n    $1($0)\n}").unwrap();
+        super::insert(&mut app, &snippet).unwrap();
+
+        type_text(&mut app, "run");
+        super::next_tab_stop(&mut app).unwrap();
+        type_text(&mut app, "x");
+        super::backspace(&mut app).unwrap();
+        type_text(&mut app, "app");
+
+        assert_eq!(
+            app.workspace.current_buffer().unwrap().data(),
+            "    fn run(app) {\n        run()\n    }"
+        );
+
+        super::next_tab_stop(&mut app).unwrap();
+        assert!(matches!(app.mode, Mode::Insert));
+        let buffer = app.workspace.current_buffer().unwrap();
+        assert_eq!(*buffer.cursor, scribe::buffer::Position{ line: 1, offset: 12 });
+    }
+
+    #[test]
+    fn previous_tab_stop_keeps_edited_placeholder_content() {
+        let mut app = snippet_app("");
+        let snippet = Snippet::parse("if", "if ${1:a} == ${2:b} { $1 }").unwrap();
+        super::insert(&mut app, &snippet).unwrap();
+
+        super::next_tab_stop(&mut app).unwrap();
+        type_text(&mut app, "c");
+        super::previous_tab_stop(&mut app).unwrap();
+        type_text(&mut app, "d");
+
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "if d == c { d }");
+    }
+
+    #[test]
+    fn is_trigger_requires_a_word_boundary() {
+        assert!(super::is_trigger("    fn", "fn"));
+        assert!(super::is_trigger("(fn", "fn"));
+        assert!(!super::is_trigger("defn", "fn"));
+        assert!(super::is_trigger("x->", "->"));
+    }
+}
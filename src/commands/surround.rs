@@ -0,0 +1,267 @@
+use crate::errors::*;
+use crate::commands::{self, Result};
+use crate::input::Key;
+use crate::models::application::{Application, Mode};
+use crate::models::application::modes::{SurroundAction, SurroundMode};
+use crate::util::text_object;
+use scribe::buffer::{Position, Range};
+use std::mem;
+
+/// Handles a key press in the surround prompt, which names the delimiter
+/// pair to add, or to change or delete around the cursor. When adding a
+/// pair, `t` prompts for an HTML tag instead, which is otherwise the key
+/// naming the innermost element enclosing the cursor.
+pub fn accept_key(app: &mut Application) -> Result {
+    let key = match *app.view.last_key() {
+        Some(Key::Char(c)) => c,
+        _ => bail!("Last key press wasn't a character"),
+    };
+
+    match app.mode {
+        Mode::Surround(ref mut mode) => {
+            if let Some(ref mut tag) = mode.tag {
+                if key != '>' {
+                    tag.push(key);
+                    return Ok(());
+                }
+            } else if key == 't' && adding(&mode.action) {
+                mode.tag = Some(String::new());
+                return Ok(());
+            }
+        }
+        _ => bail!("Can't accept surround key outside of surround mode"),
+    }
+
+    if key == '>' && tag_pending(app) {
+        return accept_tag(app);
+    }
+
+    match take_action(app)? {
+        SurroundAction::Change(None) => {
+            let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+            match enclosing_delimiters(&buffer.data(), *buffer.cursor, key) {
+                Some(delimiters) => {
+                    app.mode = Mode::Surround(
+                        SurroundMode::new(SurroundAction::Change(Some(delimiters)))
+                    );
+                    Ok(())
+                }
+                None => {
+                    commands::application::switch_to_normal_mode(app)?;
+                    bail!("No surrounding \"{}\" pair found", key);
+                }
+            }
+        }
+        SurroundAction::Delete => {
+            commands::application::switch_to_normal_mode(app)?;
+            let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+            let delimiters = enclosing_delimiters(&buffer.data(), *buffer.cursor, key)
+                .ok_or_else(|| format!("No surrounding \"{}\" pair found", key))?;
+            surround(app, SurroundAction::Change(Some(delimiters)), "", "")
+        }
+        action => {
+            commands::application::switch_to_normal_mode(app)?;
+            let (open, close) = pair(key).ok_or_else(|| format!("\"{}\" can't be used as a delimiter", key))?;
+            surround(app, action, &open, &close)
+        }
+    }
+}
+
+/// Adds or changes to the HTML tag entered in the surround prompt,
+/// with a matching closing tag named after its first word.
+pub fn accept_tag(app: &mut Application) -> Result {
+    let tag = match app.mode {
+        Mode::Surround(ref mut mode) => mode.tag.take().ok_or("No tag has been entered")?,
+        _ => bail!("Can't accept surround tag outside of surround mode"),
+    };
+    let action = take_action(app)?;
+    commands::application::switch_to_normal_mode(app)?;
+
+    let tag = tag.trim();
+    let name = tag.split_whitespace().next().ok_or("Tag name is empty")?;
+    surround(app, action, &format!("<{}>", tag), &format!("</{}>", name))
+}
+
+pub fn backspace(app: &mut Application) -> Result {
+    if let Mode::Surround(ref mut mode) = app.mode {
+        if let Some(ref mut tag) = mode.tag {
+            tag.pop();
+        }
+    }
+
+    Ok(())
+}
+
+// Wraps the range being added to, or replaces the delimiters being changed,
+// as a single undoable operation. The closing delimiter is handled first,
+// so that the position of the opening one isn't affected.
+fn surround(app: &mut Application, action: SurroundAction, open: &str, close: &str) -> Result {
+    let (open_range, close_range) = match action {
+        SurroundAction::Add(range) => (
+            Range::new(range.start(), range.start()),
+            Range::new(range.end(), range.end()),
+        ),
+        SurroundAction::Change(Some(delimiters)) => delimiters,
+        _ => bail!("No delimiters to surround with"),
+    };
+
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    buffer.start_operation_group();
+    for (range, content) in [(close_range, close), (open_range.clone(), open)].iter() {
+        buffer.delete_range(range.clone());
+        if !content.is_empty() {
+            buffer.cursor.move_to(range.start());
+            buffer.insert(*content);
+        }
+    }
+    buffer.end_operation_group();
+    buffer.cursor.move_to(open_range.start());
+
+    commands::view::scroll_to_cursor(app)
+}
+
+// Whether the action's delimiters are yet to be named.
+fn adding(action: &SurroundAction) -> bool {
+    matches!(action, SurroundAction::Add(_) | SurroundAction::Change(Some(_)))
+}
+
+fn tag_pending(app: &Application) -> bool {
+    match app.mode {
+        Mode::Surround(ref mode) => mode.tag.is_some(),
+        _ => false,
+    }
+}
+
+// Leaves surround mode, returning the action it was prompting for.
+fn take_action(app: &mut Application) -> std::result::Result<SurroundAction, Error> {
+    match mem::replace(&mut app.mode, Mode::Normal) {
+        Mode::Surround(mode) => Ok(mode.action),
+        mode => {
+            app.mode = mode;
+            bail!("Can't surround outside of surround mode");
+        }
+    }
+}
+
+// The opening and closing delimiters named by a key. Brackets are named by
+// either of their pair, and other punctuation is used on both sides.
+fn pair(key: char) -> Option<(String, String)> {
+    let (open, close) = match key {
+        '(' | ')' => ('(', ')'),
+        '[' | ']' => ('[', ']'),
+        '{' | '}' => ('{', '}'),
+        '<' | '>' => ('<', '>'),
+        c if c.is_alphanumeric() || c.is_whitespace() => return None,
+        c => (c, c),
+    };
+
+    Some((open.to_string(), close.to_string()))
+}
+
+// The ranges of the opening and closing delimiters of the pair
+// named by a key, which is the closest one enclosing the position.
+fn enclosing_delimiters(data: &str, position: Position, key: char) -> Option<(Range, Range)> {
+    let find = |around| match key {
+        't' => text_object::tag(data, position, around),
+        _ => {
+            let (open, close) = pair(key)?;
+            if open == close {
+                text_object::quoted(data, position, &open, around)
+            } else {
+                text_object::enclosing_pair(data, position, &open, &close, around)
+            }
+        }
+    };
+    let inside = find(false)?;
+    let around = find(true)?;
+
+    Some((
+        Range::new(around.start(), inside.start()),
+        Range::new(inside.end(), around.end()),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::{self, app_with_buffer};
+    use crate::input::Key;
+    use crate::models::Application;
+    use crate::models::application::Mode;
+    use scribe::buffer::Position;
+
+    fn press(app: &mut Application, keys: &str) {
+        for key in keys.chars() {
+            app.view.last_key = Some(Key::Char(key));
+            super::accept_key(app).unwrap();
+        }
+    }
+
+    #[test]
+    fn add_wraps_token_at_cursor_as_one_change() {
+        let mut app = app_with_buffer("let amp = editor;", Position{ line: 0, offset: 12 });
+        commands::application::switch_to_surround_add_mode(&mut app).unwrap();
+        press(&mut app, ")");
+
+        assert!(matches!(app.mode, Mode::Normal));
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "let amp = (editor);");
+
+        commands::buffer::undo(&mut app).unwrap();
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "let amp = editor;");
+    }
+
+    #[test]
+    fn add_wraps_selection_in_tag() {
+        let mut app = app_with_buffer("amp editor", Position{ line: 0, offset: 0 });
+        commands::application::switch_to_select_mode(&mut app).unwrap();
+        commands::cursor::move_to_end_of_line(&mut app).unwrap();
+        commands::application::switch_to_surround_add_mode(&mut app).unwrap();
+        press(&mut app, "tp clas");
+        super::backspace(&mut app).unwrap();
+        press(&mut app, "ss=\"x\"");
+        super::accept_tag(&mut app).unwrap();
+
+        assert!(matches!(app.mode, Mode::Normal));
+        assert_eq!(
+            app.workspace.current_buffer().unwrap().data(),
+            "<p class=\"x\">amp editor</p>"
+        );
+    }
+
+    #[test]
+    fn change_replaces_enclosing_pair_as_one_change() {
+        let mut app = app_with_buffer("f(a, (b), c)", Position{ line: 0, offset: 3 });
+        commands::application::switch_to_surround_change_mode(&mut app).unwrap();
+        press(&mut app, "(");
+        assert!(matches!(app.mode, Mode::Surround(_)));
+        press(&mut app, "t");
+        press(&mut app, "b>");
+
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "f<b>a, (b), c</b>");
+
+        commands::buffer::undo(&mut app).unwrap();
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "f(a, (b), c)");
+    }
+
+    #[test]
+    fn delete_removes_enclosing_quotes_and_tags() {
+        let mut app = app_with_buffer("<p>say \"amp\"</p>", Position{ line: 0, offset: 9 });
+        commands::application::switch_to_surround_delete_mode(&mut app).unwrap();
+        press(&mut app, "\"");
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "<p>say amp</p>");
+
+        commands::application::switch_to_surround_delete_mode(&mut app).unwrap();
+        press(&mut app, "t");
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "say amp");
+    }
+
+    #[test]
+    fn delete_fails_without_an_enclosing_pair() {
+        let mut app = app_with_buffer("amp", Position{ line: 0, offset: 1 });
+        commands::application::switch_to_surround_delete_mode(&mut app).unwrap();
+        app.view.last_key = Some(Key::Char('['));
+
+        assert!(super::accept_key(&mut app).is_err());
+        assert!(matches!(app.mode, Mode::Normal));
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "amp");
+    }
+}
@@ -0,0 +1,233 @@
+use crate::errors::*;
+use crate::commands::{self, Result};
+use crate::models::application::{Application, Mode};
+use crate::models::application::modes::{SelectMode, TextObjectOperation};
+use crate::util::text_object;
+use scribe::buffer::{Position, Range};
+
+pub fn choose_inside(app: &mut Application) -> Result {
+    choose_side(app, false)
+}
+
+pub fn choose_around(app: &mut Application) -> Result {
+    choose_side(app, true)
+}
+
+pub fn choose_delete(app: &mut Application) -> Result {
+    choose_operation(app, TextObjectOperation::Delete)
+}
+
+pub fn choose_change(app: &mut Application) -> Result {
+    choose_operation(app, TextObjectOperation::Change)
+}
+
+pub fn choose_copy(app: &mut Application) -> Result {
+    choose_operation(app, TextObjectOperation::Copy)
+}
+
+pub fn select_inside_parentheses(app: &mut Application) -> Result {
+    select(app, |data, position| text_object::enclosing_pair(data, position, "(", ")", false))
+}
+
+pub fn select_around_parentheses(app: &mut Application) -> Result {
+    select(app, |data, position| text_object::enclosing_pair(data, position, "(", ")", true))
+}
+
+pub fn select_inside_brackets(app: &mut Application) -> Result {
+    select(app, |data, position| text_object::enclosing_pair(data, position, "[", "]", false))
+}
+
+pub fn select_around_brackets(app: &mut Application) -> Result {
+    select(app, |data, position| text_object::enclosing_pair(data, position, "[", "]", true))
+}
+
+pub fn select_inside_braces(app: &mut Application) -> Result {
+    select(app, |data, position| text_object::enclosing_pair(data, position, "{", "}", false))
+}
+
+pub fn select_around_braces(app: &mut Application) -> Result {
+    select(app, |data, position| text_object::enclosing_pair(data, position, "{", "}", true))
+}
+
+pub fn select_inside_double_quotes(app: &mut Application) -> Result {
+    select(app, |data, position| text_object::quoted(data, position, "\"", false))
+}
+
+pub fn select_around_double_quotes(app: &mut Application) -> Result {
+    select(app, |data, position| text_object::quoted(data, position, "\"", true))
+}
+
+pub fn select_inside_single_quotes(app: &mut Application) -> Result {
+    select(app, |data, position| text_object::quoted(data, position, "'", false))
+}
+
+pub fn select_around_single_quotes(app: &mut Application) -> Result {
+    select(app, |data, position| text_object::quoted(data, position, "'", true))
+}
+
+pub fn select_inside_backticks(app: &mut Application) -> Result {
+    select(app, |data, position| text_object::quoted(data, position, "`", false))
+}
+
+pub fn select_around_backticks(app: &mut Application) -> Result {
+    select(app, |data, position| text_object::quoted(data, position, "`", true))
+}
+
+pub fn select_inside_tag(app: &mut Application) -> Result {
+    select(app, |data, position| text_object::tag(data, position, false))
+}
+
+pub fn select_around_tag(app: &mut Application) -> Result {
+    select(app, |data, position| text_object::tag(data, position, true))
+}
+
+pub fn select_inside_paragraph(app: &mut Application) -> Result {
+    select(app, |data, position| text_object::paragraph(data, position, false))
+}
+
+pub fn select_around_paragraph(app: &mut Application) -> Result {
+    select(app, |data, position| text_object::paragraph(data, position, true))
+}
+
+pub fn select_inside_token(app: &mut Application) -> Result {
+    select(app, |data, position| text_object::token(data, position, false))
+}
+
+pub fn select_around_token(app: &mut Application) -> Result {
+    select(app, |data, position| text_object::token(data, position, true))
+}
+
+fn choose_side(app: &mut Application, around: bool) -> Result {
+    if let Mode::TextObject(ref mut mode) = app.mode {
+        mode.around = Some(around);
+    }
+
+    Ok(())
+}
+
+fn choose_operation(app: &mut Application, operation: TextObjectOperation) -> Result {
+    if let Mode::TextObject(ref mut mode) = app.mode {
+        if mode.around.is_none() {
+            mode.operation = operation;
+        }
+    }
+
+    Ok(())
+}
+
+// Switches to select mode, anchored at the start of the text object
+// found at the cursor, with the cursor moved to the end of it. If the
+// text object prompt was given an operation, it's applied to the selection.
+fn select<F>(app: &mut Application, find: F) -> Result
+    where F: Fn(&str, Position) -> Option<Range>
+{
+    let operation = match app.mode {
+        Mode::TextObject(ref mode) => mode.operation,
+        _ => TextObjectOperation::Select,
+    };
+    let buffer = app.workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let range = match find(&buffer.data(), *buffer.cursor) {
+        Some(range) => range,
+        None => {
+            // Leave the text object prompt, if that's where we came from.
+            if let Mode::TextObject(_) = app.mode {
+                app.mode = Mode::Normal;
+            }
+            bail!("No matching text object found at the cursor");
+        }
+    };
+
+    app.mode = Mode::Select(SelectMode::new(range.start()));
+    buffer.cursor.move_to(range.end());
+
+    match operation {
+        TextObjectOperation::Select => (),
+        TextObjectOperation::Delete => {
+            commands::selection::copy_and_delete(app)?;
+            commands::application::switch_to_normal_mode(app)?;
+        }
+        TextObjectOperation::Change => commands::selection::change(app)?,
+        TextObjectOperation::Copy => {
+            commands::selection::copy(app)?;
+            if let Some(buffer) = app.workspace.current_buffer() {
+                buffer.cursor.move_to(range.start());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::{self, app_with_buffer};
+    use crate::input::Key;
+    use crate::models::application::{ClipboardContent, Mode};
+    use scribe::buffer::Position;
+
+    #[test]
+    fn text_objects_can_be_changed_from_normal_mode() {
+        let mut app = app_with_buffer("amp(editor, 1)", Position{ line: 0, offset: 6 });
+        super::select_inside_parentheses(&mut app).unwrap();
+        commands::selection::change(&mut app).unwrap();
+
+        assert!(matches!(app.mode, Mode::Insert));
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "amp()");
+        assert_eq!(*app.workspace.current_buffer().unwrap().cursor, Position{ line: 0, offset: 4 });
+    }
+
+    #[test]
+    fn text_objects_can_be_picked_from_select_mode() {
+        let mut app = app_with_buffer("let amp = \"editor\";", Position{ line: 0, offset: 13 });
+        for c in "va\"d".chars() {
+            app.view.last_key = Some(Key::Char(c));
+            commands::application::handle_input(&mut app).unwrap();
+        }
+
+        assert!(matches!(app.mode, Mode::Normal));
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "let amp = ;");
+    }
+
+    #[test]
+    fn text_objects_can_be_deleted_from_normal_mode() {
+        let mut app = app_with_buffer("amp(editor, 1)", Position{ line: 0, offset: 6 });
+        for c in "Tdi(".chars() {
+            app.view.last_key = Some(Key::Char(c));
+            commands::application::handle_input(&mut app).unwrap();
+        }
+
+        assert!(matches!(app.mode, Mode::Normal));
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "amp()");
+        assert_eq!(app.clipboard.get_content(), &ClipboardContent::Inline("editor, 1".to_string()));
+    }
+
+    #[test]
+    fn text_objects_can_be_changed_and_copied_from_normal_mode() {
+        let mut app = app_with_buffer("let amp = \"editor\";", Position{ line: 0, offset: 13 });
+        for c in "Tya\"".chars() {
+            app.view.last_key = Some(Key::Char(c));
+            commands::application::handle_input(&mut app).unwrap();
+        }
+
+        assert!(matches!(app.mode, Mode::Normal));
+        assert_eq!(app.clipboard.get_content(), &ClipboardContent::Inline("\"editor\"".to_string()));
+        assert_eq!(*app.workspace.current_buffer().unwrap().cursor, Position{ line: 0, offset: 10 });
+
+        for c in "Tci\"".chars() {
+            app.view.last_key = Some(Key::Char(c));
+            commands::application::handle_input(&mut app).unwrap();
+        }
+
+        assert!(matches!(app.mode, Mode::Insert));
+        assert_eq!(app.workspace.current_buffer().unwrap().data(), "let amp = \"\";");
+    }
+
+    #[test]
+    fn missing_text_objects_leave_the_prompt() {
+        let mut app = app_with_buffer("amp", Position{ line: 0, offset: 1 });
+        commands::application::switch_to_text_object_inside_mode(&mut app).unwrap();
+
+        assert!(super::select_inside_braces(&mut app).is_err());
+        assert!(matches!(app.mode, Mode::Normal));
+    }
+}
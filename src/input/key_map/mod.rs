@@ -7,8 +7,13 @@ use std::ops::{Deref, DerefMut};
 use std::convert::Into;
 use crate::yaml::yaml::{Hash, Yaml, YamlLoader};
 
-/// Nested HashMap newtype that provides a more ergonomic interface.
-pub struct KeyMap(HashMap<String, HashMap<Key, SmallVec<[Command; 4]>>>);
+/// Nested HashMap wrapper that provides a more ergonomic interface.
+/// Keys bound to macros are tracked separately, by name, and are
+/// bound to the command that looks up and replays them.
+pub struct KeyMap {
+    bindings: HashMap<String, HashMap<Key, SmallVec<[Command; 4]>>>,
+    macros: HashMap<String, HashMap<Key, String>>,
+}
 
 impl KeyMap {
     /// Parses a Yaml tree of modes and their keybindings into a complete keymap.
@@ -22,21 +27,29 @@ impl KeyMap {
     ///
     ///   "normal" => { Key::Char('k') => commands::cursor::move_up }
     ///
+    /// Keys can also be bound to a recorded macro, by name:
+    ///
+    ///  normal:
+    ///     ctrl-w:
+    ///       macro: "a"
+    ///
     pub fn from(keymap_data: &Hash) -> Result<KeyMap> {
-        let mut keymap = HashMap::new();
+        let mut bindings = HashMap::new();
+        let mut macros = HashMap::new();
         let commands = commands::hash_map();
 
         for (yaml_mode, yaml_key_bindings) in keymap_data {
             let mode = yaml_mode.as_str().ok_or_else(||
                 "A mode key couldn't be parsed as a string".to_string()
             )?;
-            let key_bindings = parse_mode_key_bindings(yaml_key_bindings, &commands).
+            let (key_bindings, macro_bindings) = parse_mode_key_bindings(yaml_key_bindings, &commands).
                 chain_err(|| format!("Failed to parse keymaps for \"{}\" mode", mode))?;
 
-            keymap.insert(mode.to_string(), key_bindings);
+            bindings.insert(mode.to_string(), key_bindings);
+            macros.insert(mode.to_string(), macro_bindings);
         }
 
-        Ok(KeyMap(keymap))
+        Ok(KeyMap{ bindings, macros })
     }
 
     /// Searches the keymap for the specified key.
@@ -44,7 +57,7 @@ impl KeyMap {
     /// if the specific character binding cannot be found.
     ///
     pub fn commands_for(&self, mode: &str, key: &Key) -> Option<SmallVec<[Command; 4]>> {
-        self.0.get(mode).and_then(|mode_keymap| {
+        self.bindings.get(mode).and_then(|mode_keymap| {
             if let Key::Char(_) = *key {
                 // Look for a command for this specific character, falling
                 // back to another search for a wildcard character binding.
@@ -55,6 +68,14 @@ impl KeyMap {
         }).map(|commands| (*commands).clone())
     }
 
+    /// The name of the macro bound to the specified key, if any.
+    pub fn macro_for(&self, mode: &str, key: &Key) -> Option<&str> {
+        self.macros
+            .get(mode)
+            .and_then(|mode_macros| mode_macros.get(key))
+            .map(|name| name.as_str())
+    }
+
     /// Loads the default keymap from a static
     /// YAML document injected during the build.
     pub fn default() -> Result<KeyMap> {
@@ -96,17 +117,23 @@ impl KeyMap {
     ///
     pub fn merge(&mut self, mut key_map: KeyMap) {
         // Step through the specified key map's modes.
-        for (mode, other_key_bindings) in key_map.iter_mut() {
+        for (mode, other_key_bindings) in key_map.bindings.iter_mut() {
             // Fetch the current key bindings for the specified mode.
-            if let Some(key_bindings) = self.get_mut(mode) {
+            if let Some(key_bindings) = self.bindings.get_mut(mode) {
                 for (key, command) in other_key_bindings.drain() {
                     key_bindings.insert(key, command);
                 }
+
+                if let Some(other_macros) = key_map.macros.remove(mode) {
+                    self.macros.entry(mode.clone()).or_default().extend(other_macros);
+                }
             }
         }
     }
 }
 
+type KeyBindings = HashMap<Key, SmallVec<[Command; 4]>>;
+
 /// Parses the key bindings for a particular mode.
 ///
 /// e.g.
@@ -117,12 +144,16 @@ impl KeyMap {
 ///
 ///   Key::Char('k') => [commands::cursor::move_up]
 ///
-fn parse_mode_key_bindings(mode: &Yaml, commands: &HashMap<&str, Command>) -> Result<HashMap<Key, SmallVec<[Command; 4]>>> {
+/// Macro bindings are bound to commands::macros::run_bound_macro,
+/// and the macro names are returned in a separate HashMap.
+///
+fn parse_mode_key_bindings(mode: &Yaml, commands: &HashMap<&str, Command>) -> Result<(KeyBindings, HashMap<Key, String>)> {
     let mode_key_bindings = mode.as_hash().ok_or(
         "Keymap mode config didn't return a hash of key bindings",
     )?;
 
     let mut key_bindings = HashMap::new();
+    let mut macro_bindings = HashMap::new();
     for (yaml_key, yaml_command) in mode_key_bindings {
         // Parse modifier/character from key component.
         let key = parse_key(yaml_key.as_str().ok_or_else(||
@@ -156,6 +187,14 @@ fn parse_mode_key_bindings(mode: &Yaml, commands: &HashMap<&str, Command>) -> Re
                     );
                 }
             },
+            Yaml::Hash(_) => {
+                let name = yaml_command["macro"].as_str().ok_or_else(||
+                    format!("Keymap macro \"{:?}\" couldn't be parsed", yaml_command)
+                )?;
+
+                key_commands.push(commands::macros::run_bound_macro as Command);
+                macro_bindings.insert(key.clone(), name.to_string());
+            },
             _ => bail!(format!("Keymap command \"{:?}\" couldn't be parsed", yaml_command))
         }
 
@@ -163,7 +202,7 @@ fn parse_mode_key_bindings(mode: &Yaml, commands: &HashMap<&str, Command>) -> Re
         key_bindings.insert(key, key_commands);
     }
 
-    Ok(key_bindings)
+    Ok((key_bindings, macro_bindings))
 }
 
 /// Parses a str-based key into its Key equivalent.
@@ -171,8 +210,9 @@ fn parse_mode_key_bindings(mode: &Yaml, commands: &HashMap<&str, Command>) -> Re
 /// e.g.
 ///
 ///   ctrl-r becomes Key::Ctrl('r')
+///   shift-tab becomes Key::BackTab
 ///
-fn parse_key(data: &str) -> Result<Key> {
+pub fn parse_key(data: &str) -> Result<Key> {
     let mut key_components = data.split('-');
     let component = key_components.next().ok_or(
         "A keymap key is an empty string",
@@ -188,6 +228,7 @@ fn parse_key(data: &str) -> Result<Key> {
         // Find the variant for the specified modifier.
         match component {
             "ctrl" => Ok(Key::Ctrl(key_char)),
+            "shift" if key == "tab" => Ok(Key::BackTab),
             _ => bail!(format!("Keymap modifier \"{}\" is invalid", component)),
         }
     } else {
@@ -223,19 +264,19 @@ impl Deref for KeyMap {
     type Target = HashMap<String, HashMap<Key, SmallVec<[Command; 4]>>>;
 
     fn deref(&self) -> &HashMap<String, HashMap<Key, SmallVec<[Command; 4]>>> {
-        &self.0
+        &self.bindings
     }
 }
 
 impl DerefMut for KeyMap {
     fn deref_mut(&mut self) -> &mut HashMap<String, HashMap<Key, SmallVec<[Command; 4]>>> {
-        &mut self.0
+        &mut self.bindings
     }
 }
 
 impl Into<HashMap<String, HashMap<Key, SmallVec<[Command; 4]>>>> for KeyMap {
     fn into(self) -> HashMap<String, HashMap<Key, SmallVec<[Command; 4]>>> {
-        self.0
+        self.bindings
     }
 }
 
@@ -337,6 +378,7 @@ mod tests {
             ("normal:\n  insert: cursor::move_up",    Key::Insert,    commands::cursor::move_up),
             ("normal:\n  escape: cursor::move_up",    Key::Esc,       commands::cursor::move_up),
             ("normal:\n  tab: cursor::move_up",       Key::Tab,       commands::cursor::move_up),
+            ("normal:\n  shift-tab: cursor::move_up", Key::BackTab,   commands::cursor::move_up),
             ("normal:\n  enter: cursor::move_up",     Key::Enter,     commands::cursor::move_up)
         ];
 
@@ -420,4 +462,34 @@ mod tests {
             (commands::cursor::move_down as *const usize)
         );
     }
+
+    #[test]
+    fn keymap_correctly_parses_yaml_macro_keybindings() {
+        let yaml_data = "normal:\n  ctrl-w:\n    macro: wrap";
+        let yaml = YamlLoader::load_from_str(yaml_data).unwrap();
+        let keymap = KeyMap::from(yaml[0].as_hash().unwrap()).unwrap();
+
+        let command = keymap.commands_for("normal", &Key::Ctrl('w')).expect(
+            "Keymap doesn't contain command",
+        );
+        assert_eq!(
+            (command[0] as *const usize),
+            (commands::macros::run_bound_macro as *const usize)
+        );
+        assert_eq!(keymap.macro_for("normal", &Key::Ctrl('w')), Some("wrap"));
+        assert_eq!(keymap.macro_for("normal", &Key::Ctrl('r')), None);
+    }
+
+    #[test]
+    fn keymap_correctly_merges_macro_keybindings() {
+        let mut keymap = KeyMap::default().unwrap();
+
+        let other_yaml_data = "normal:\n  ctrl-w:\n    macro: wrap";
+        let other_yaml = YamlLoader::load_from_str(other_yaml_data).unwrap();
+        let other_keymap = KeyMap::from(other_yaml[0].as_hash().unwrap()).unwrap();
+
+        keymap.merge(other_keymap);
+
+        assert_eq!(keymap.macro_for("normal", &Key::Ctrl('w')), Some("wrap"));
+    }
 }
@@ -1,5 +1,5 @@
 
-pub use self::key_map::KeyMap;
+pub use self::key_map::{KeyMap, parse_key};
 
 mod key_map;
 
@@ -18,6 +18,7 @@ pub enum Key {
     Insert,
     Esc,
     Tab,
+    BackTab,
     Enter,
     AnyChar,
     Char(char),
@@ -9,11 +9,13 @@ extern crate fragment;
 extern crate git2;
 extern crate luthor;
 extern crate mio;
+extern crate rand;
 extern crate regex;
 extern crate scribe;
 extern crate signal_hook;
 extern crate syntect;
 extern crate unicode_segmentation;
+extern crate unicode_width;
 extern crate clipboard;
 extern crate yaml_rust as yaml;
 extern crate smallvec;
@@ -0,0 +1,94 @@
+use scribe::Buffer;
+use scribe::buffer::Position;
+
+/// Positions of closing characters inserted by auto-pairing, which are
+/// stepped over (rather than duplicated) when typed. Positions are kept
+/// in sync with edits made on their line, and are discarded otherwise.
+#[derive(Debug, Default)]
+pub struct AutoPairs {
+    buffer_id: Option<usize>,
+    closers: Vec<Position>,
+}
+
+impl AutoPairs {
+    /// Tracks an auto-inserted closing character, forgetting
+    /// those previously tracked if the buffer has changed.
+    pub fn track(&mut self, buffer: &Buffer, position: Position) {
+        if self.buffer_id != buffer.id {
+            self.closers.clear();
+            self.buffer_id = buffer.id;
+        }
+        self.closers.push(position);
+    }
+
+    /// Stops tracking the closing character at the specified
+    /// position, returning whether or not it was being tracked.
+    pub fn take(&mut self, buffer: &Buffer, position: Position) -> bool {
+        if self.buffer_id != buffer.id {
+            return false;
+        }
+
+        let index = self.closers.iter().position(|closer| *closer == position);
+        index.map(|index| self.closers.remove(index)).is_some()
+    }
+
+    /// Shifts closers following an insertion on the same line.
+    pub fn inserted(&mut self, position: Position, length: usize) {
+        for closer in self.closers.iter_mut() {
+            if closer.line == position.line && closer.offset >= position.offset {
+                closer.offset += length;
+            }
+        }
+    }
+
+    /// Discards closers within a deletion on a single line,
+    /// shifting those following it on the same line.
+    pub fn deleted(&mut self, position: Position, length: usize) {
+        let end = position.offset + length;
+        self.closers.retain(|closer| {
+            closer.line != position.line || closer.offset < position.offset || closer.offset >= end
+        });
+        for closer in self.closers.iter_mut() {
+            if closer.line == position.line && closer.offset >= end {
+                closer.offset -= length;
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.closers.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AutoPairs;
+    use scribe::Buffer;
+    use scribe::buffer::Position;
+
+    #[test]
+    fn closers_are_shifted_by_edits_preceding_them_on_their_line() {
+        let buffer = Buffer::new();
+        let mut auto_pairs = AutoPairs::default();
+        auto_pairs.track(&buffer, Position{ line: 0, offset: 4 });
+        auto_pairs.track(&buffer, Position{ line: 1, offset: 4 });
+
+        auto_pairs.inserted(Position{ line: 0, offset: 2 }, 3);
+        auto_pairs.deleted(Position{ line: 0, offset: 0 }, 1);
+
+        assert!(!auto_pairs.take(&buffer, Position{ line: 0, offset: 4 }));
+        assert!(auto_pairs.take(&buffer, Position{ line: 0, offset: 6 }));
+        assert!(auto_pairs.take(&buffer, Position{ line: 1, offset: 4 }));
+    }
+
+    #[test]
+    fn deleted_closers_are_discarded() {
+        let buffer = Buffer::new();
+        let mut auto_pairs = AutoPairs::default();
+        auto_pairs.track(&buffer, Position{ line: 0, offset: 4 });
+
+        auto_pairs.deleted(Position{ line: 0, offset: 3 }, 2);
+
+        assert!(!auto_pairs.take(&buffer, Position{ line: 0, offset: 3 }));
+    }
+}
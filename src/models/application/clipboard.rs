@@ -1,24 +1,36 @@
 
 use crate::errors::*;
 use clipboard::{ClipboardContext, ClipboardProvider};
+use std::collections::{HashMap, VecDeque};
+use std::collections::vec_deque::Iter;
 
-/// In-app content can be captured in both regular and full-line selection
-/// modes. This type describes the structure of said content, based on the
-/// context in which it was captured. When OS-level clipboard contents are
-/// used, they are always represented as inline, as we cannot infer block
+const HISTORY_CAPACITY: usize = 20;
+
+/// In-app content can be captured in regular, full-line, and rectangular
+/// selection modes. This type describes the structure of said content, based
+/// on the context in which it was captured. When OS-level clipboard contents
+/// are used, they are always represented as inline, as we cannot infer block
 /// style without the copy context.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum ClipboardContent {
     Inline(String),
     Block(String),
+    /// The selected portion of each line in a rectangle, separated by newlines.
+    Rectangle(String),
     None,
 }
 
 /// Qualifies in-app copy/paste content with structural information, and
 /// synchronizes said content with the OS-level clipboard (preferring it
 /// in scenarios where it differs from the in-app equivalent).
+///
+/// Copied and deleted content is also tracked in a bounded history, ordered
+/// from newest to oldest, and content can be stored in named registers,
+/// neither of which are shared with the OS-level clipboard.
 pub struct Clipboard {
     content: ClipboardContent,
+    history: VecDeque<ClipboardContent>,
+    registers: HashMap<char, ClipboardContent>,
     system_clipboard: Option<ClipboardContext>,
 }
 
@@ -38,6 +50,8 @@ impl Clipboard {
 
         Clipboard {
             content: ClipboardContent::None,
+            history: VecDeque::new(),
+            registers: HashMap::new(),
             system_clipboard,
         }
     }
@@ -57,7 +71,8 @@ impl Clipboard {
                             // There is system clipboard content we can use.
                             match self.content {
                                 ClipboardContent::Inline(ref app_content) |
-                                ClipboardContent::Block(ref app_content) => {
+                                ClipboardContent::Block(ref app_content) |
+                                ClipboardContent::Rectangle(ref app_content) => {
                                     // We have in-app clipboard content, too. Prefer
                                     // the system clipboard content if they differ.
                                     if content != *app_content {
@@ -78,8 +93,9 @@ impl Clipboard {
         };
 
         // Update the in-app clipboard if we've found newer content.
-        if new_content.is_some() {
-            self.content = new_content.unwrap();
+        if let Some(content) = new_content {
+            self.record(content.clone());
+            self.content = content;
         }
 
         &self.content
@@ -88,12 +104,14 @@ impl Clipboard {
     // Updates the in-app and system clipboards with the specified content.
     pub fn set_content(&mut self, content: ClipboardContent) -> Result<()> {
         // Update the in-app clipboard.
+        self.record(content.clone());
         self.content = content;
 
         // Update the system clipboard.
         match self.content {
             ClipboardContent::Inline(ref app_content) |
-            ClipboardContent::Block(ref app_content) => {
+            ClipboardContent::Block(ref app_content) |
+            ClipboardContent::Rectangle(ref app_content) => {
                 if let Some(ref mut clipboard) = self.system_clipboard {
                     return clipboard
                         .set_contents(app_content.clone())
@@ -105,4 +123,91 @@ impl Clipboard {
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Adds content to the history as its newest entry, removing any
+    /// earlier duplicates and discarding the oldest beyond capacity.
+    /// This is used on its own for deleted content that isn't copied.
+    pub fn record(&mut self, content: ClipboardContent) {
+        match content {
+            ClipboardContent::Inline(ref data) |
+            ClipboardContent::Block(ref data) |
+            ClipboardContent::Rectangle(ref data) if !data.is_empty() => (),
+            _ => return,
+        }
+
+        self.history.retain(|entry| *entry != content);
+        self.history.push_front(content);
+        self.history.truncate(HISTORY_CAPACITY);
+    }
+
+    /// Previously copied and deleted content, from newest to oldest.
+    pub fn history(&self) -> Iter<'_, ClipboardContent> {
+        self.history.iter()
+    }
+
+    pub fn register(&self, name: char) -> Option<&ClipboardContent> {
+        self.registers.get(&name)
+    }
+
+    /// Stores content in a named register, leaving the in-app and system
+    /// clipboards untouched. It's added to the history, like any other copy.
+    pub fn set_register(&mut self, name: char, content: ClipboardContent) {
+        self.record(content.clone());
+        self.registers.insert(name, content);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Clipboard, ClipboardContent, HISTORY_CAPACITY};
+
+    #[test]
+    fn record_moves_duplicate_entries_to_the_front() {
+        let mut clipboard = Clipboard::new();
+        clipboard.record(ClipboardContent::Inline(String::from("amp")));
+        clipboard.record(ClipboardContent::Block(String::from("amp\n")));
+        clipboard.record(ClipboardContent::Inline(String::from("amp")));
+
+        assert_eq!(
+            clipboard.history().collect::<Vec<&ClipboardContent>>(),
+            vec![
+                &ClipboardContent::Inline(String::from("amp")),
+                &ClipboardContent::Block(String::from("amp\n")),
+            ]
+        );
+    }
+
+    #[test]
+    fn record_discards_oldest_entries_beyond_capacity() {
+        let mut clipboard = Clipboard::new();
+        for i in 0..HISTORY_CAPACITY + 1 {
+            clipboard.record(ClipboardContent::Inline(i.to_string()));
+        }
+
+        assert_eq!(clipboard.history().count(), HISTORY_CAPACITY);
+        assert_eq!(
+            clipboard.history().last(),
+            Some(&ClipboardContent::Inline(String::from("1")))
+        );
+    }
+
+    #[test]
+    fn record_ignores_empty_content() {
+        let mut clipboard = Clipboard::new();
+        clipboard.record(ClipboardContent::Inline(String::new()));
+        clipboard.record(ClipboardContent::None);
+
+        assert_eq!(clipboard.history().count(), 0);
+    }
+
+    #[test]
+    fn set_register_records_content_without_replacing_the_clipboard() {
+        let mut clipboard = Clipboard::new();
+        clipboard.set_register('a', ClipboardContent::Block(String::from("amp\n")));
+
+        assert_eq!(clipboard.register('a'), Some(&ClipboardContent::Block(String::from("amp\n"))));
+        assert_eq!(clipboard.register('b'), None);
+        assert_eq!(clipboard.history().count(), 1);
+        assert_eq!(clipboard.content, ClipboardContent::None);
+    }
+}
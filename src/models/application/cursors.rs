@@ -0,0 +1,255 @@
+use scribe::Buffer;
+use scribe::buffer::{Position, Range};
+use std::cmp::Ordering;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A set of cursors for a single buffer, each of which starts a (possibly
+/// empty) selection. The buffer's own cursor is treated as one of them,
+/// whether or not it was part of the set, so that edits made through
+/// `Cursors::edit` are applied at every cursor and the buffer's alike.
+#[derive(Debug, Default)]
+pub struct Cursors {
+    buffer_id: Option<usize>,
+    selections: Vec<Range>,
+    version: usize,
+}
+
+impl Cursors {
+    /// Replaces the cursors with one at the start of each selection,
+    /// associating them with the specified buffer.
+    pub fn set(&mut self, buffer: &Buffer, mut selections: Vec<Range>) {
+        selections.sort_by(|a, b| {
+            a.start().partial_cmp(&b.start()).unwrap_or(Ordering::Equal)
+        });
+        selections.dedup_by(|a, b| a.start() == b.start());
+
+        self.buffer_id = buffer.id;
+        self.selections = selections;
+        self.version += 1;
+    }
+
+    pub fn add(&mut self, buffer: &Buffer, selection: Range) {
+        let mut selections = self.selections(buffer).to_vec();
+        selections.push(selection);
+        self.set(buffer, selections);
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer_id = None;
+        self.selections.clear();
+        self.version += 1;
+    }
+
+    /// A counter that's bumped whenever the cursors are set, edited, or
+    /// cleared, used to determine whether an edit was made through them.
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    /// The selections for the specified buffer, which are
+    /// empty if the cursors were set for a different buffer.
+    pub fn selections(&self, buffer: &Buffer) -> &[Range] {
+        if self.buffer_id == buffer.id {
+            &self.selections
+        } else {
+            &[]
+        }
+    }
+
+    /// The positions of every cursor other than the buffer's own.
+    pub fn secondary_positions(&self, buffer: &Buffer) -> Vec<Position> {
+        self.selections(buffer)
+            .iter()
+            .map(|selection| selection.start())
+            .filter(|position| *position != *buffer.cursor)
+            .collect()
+    }
+
+    /// Whether or not there are cursors other than the buffer's own.
+    pub fn active(&self, buffer: &Buffer) -> bool {
+        self.selections(buffer)
+            .iter()
+            .any(|selection| selection.start() != *buffer.cursor)
+    }
+
+    /// Applies an edit at every cursor, in reverse order so that the edits
+    /// don't invalidate the positions of cursors that have yet to be visited.
+    /// The edit closure is given each cursor's selection, and returns the
+    /// range it'd like to replace, along with its replacement, if anything.
+    ///
+    /// Every cursor is moved to the end of its replacement, and has its
+    /// selection cleared. Edits to the buffer are not grouped; callers
+    /// are expected to manage operation groups, if need be.
+    pub fn edit<F>(&mut self, buffer: &mut Buffer, mut edit: F)
+        where F: FnMut(&Buffer, &Range) -> Option<(Range, String)>
+    {
+        let primary = *buffer.cursor;
+        let mut selections = self.selections(buffer).to_vec();
+        if !selections.iter().any(|selection| selection.start() == primary) {
+            selections.push(Range::new(primary, primary));
+        }
+        selections.sort_by(|a, b| {
+            a.start().partial_cmp(&b.start()).unwrap_or(Ordering::Equal)
+        });
+
+        // Track updated cursor positions (and which is the buffer's
+        // own cursor) for those we've already visited, all of which
+        // follow the current one and need to be shifted by its edit.
+        let mut positions: Vec<(Position, bool)> = Vec::with_capacity(selections.len());
+        for selection in selections.iter().rev() {
+            let is_primary = selection.start() == primary;
+            let position = match edit(buffer, selection) {
+                Some((range, content)) => {
+                    buffer.delete_range(range.clone());
+                    buffer.cursor.move_to(range.start());
+                    buffer.insert(content.as_str());
+
+                    let end = end_of_insertion(range.start(), &content);
+                    for (position, _) in positions.iter_mut() {
+                        *position = shift(*position, &range, end);
+                    }
+
+                    end
+                }
+                None => selection.start(),
+            };
+            positions.push((position, is_primary));
+        }
+
+        if let Some(&(position, _)) = positions.iter().find(|(_, is_primary)| *is_primary) {
+            buffer.cursor.move_to(position);
+        }
+        let selections = positions
+            .into_iter()
+            .map(|(position, _)| Range::new(position, position))
+            .collect();
+        self.set(buffer, selections);
+    }
+}
+
+// The position following content inserted at the specified position.
+fn end_of_insertion(start: Position, content: &str) -> Position {
+    let lines: Vec<&str> = content.split('\n').collect();
+    let last_line_length = lines.last().map(|line| line.graphemes(true).count()).unwrap_or(0);
+
+    if lines.len() > 1 {
+        Position{ line: start.line + lines.len() - 1, offset: last_line_length }
+    } else {
+        Position{ line: start.line, offset: start.offset + last_line_length }
+    }
+}
+
+// Maps a position at or beyond the end of a replaced range to the
+// location of that same content, following the range's replacement.
+fn shift(position: Position, range: &Range, replacement_end: Position) -> Position {
+    let range_end = range.end();
+
+    if position.line == range_end.line {
+        Position{
+            line: replacement_end.line,
+            offset: replacement_end.offset + position.offset - range_end.offset,
+        }
+    } else {
+        Position{
+            line: position.line + replacement_end.line - range_end.line,
+            offset: position.offset,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Cursors;
+    use scribe::Buffer;
+    use scribe::buffer::{Position, Range};
+
+    fn cursor(line: usize, offset: usize) -> Range {
+        let position = Position{ line, offset };
+        Range::new(position, position)
+    }
+
+    #[test]
+    fn selections_are_only_returned_for_their_buffer() {
+        let mut buffer = Buffer::new();
+        buffer.id = Some(1);
+        let mut other_buffer = Buffer::new();
+        other_buffer.id = Some(2);
+
+        let mut cursors = Cursors::default();
+        cursors.set(&buffer, vec![cursor(0, 0)]);
+
+        assert_eq!(cursors.selections(&buffer), &[cursor(0, 0)]);
+        assert!(cursors.selections(&other_buffer).is_empty());
+    }
+
+    #[test]
+    fn active_ignores_the_buffer_cursor() {
+        let mut buffer = Buffer::new();
+        buffer.insert("amp");
+        let mut cursors = Cursors::default();
+
+        cursors.set(&buffer, vec![cursor(0, 0)]);
+        assert!(!cursors.active(&buffer));
+
+        cursors.add(&buffer, cursor(0, 2));
+        assert!(cursors.active(&buffer));
+        assert_eq!(cursors.secondary_positions(&buffer), vec![Position{ line: 0, offset: 2 }]);
+    }
+
+    #[test]
+    fn edit_applies_insertions_at_every_cursor() {
+        let mut buffer = Buffer::new();
+        buffer.insert("amp\namp editor");
+        let mut cursors = Cursors::default();
+        cursors.set(&buffer, vec![cursor(1, 0), cursor(1, 4)]);
+
+        cursors.edit(&mut buffer, |_, selection| {
+            Some((selection.clone(), String::from("x\n")))
+        });
+
+        assert_eq!(buffer.data(), "x\namp\nx\namp x\neditor");
+        assert_eq!(*buffer.cursor, Position{ line: 1, offset: 0 });
+        assert_eq!(
+            cursors.selections(&buffer),
+            &[cursor(1, 0), cursor(3, 0), cursor(4, 0)]
+        );
+    }
+
+    #[test]
+    fn edit_replaces_selections() {
+        let mut buffer = Buffer::new();
+        buffer.insert("amp editor amp");
+        let mut cursors = Cursors::default();
+        cursors.set(&buffer, vec![
+            Range::new(Position{ line: 0, offset: 0 }, Position{ line: 0, offset: 3 }),
+            Range::new(Position{ line: 0, offset: 11 }, Position{ line: 0, offset: 14 }),
+        ]);
+
+        cursors.edit(&mut buffer, |_, selection| {
+            Some((selection.clone(), String::from("scribe")))
+        });
+
+        assert_eq!(buffer.data(), "scribe editor scribe");
+        assert_eq!(*buffer.cursor, Position{ line: 0, offset: 6 });
+        assert_eq!(cursors.selections(&buffer), &[cursor(0, 6), cursor(0, 20)]);
+    }
+
+    #[test]
+    fn edit_merges_cursors_that_end_up_in_the_same_place() {
+        let mut buffer = Buffer::new();
+        buffer.insert("ab");
+        buffer.cursor.move_to(Position{ line: 0, offset: 1 });
+        let mut cursors = Cursors::default();
+        cursors.set(&buffer, vec![cursor(0, 1), cursor(0, 2)]);
+
+        // Delete the preceding character.
+        cursors.edit(&mut buffer, |_, selection| {
+            let end = selection.start();
+            let start = Position{ line: end.line, offset: end.offset - 1 };
+            Some((Range::new(start, end), String::new()))
+        });
+
+        assert_eq!(buffer.data(), "");
+        assert_eq!(cursors.selections(&buffer), &[cursor(0, 0)]);
+    }
+}
@@ -0,0 +1,237 @@
+use crate::errors::*;
+use crate::models::application::Preferences;
+use crate::yaml::YamlEmitter;
+use crate::yaml::yaml::{Hash, Yaml, YamlLoader};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+const FILE_NAME: &str = "history.yml";
+const CAPACITY: usize = 100;
+
+/// The prompts for which input history is tracked.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum HistoryKind {
+    LineJump,
+    Path,
+    Search,
+}
+
+impl HistoryKind {
+    fn key(self) -> &'static str {
+        match self {
+            HistoryKind::LineJump => "line_jump",
+            HistoryKind::Path => "path",
+            HistoryKind::Search => "search",
+        }
+    }
+}
+
+const KINDS: [HistoryKind; 3] = [HistoryKind::LineJump, HistoryKind::Path, HistoryKind::Search];
+
+/// Bounded lists of previously entered prompt values, ordered from oldest
+/// to newest, which are persisted to the user preferences directory.
+#[derive(Debug, Default)]
+pub struct History {
+    entries: HashMap<HistoryKind, Vec<String>>,
+    path: Option<PathBuf>,
+}
+
+impl History {
+    /// Builds a new in-memory instance, which will be
+    /// persisted to the specified path, if one is provided.
+    pub fn new(path: Option<PathBuf>) -> History {
+        History {
+            entries: HashMap::new(),
+            path,
+        }
+    }
+
+    /// Loads history from the user preferences directory. Missing or
+    /// unreadable history isn't worth failing over, so this falls back
+    /// to an empty history that will be written to the same location.
+    pub fn load() -> History {
+        match Preferences::directory() {
+            Ok(directory) => History::load_from(directory.join(FILE_NAME)),
+            Err(_) => History::new(None),
+        }
+    }
+
+    fn load_from(path: PathBuf) -> History {
+        let document = fs::read_to_string(&path)
+            .ok()
+            .and_then(|data| YamlLoader::load_from_str(&data).ok())
+            .and_then(|documents| documents.into_iter().next());
+        let mut history = History::new(Some(path));
+
+        if let Some(document) = document {
+            for kind in KINDS.iter() {
+                if let Some(values) = document[kind.key()].as_vec() {
+                    let entries = values
+                        .iter()
+                        .filter_map(|value| value.as_str().map(|v| v.to_string()))
+                        .collect();
+                    history.entries.insert(*kind, entries);
+                }
+            }
+        }
+
+        history
+    }
+
+    /// Adds an entry as the newest of its kind, removing any earlier
+    /// duplicates and discarding the oldest entries beyond capacity.
+    pub fn add<T: Into<String>>(&mut self, kind: HistoryKind, entry: T) {
+        let entry = entry.into();
+        if entry.is_empty() {
+            return;
+        }
+
+        let entries = self.entries.entry(kind).or_default();
+        entries.retain(|existing| *existing != entry);
+        entries.push(entry);
+
+        if entries.len() > CAPACITY {
+            let overflow = entries.len() - CAPACITY;
+            entries.drain(..overflow);
+        }
+    }
+
+    pub fn entries(&self, kind: HistoryKind) -> &[String] {
+        self.entries.get(&kind).map(|entries| entries.as_slice()).unwrap_or(&[])
+    }
+
+    /// Writes the history to disk, if it was built with a path.
+    pub fn save(&self) -> Result<()> {
+        let path = match self.path {
+            Some(ref path) => path,
+            None => return Ok(()),
+        };
+
+        let mut document = Hash::new();
+        for kind in KINDS.iter() {
+            let values = self.entries(*kind)
+                .iter()
+                .map(|entry| Yaml::String(entry.clone()))
+                .collect();
+            document.insert(Yaml::String(kind.key().to_string()), Yaml::Array(values));
+        }
+
+        let mut data = String::new();
+        YamlEmitter::new(&mut data)
+            .dump(&Yaml::Hash(document))
+            .chain_err(|| "Couldn't serialize input history")?;
+        fs::write(path, data).chain_err(|| "Couldn't write input history")
+    }
+}
+
+/// Tracks a prompt's position while browsing its history,
+/// preserving whatever was entered before browsing began.
+#[derive(Debug, Default)]
+pub struct HistoryPosition {
+    index: Option<usize>,
+    draft: Option<String>,
+}
+
+impl HistoryPosition {
+    /// Moves to the next oldest entry, returning its value.
+    pub fn previous(&mut self, entries: &[String], current: &str) -> Option<String> {
+        let index = match self.index {
+            Some(0) => return None,
+            Some(index) => index - 1,
+            None => {
+                let index = entries.len().checked_sub(1)?;
+                self.draft = Some(current.to_string());
+                index
+            },
+        };
+        self.index = Some(index);
+
+        entries.get(index).cloned()
+    }
+
+    /// Moves to the next newest entry, returning its value, or the
+    /// value entered before browsing began once beyond the newest.
+    pub fn next(&mut self, entries: &[String]) -> Option<String> {
+        let index = self.index? + 1;
+        if index < entries.len() {
+            self.index = Some(index);
+            entries.get(index).cloned()
+        } else {
+            self.index = None;
+            self.draft.take()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CAPACITY, History, HistoryKind, HistoryPosition};
+    use std::env;
+
+    #[test]
+    fn add_moves_duplicate_entries_to_the_end() {
+        let mut history = History::new(None);
+        history.add(HistoryKind::Search, "amp");
+        history.add(HistoryKind::Search, "editor");
+        history.add(HistoryKind::Search, "amp");
+
+        assert_eq!(history.entries(HistoryKind::Search), &["editor", "amp"]);
+        assert!(history.entries(HistoryKind::Path).is_empty());
+    }
+
+    #[test]
+    fn add_discards_oldest_entries_beyond_capacity() {
+        let mut history = History::new(None);
+        for i in 0..CAPACITY + 1 {
+            history.add(HistoryKind::LineJump, i.to_string());
+        }
+
+        let entries = history.entries(HistoryKind::LineJump);
+        assert_eq!(entries.len(), CAPACITY);
+        assert_eq!(entries[0], "1");
+    }
+
+    #[test]
+    fn add_ignores_empty_entries() {
+        let mut history = History::new(None);
+        history.add(HistoryKind::Search, "");
+
+        assert!(history.entries(HistoryKind::Search).is_empty());
+    }
+
+    #[test]
+    fn save_writes_entries_that_can_be_loaded() {
+        let path = env::temp_dir().join("amp_history_test.yml");
+        let mut history = History::new(Some(path.clone()));
+        history.add(HistoryKind::Search, "amp: editor");
+        history.add(HistoryKind::Path, "src/lib.rs");
+        history.save().unwrap();
+
+        let loaded = History::load_from(path);
+        assert_eq!(loaded.entries(HistoryKind::Search), &["amp: editor"]);
+        assert_eq!(loaded.entries(HistoryKind::Path), &["src/lib.rs"]);
+        assert!(loaded.entries(HistoryKind::LineJump).is_empty());
+    }
+
+    #[test]
+    fn position_browses_entries_and_restores_draft() {
+        let entries = vec![String::from("amp"), String::from("editor")];
+        let mut position = HistoryPosition::default();
+
+        assert_eq!(position.previous(&entries, "draft"), Some(String::from("editor")));
+        assert_eq!(position.previous(&entries, "editor"), Some(String::from("amp")));
+        assert_eq!(position.previous(&entries, "amp"), None);
+        assert_eq!(position.next(&entries), Some(String::from("editor")));
+        assert_eq!(position.next(&entries), Some(String::from("draft")));
+        assert_eq!(position.next(&entries), None);
+    }
+
+    #[test]
+    fn position_does_not_move_without_entries() {
+        let mut position = HistoryPosition::default();
+
+        assert_eq!(position.previous(&[], "draft"), None);
+        assert_eq!(position.next(&[]), None);
+    }
+}
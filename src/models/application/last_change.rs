@@ -0,0 +1,136 @@
+use crate::input::Key;
+
+/// Tracks the key presses that made the most recent change to a buffer, so
+/// that it can be repeated. Keys are collected from the moment one is pressed
+/// in normal mode until the application returns to it (e.g. after an insert
+/// mode session), and kept if the current buffer's revision (see
+/// `View::buffer_revision`) changed along the way. Sequences that only move
+/// the cursor, select, or search are dropped.
+#[derive(Default)]
+pub struct LastChange {
+    change: Option<(Option<usize>, Vec<Key>)>,
+    pending: Option<PendingChange>,
+    replaying: bool,
+}
+
+// Keys pressed since leaving normal mode, along with the pending count and
+// the ID and revision of the current buffer before the first was handled.
+struct PendingChange {
+    count: Option<usize>,
+    keys: Vec<Key>,
+    buffer: Option<(Option<usize>, usize)>,
+    ignored: bool,
+}
+
+impl LastChange {
+    /// The count and keys that made the last change, if there's been one.
+    pub fn get(&self) -> Option<(Option<usize>, Vec<Key>)> {
+        self.change.clone()
+    }
+
+    pub fn pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Starts collecting keys, tracking the current buffer's ID and revision
+    /// so that they can be compared once the sequence finishes. Nothing is
+    /// collected while a change is being replayed.
+    pub fn start(&mut self, count: Option<usize>, buffer: Option<(Option<usize>, usize)>) {
+        if self.replaying {
+            return;
+        }
+
+        self.pending = Some(PendingChange {
+            count,
+            keys: Vec::new(),
+            buffer,
+            ignored: false,
+        });
+    }
+
+    pub fn record(&mut self, key: Key) {
+        if let Some(ref mut pending) = self.pending {
+            pending.keys.push(key);
+        }
+    }
+
+    /// Keeps the collected keys as the last change, provided
+    /// they've changed the revision of the buffer they started in.
+    pub fn finish(&mut self, buffer: Option<(Option<usize>, usize)>) {
+        let pending = match self.pending.take() {
+            Some(pending) => pending,
+            None => return,
+        };
+
+        let changed = match (pending.buffer, buffer) {
+            (Some((id, before)), Some((current_id, after))) => id == current_id && before != after,
+            _ => false,
+        };
+
+        if changed && !pending.ignored && !pending.keys.is_empty() {
+            self.change = Some((pending.count, pending.keys));
+        }
+    }
+
+    /// Excludes the keys being collected from being kept as the last change,
+    /// for commands that modify buffers without editing them (e.g. undo).
+    pub fn ignore(&mut self) {
+        if let Some(ref mut pending) = self.pending {
+            pending.ignored = true;
+        }
+    }
+
+    /// Whether the last change is being replayed, during which
+    /// its keys shouldn't be recorded anywhere else (e.g. macros).
+    pub fn replaying(&self) -> bool {
+        self.replaying
+    }
+
+    pub fn start_replay(&mut self) {
+        self.pending = None;
+        self.replaying = true;
+    }
+
+    pub fn finish_replay(&mut self) {
+        self.replaying = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input::Key;
+    use super::LastChange;
+
+    #[test]
+    fn finish_keeps_keys_that_changed_the_buffer() {
+        let mut last_change = LastChange::default();
+        last_change.start(Some(2), Some((Some(0), 0)));
+        last_change.record(Key::Char('d'));
+        last_change.finish(Some((Some(0), 1)));
+
+        assert_eq!(last_change.get(), Some((Some(2), vec![Key::Char('d')])));
+    }
+
+    #[test]
+    fn finish_drops_movements_and_ignored_changes() {
+        let mut last_change = LastChange::default();
+        last_change.start(None, Some((Some(0), 0)));
+        last_change.record(Key::Char('d'));
+        last_change.finish(Some((Some(0), 1)));
+
+        last_change.start(None, Some((Some(0), 1)));
+        last_change.record(Key::Char('j'));
+        last_change.finish(Some((Some(0), 1)));
+
+        last_change.start(None, Some((Some(0), 1)));
+        last_change.record(Key::Char('u'));
+        last_change.ignore();
+        last_change.finish(Some((Some(0), 2)));
+
+        last_change.start(None, Some((Some(0), 2)));
+        last_change.record(Key::Char('B'));
+        last_change.finish(Some((Some(1), 0)));
+
+        assert_eq!(last_change.get(), Some((None, vec![Key::Char('d')])));
+    }
+}
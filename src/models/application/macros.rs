@@ -0,0 +1,231 @@
+use crate::errors::*;
+use crate::input::{Key, parse_key};
+use crate::models::application::Preferences;
+use crate::yaml::YamlEmitter;
+use crate::yaml::yaml::{Hash, Yaml, YamlLoader};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+const FILE_NAME: &str = "macros.yml";
+const MAX_REPLAY_DEPTH: usize = 10;
+
+/// Named sequences of recorded key presses, which are
+/// persisted to the user preferences directory.
+#[derive(Debug, Default)]
+pub struct Macros {
+    registers: HashMap<String, Vec<Key>>,
+    recording: Option<(String, Vec<Key>)>,
+    replay_depth: usize,
+    path: Option<PathBuf>,
+}
+
+impl Macros {
+    /// Builds a new in-memory instance, which will be
+    /// persisted to the specified path, if one is provided.
+    pub fn new(path: Option<PathBuf>) -> Macros {
+        Macros {
+            path,
+            ..Default::default()
+        }
+    }
+
+    /// Loads macros from the user preferences directory. Like input
+    /// history, missing or unreadable macros fall back to an empty set.
+    pub fn load() -> Macros {
+        match Preferences::directory() {
+            Ok(directory) => Macros::load_from(directory.join(FILE_NAME)),
+            Err(_) => Macros::new(None),
+        }
+    }
+
+    fn load_from(path: PathBuf) -> Macros {
+        let document = fs::read_to_string(&path)
+            .ok()
+            .and_then(|data| YamlLoader::load_from_str(&data).ok())
+            .and_then(|documents| documents.into_iter().next());
+        let mut macros = Macros::new(Some(path));
+
+        if let Some(Yaml::Hash(document)) = document {
+            for (name, keys) in document {
+                let keys = keys.as_vec().and_then(|keys| {
+                    keys.iter().map(|key| key.as_str().and_then(decode_key)).collect()
+                });
+
+                if let (Some(name), Some(keys)) = (name.as_str(), keys) {
+                    macros.registers.insert(name.to_string(), keys);
+                }
+            }
+        }
+
+        macros
+    }
+
+    /// Writes the macros to disk, if they were built with a path.
+    pub fn save(&self) -> Result<()> {
+        let path = match self.path {
+            Some(ref path) => path,
+            None => return Ok(()),
+        };
+
+        let mut names: Vec<&String> = self.registers.keys().collect();
+        names.sort();
+
+        let mut document = Hash::new();
+        for name in names {
+            let keys = self.registers[name]
+                .iter()
+                .map(|key| Yaml::String(encode_key(key)))
+                .collect();
+            document.insert(Yaml::String(name.clone()), Yaml::Array(keys));
+        }
+
+        let mut data = String::new();
+        YamlEmitter::new(&mut data)
+            .dump(&Yaml::Hash(document))
+            .chain_err(|| "Couldn't serialize macros")?;
+        fs::write(path, data).chain_err(|| "Couldn't write macros")
+    }
+
+    pub fn get(&self, name: &str) -> Option<&[Key]> {
+        self.registers.get(name).map(|keys| keys.as_slice())
+    }
+
+    /// The name of the macro being recorded, if any.
+    pub fn recording(&self) -> Option<&str> {
+        self.recording.as_ref().map(|(name, _)| name.as_str())
+    }
+
+    pub fn start_recording<T: Into<String>>(&mut self, name: T) {
+        self.recording = Some((name.into(), Vec::new()));
+    }
+
+    /// Stores the recorded keys under the macro's name,
+    /// replacing any macro previously recorded using it.
+    pub fn stop_recording(&mut self) -> Result<()> {
+        let (name, keys) = self.recording.take().ok_or("Not recording a macro")?;
+        self.registers.insert(name, keys);
+
+        Ok(())
+    }
+
+    /// Adds a key to the macro being recorded. Keys pressed on
+    /// behalf of a macro being replayed are not recorded; only
+    /// the key that triggered the replay itself.
+    pub fn record(&mut self, key: Key) {
+        if self.replay_depth > 0 {
+            return;
+        }
+
+        if let Some((_, ref mut keys)) = self.recording {
+            keys.push(key);
+        }
+    }
+
+    /// Tracks the start of a replay, which is refused beyond a maximum
+    /// depth, preventing macros from endlessly replaying themselves.
+    pub fn start_replay(&mut self) -> Result<()> {
+        if self.replay_depth == MAX_REPLAY_DEPTH {
+            bail!("Macros can't be nested more than {} levels deep", MAX_REPLAY_DEPTH);
+        }
+        self.replay_depth += 1;
+
+        Ok(())
+    }
+
+    pub fn finish_replay(&mut self) {
+        self.replay_depth = self.replay_depth.saturating_sub(1);
+    }
+}
+
+// Single characters are stored as-is, and all other
+// keys use the names found in keymap configuration.
+fn encode_key(key: &Key) -> String {
+    match *key {
+        Key::Char(c) => c.to_string(),
+        Key::Ctrl(c) => format!("ctrl-{}", c),
+        Key::Backspace => String::from("backspace"),
+        Key::Left => String::from("left"),
+        Key::Right => String::from("right"),
+        Key::Up => String::from("up"),
+        Key::Down => String::from("down"),
+        Key::Home => String::from("home"),
+        Key::End => String::from("end"),
+        Key::PageUp => String::from("page_up"),
+        Key::PageDown => String::from("page_down"),
+        Key::Delete => String::from("delete"),
+        Key::Insert => String::from("insert"),
+        Key::Esc => String::from("escape"),
+        Key::Tab => String::from("tab"),
+        Key::BackTab => String::from("shift-tab"),
+        Key::Enter => String::from("enter"),
+        Key::AnyChar => String::from("_"),
+    }
+}
+
+fn decode_key(data: &str) -> Option<Key> {
+    let mut characters = data.chars();
+    match (characters.next(), characters.next()) {
+        (Some(c), None) => Some(Key::Char(c)),
+        _ => parse_key(data).ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Macros, MAX_REPLAY_DEPTH};
+    use crate::input::Key;
+    use std::env;
+
+    #[test]
+    fn stop_recording_stores_recorded_keys() {
+        let mut macros = Macros::new(None);
+        macros.start_recording("a");
+        macros.record(Key::Char('x'));
+        macros.record(Key::Esc);
+        assert_eq!(macros.recording(), Some("a"));
+
+        macros.stop_recording().unwrap();
+        assert_eq!(macros.recording(), None);
+        assert_eq!(macros.get("a"), Some(&[Key::Char('x'), Key::Esc][..]));
+    }
+
+    #[test]
+    fn record_ignores_keys_while_replaying() {
+        let mut macros = Macros::new(None);
+        macros.start_recording("a");
+        macros.start_replay().unwrap();
+        macros.record(Key::Char('x'));
+        macros.finish_replay();
+        macros.record(Key::Char('y'));
+        macros.stop_recording().unwrap();
+
+        assert_eq!(macros.get("a"), Some(&[Key::Char('y')][..]));
+    }
+
+    #[test]
+    fn start_replay_refuses_to_exceed_maximum_depth() {
+        let mut macros = Macros::new(None);
+        for _ in 0..MAX_REPLAY_DEPTH {
+            macros.start_replay().unwrap();
+        }
+
+        assert!(macros.start_replay().is_err());
+    }
+
+    #[test]
+    fn save_writes_macros_that_can_be_loaded() {
+        let path = env::temp_dir().join("amp_macros_test.yml");
+        let keys = vec![Key::Char('-'), Key::Char(' '), Key::Ctrl('r'), Key::Enter, Key::Char('_')];
+        let mut macros = Macros::new(Some(path.clone()));
+        macros.start_recording("wrap");
+        for key in keys.iter() {
+            macros.record(key.clone());
+        }
+        macros.stop_recording().unwrap();
+        macros.save().unwrap();
+
+        let loaded = Macros::load_from(path);
+        assert_eq!(loaded.get("wrap"), Some(keys.as_slice()));
+    }
+}
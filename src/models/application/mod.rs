@@ -1,13 +1,27 @@
 
+mod auto_pairs;
 mod clipboard;
+mod cursors;
 mod event;
+mod history;
+mod last_change;
+mod macros;
 pub mod modes;
 mod preferences;
+mod snippets;
+mod undo_history;
 
 // Published API
+pub use self::auto_pairs::AutoPairs;
 pub use self::clipboard::ClipboardContent;
+pub use self::cursors::Cursors;
 pub use self::event::Event;
+pub use self::history::{History, HistoryKind, HistoryPosition};
+pub use self::last_change::LastChange;
+pub use self::macros::Macros;
 pub use self::preferences::Preferences;
+pub use self::snippets::Snippet;
+pub use self::undo_history::UndoHistory;
 
 use self::clipboard::Clipboard;
 use self::modes::*;
@@ -16,6 +30,7 @@ use crate::errors::*;
 use git2::Repository;
 use crate::presenters;
 use scribe::{Buffer, Workspace};
+use scribe::buffer::{Position, Range};
 use std::cell::RefCell;
 use std::env;
 use std::path::Path;
@@ -23,28 +38,58 @@ use std::rc::Rc;
 use std::sync::mpsc::{self, Receiver, Sender};
 use crate::view::View;
 
+/// The largest count that can be entered to repeat a command or macro.
+pub const MAX_COUNT: usize = 10_000;
+
 pub enum Mode {
+    Align(AlignMode),
+    ClipboardHistory(ClipboardHistoryMode),
+    Completion(CompletionMode),
     Confirm(ConfirmMode),
     Command(CommandMode),
     Exit,
+    Grep(GrepMode),
     Insert,
     Jump(JumpMode),
     LineJump(LineJumpMode),
+    Macro(MacroMode),
     Path(PathMode),
     Normal,
     Open(OpenMode),
+    Register(RegisterMode),
+    Replace(ReplaceMode),
     Select(SelectMode),
+    SelectBlock(SelectBlockMode),
     SelectLine(SelectLineMode),
     Search(SearchMode),
+    Snippet(SnippetMode),
+    Surround(SurroundMode),
     SymbolJump(SymbolJumpMode),
     Syntax(SyntaxMode),
+    TabStop(TabStopMode),
+    TextObject(TextObjectMode),
     Theme(ThemeMode),
 }
 
 pub struct Application {
     pub mode: Mode,
     pub workspace: Workspace,
+    pub cursors: Cursors,
     pub search_query: Option<String>,
+    pub history: History,
+    pub macros: Macros,
+    pub last_change: LastChange,
+    pub undo_history: UndoHistory,
+    pub search_options: SearchOptions,
+
+    /// Whether or not results for the last search query are highlighted
+    /// in normal mode; enabled when a query is accepted, until cleared.
+    pub highlight_search_results: bool,
+    search_highlights: Option<SearchHighlights>,
+
+    /// A count entered in normal mode, which repeats the next command.
+    pub count: Option<usize>,
+    pub auto_pairs: AutoPairs,
     pub view: View,
     pub clipboard: Clipboard,
     pub repository: Option<Repository>,
@@ -54,6 +99,16 @@ pub struct Application {
     events: Receiver<Event>,
 }
 
+// Search results highlighted in normal mode, along with
+// the buffer state and search they were produced from.
+struct SearchHighlights {
+    buffer_id: Option<usize>,
+    revision: Option<usize>,
+    query: String,
+    options: SearchOptions,
+    results: Vec<Range>,
+}
+
 impl Application {
     pub fn new(args: &Vec<String>) -> Result<Application> {
         let preferences = initialize_preferences();
@@ -63,16 +118,29 @@ impl Application {
         let clipboard = Clipboard::new();
 
         // Set up a workspace in the current directory.
-        let workspace = create_workspace(&mut view, &preferences.borrow(), args)?;
+        let mut undo_history = UndoHistory::load();
+        let (workspace, restore_error) = create_workspace(&mut view, &preferences.borrow(), &mut undo_history, args)?;
+        let search_options = preferences.borrow().search_options();
+        let error = preferences.borrow_mut().take_indent_rules_error().map(Error::from).or(restore_error);
 
         Ok(Application {
             mode: Mode::Normal,
             workspace,
+            cursors: Cursors::default(),
             search_query: None,
+            history: History::load(),
+            macros: Macros::load(),
+            last_change: LastChange::default(),
+            undo_history,
+            search_options,
+            highlight_search_results: false,
+            search_highlights: None,
+            count: None,
+            auto_pairs: AutoPairs::default(),
             view,
             clipboard,
             repository: Repository::discover(&env::current_dir()?).ok(),
-            error: None,
+            error,
             preferences,
             event_channel,
             events,
@@ -80,6 +148,12 @@ impl Application {
     }
 
     pub fn run(&mut self) -> Result<()> {
+        // Undo histories of deleted files are cleaned up here, rather than
+        // when they're loaded, so that only a running application does so.
+        if let Err(error) = self.undo_history.prune() {
+            self.error.get_or_insert(error);
+        }
+
         loop {
             self.render();
             self.wait_for_event()?;
@@ -89,7 +163,36 @@ impl Application {
             }
         }
 
-        Ok(())
+        self.persist()
+    }
+
+    // Saves input history, macros, and the undo history of every open buffer.
+    // Each is saved independently, so that one failing doesn't prevent the
+    // others from being saved; any failures are reported together afterwards.
+    fn persist(&mut self) -> Result<()> {
+        let mut errors = Vec::new();
+        if let Err(error) = self.history.save() {
+            errors.push(error);
+        }
+        if let Err(error) = self.macros.save() {
+            errors.push(error);
+        }
+
+        // Undo histories are only saved for buffers being closed,
+        // since saving one stops its changes from being recorded.
+        while let Some(buffer) = self.workspace.current_buffer() {
+            if let Err(error) = self.undo_history.save(buffer) {
+                errors.push(error);
+            }
+            self.workspace.close_current_buffer();
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            let messages: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+            bail!("Failed to save application data: {}", messages.join("; "))
+        }
     }
 
     fn render(&mut self) {
@@ -103,16 +206,50 @@ impl Application {
 
     fn present(&mut self) -> Result<()> {
         match self.mode {
+            Mode::ClipboardHistory(ref mut mode) => {
+                presenters::modes::search_select::display(&mut self.workspace, mode, &mut self.view)
+            }
             Mode::Confirm(_) => {
                 presenters::modes::confirm::display(&mut self.workspace, &mut self.view)
             }
             Mode::Command(ref mut mode) => {
                 presenters::modes::search_select::display(&mut self.workspace, mode, &mut self.view)
             }
-            Mode::Insert => presenters::modes::insert::display(&mut self.workspace, &mut self.view),
+            Mode::Grep(ref mut mode) => {
+                presenters::modes::search_select::display(&mut self.workspace, mode, &mut self.view)
+            }
+            Mode::Completion(ref mode) => {
+                presenters::modes::completion::display(
+                    &mut self.workspace,
+                    mode,
+                    &mut self.view,
+                    self.macros.recording(),
+                )
+            }
+            Mode::Insert => {
+                let cursors = self.secondary_cursors();
+                presenters::modes::insert::display(
+                    &mut self.workspace,
+                    &mut self.view,
+                    &cursors,
+                    self.macros.recording(),
+                )
+            }
+            Mode::Macro(ref mode) => {
+                presenters::modes::prompt::display(&mut self.workspace, mode, &mut self.view)
+            }
             Mode::Open(ref mut mode) => {
                 presenters::modes::search_select::display(&mut self.workspace, mode, &mut self.view)
             }
+            Mode::Align(ref mode) => {
+                presenters::modes::prompt::display(&mut self.workspace, mode, &mut self.view)
+            }
+            Mode::Register(ref mode) => {
+                presenters::modes::prompt::display(&mut self.workspace, mode, &mut self.view)
+            }
+            Mode::Replace(ref mode) => {
+                presenters::modes::replace::display(&mut self.workspace, mode, &mut self.view)
+            }
             Mode::Search(ref mode) => {
                 presenters::modes::search::display(&mut self.workspace, mode, &mut self.view)
             }
@@ -131,17 +268,45 @@ impl Application {
             Mode::Syntax(ref mut mode) => {
                 presenters::modes::search_select::display(&mut self.workspace, mode, &mut self.view)
             }
+            Mode::Snippet(ref mut mode) => {
+                presenters::modes::search_select::display(&mut self.workspace, mode, &mut self.view)
+            }
+            Mode::Surround(ref mode) => {
+                presenters::modes::prompt::display(&mut self.workspace, mode, &mut self.view)
+            }
+            Mode::TabStop(ref mode) => {
+                presenters::modes::tab_stop::display(
+                    &mut self.workspace,
+                    mode,
+                    &mut self.view,
+                    self.macros.recording(),
+                )
+            }
             Mode::Select(ref mode) => {
                 presenters::modes::select::display(&mut self.workspace, mode, &mut self.view)
             }
             Mode::SelectLine(ref mode) => {
                 presenters::modes::select_line::display(&mut self.workspace, mode, &mut self.view)
             }
-            Mode::Normal => presenters::modes::normal::display(
-                &mut self.workspace,
-                &mut self.view,
-                &self.repository,
-            ),
+            Mode::SelectBlock(ref mode) => {
+                presenters::modes::select_block::display(&mut self.workspace, mode, &mut self.view)
+            }
+            Mode::Normal => {
+                let highlights = self.selection_highlights().or_else(|| self.search_highlights());
+                let cursors = self.secondary_cursors();
+                presenters::modes::normal::display(
+                    &mut self.workspace,
+                    &mut self.view,
+                    &self.repository,
+                    highlights.as_deref(),
+                    &cursors,
+                    self.macros.recording(),
+                    self.count,
+                )
+            }
+            Mode::TextObject(ref mode) => {
+                presenters::modes::prompt::display(&mut self.workspace, mode, &mut self.view)
+            }
             Mode::Theme(ref mut mode) => {
                 presenters::modes::search_select::display(&mut self.workspace, mode, &mut self.view)
             }
@@ -149,6 +314,67 @@ impl Application {
         }
     }
 
+    fn secondary_cursors(&mut self) -> Vec<Position> {
+        match self.workspace.current_buffer() {
+            Some(buffer) => self.cursors.secondary_positions(buffer),
+            None => Vec::new(),
+        }
+    }
+
+    // Non-empty selections belonging to the current buffer's cursors.
+    fn selection_highlights(&mut self) -> Option<Vec<Range>> {
+        let buffer = self.workspace.current_buffer()?;
+        let selections: Vec<Range> = self.cursors
+            .selections(buffer)
+            .iter()
+            .filter(|selection| selection.start() != selection.end())
+            .cloned()
+            .collect();
+
+        if selections.is_empty() {
+            None
+        } else {
+            Some(selections)
+        }
+    }
+
+    // Re-runs the last search query against the current buffer, so that its
+    // results can be highlighted beyond the lifetime of the search mode. The
+    // results are cached until the query, options, or buffer content change.
+    fn search_highlights(&mut self) -> Option<Vec<Range>> {
+        if !self.highlight_search_results {
+            return None;
+        }
+
+        let query = self.search_query.clone()?;
+        let options = self.search_options;
+        let buffer = self.workspace.current_buffer()?;
+        let revision = self.view.buffer_revision(buffer);
+        let cached = self.search_highlights.as_ref().filter(|highlights| {
+            revision.is_some() &&
+                highlights.buffer_id == buffer.id &&
+                highlights.revision == revision &&
+                highlights.query == query &&
+                highlights.options == options
+        });
+        if let Some(highlights) = cached {
+            return Some(highlights.results.clone());
+        }
+
+        let mut mode = SearchMode::new(Some(query.clone()), options);
+        mode.search(buffer).ok()?;
+        let results = mode.results.map(|results| results.to_vec())?;
+        self.search_highlights = Some(SearchHighlights {
+            buffer_id: buffer.id,
+            revision,
+            query,
+            options,
+            results: results.clone(),
+        });
+
+        Some(results)
+    }
+
     fn wait_for_event(&mut self) -> Result<()> {
         let event = self
             .events
@@ -169,6 +395,15 @@ impl Application {
                     open_mode.search();
                 }
             }
+            Event::GrepModeIndexComplete(index) => {
+                if let Mode::Grep(ref mut grep_mode) = self.mode {
+                    grep_mode.set_index(index);
+
+                    // Trigger a search, in case a query was
+                    // entered while we were indexing.
+                    grep_mode.search();
+                }
+            }
         }
 
         Ok(())
@@ -186,6 +421,11 @@ impl Application {
             } else {
                 Some("search_select")
             },
+            Mode::Grep(ref mode) => if mode.insert_mode() {
+                Some("search_select_insert")
+            } else {
+                Some("search_select")
+            },
             Mode::Open(ref mode) => if mode.insert_mode() {
                 Some("search_select_insert")
             } else {
@@ -201,14 +441,41 @@ impl Application {
             } else {
                 Some("search_select")
             },
+            Mode::Snippet(ref mode) => if mode.insert_mode() {
+                Some("search_select_insert")
+            } else {
+                Some("search_select")
+            },
+            Mode::ClipboardHistory(ref mode) => if mode.insert_mode() {
+                Some("search_select_insert")
+            } else {
+                Some("search_select")
+            },
             Mode::Normal => Some("normal"),
             Mode::Path(_) => Some("path"),
             Mode::Confirm(_) => Some("confirm"),
             Mode::Insert => Some("insert"),
+            Mode::Completion(_) => Some("completion"),
+            Mode::TabStop(_) => Some("tab_stop"),
             Mode::Jump(_) => Some("jump"),
             Mode::LineJump(_) => Some("line_jump"),
+            Mode::Macro(_) => Some("macro"),
+            Mode::Align(_) => Some("align"),
+            Mode::Register(_) => Some("register"),
+            Mode::Surround(_) => Some("surround"),
             Mode::Select(_) => Some("select"),
             Mode::SelectLine(_) => Some("select_line"),
+            Mode::SelectBlock(_) => Some("select_block"),
+            Mode::TextObject(ref mode) => match mode.around {
+                Some(true) => Some("text_object_around"),
+                Some(false) => Some("text_object_inside"),
+                None => Some("text_object"),
+            },
+            Mode::Replace(ref mode) => if mode.insert_mode() {
+                Some("replace_insert")
+            } else {
+                Some("replace")
+            },
             Mode::Search(ref mode) => if mode.insert_mode() {
                 Some("search_insert")
             } else {
@@ -225,7 +492,10 @@ fn initialize_preferences() -> Rc<RefCell<Preferences>> {
     ))
 }
 
-fn create_workspace(view: &mut View, preferences: &Preferences, args: &Vec<String>) -> Result<Workspace> {
+// Opens the files specified in the arguments in a new workspace. Undo
+// histories that couldn't be restored don't prevent their files from being
+// opened; their errors are combined and returned alongside the workspace.
+fn create_workspace(view: &mut View, preferences: &Preferences, undo_history: &mut UndoHistory, args: &Vec<String>) -> Result<(Workspace, Option<Error>)> {
     // Discard the executable portion of the argument list.
     let mut path_args = args.iter().skip(1).peekable();
 
@@ -258,6 +528,7 @@ fn create_workspace(view: &mut View, preferences: &Preferences, args: &Vec<Strin
     if workspace_dir != initial_dir { path_args.next(); }
 
     // Try to open specified files.
+    let mut restore_errors = Vec::new();
     for path_arg in path_args {
         let path = Path::new(&path_arg);
 
@@ -275,6 +546,9 @@ fn create_workspace(view: &mut View, preferences: &Preferences, args: &Vec<Strin
         let argument_buffer = if path.exists() {
             let mut buffer = Buffer::from_file(path)?;
             buffer.syntax_definition = syntax_definition;
+            if let Err(error) = undo_history.restore(&mut buffer) {
+                restore_errors.push(error.to_string());
+            }
 
             buffer
         } else {
@@ -295,12 +569,18 @@ fn create_workspace(view: &mut View, preferences: &Preferences, args: &Vec<Strin
         view.initialize_buffer(workspace.current_buffer().unwrap())?;
     }
 
-    Ok(workspace)
+    let restore_error = if restore_errors.is_empty() {
+        None
+    } else {
+        Some(Error::from(format!("Failed to restore undo history: {}", restore_errors.join("; "))))
+    };
+
+    Ok((workspace, restore_error))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Application;
+    use super::{Application, UndoHistory};
     use crate::view::View;
     use super::preferences::Preferences;
 
@@ -348,11 +628,25 @@ mod tests {
         let mut view = View::new(preferences.clone(), event_channel.clone()).unwrap();
 
         let args = vec![String::new(), String::from("src/test.xyz")];
-        let mut workspace = super::create_workspace(&mut view, &preferences.borrow(), &args).unwrap();
+        let (mut workspace, _) = super::create_workspace(&mut view, &preferences.borrow(), &mut UndoHistory::new(None), &args).unwrap();
 
         assert_eq!(
             workspace.current_buffer().unwrap().syntax_definition.as_ref().unwrap().name,
             "Rust"
         );
     }
+
+    #[test]
+    fn search_highlights_are_recomputed_when_the_buffer_changes() {
+        let mut app = Application::new(&Vec::new()).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("amp");
+        crate::util::add_buffer(buffer, &mut app).unwrap();
+        app.search_query = Some(String::from("amp"));
+        app.highlight_search_results = true;
+        assert_eq!(app.search_highlights().map(|results| results.len()), Some(1));
+
+        app.workspace.current_buffer().unwrap().insert("amp ");
+        assert_eq!(app.search_highlights().map(|results| results.len()), Some(2));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,39 @@
+use regex::Regex;
+use std::fmt;
+
+/// Prompts for the delimiter (a string or regular expression)
+/// to align the lines that were selected on.
+pub struct AlignMode {
+    pub input: String,
+    pub regex: bool,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+impl AlignMode {
+    pub fn new(start_line: usize, end_line: usize) -> AlignMode {
+        AlignMode {
+            input: String::new(),
+            regex: true,
+            start_line,
+            end_line,
+        }
+    }
+
+    /// Whether the input is matched literally, either because regular
+    /// expressions have been turned off, or because it isn't a valid one.
+    pub fn literal(&self) -> bool {
+        !self.regex || Regex::new(&self.input).is_err()
+    }
+}
+
+impl fmt::Display for AlignMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Align on: {}", self.input)?;
+        if self.literal() {
+            write!(f, " [literal]")?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,160 @@
+use crate::models::application::ClipboardContent;
+use crate::models::application::modes::{SearchSelectMode, SearchSelectConfig};
+use crate::util::SelectableVec;
+use std::fmt;
+use std::slice::Iter;
+
+/// Lists previously copied and deleted content, newest first, so
+/// that any of it can be pasted. Queries filter entries to those
+/// containing the query, ignoring case.
+pub struct ClipboardHistoryMode {
+    insert: bool,
+    input: String,
+    entries: Vec<ClipboardContent>,
+    results: SelectableVec<ClipboardEntry>,
+    config: SearchSelectConfig,
+}
+
+impl ClipboardHistoryMode {
+    pub fn new(entries: Vec<ClipboardContent>, config: SearchSelectConfig) -> ClipboardHistoryMode {
+        ClipboardHistoryMode {
+            insert: true,
+            input: String::new(),
+            entries,
+            results: SelectableVec::new(Vec::new()),
+            config,
+        }
+    }
+}
+
+impl fmt::Display for ClipboardHistoryMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CLIPBOARD")
+    }
+}
+
+impl SearchSelectMode<ClipboardEntry> for ClipboardHistoryMode {
+    fn search(&mut self) {
+        let query = self.input.to_lowercase();
+        let results = self.entries
+            .iter()
+            .filter(|entry| match entry {
+                ClipboardContent::Inline(data) |
+                ClipboardContent::Block(data) |
+                ClipboardContent::Rectangle(data) => data.to_lowercase().contains(&query),
+                ClipboardContent::None => false,
+            })
+            .take(self.config.max_results)
+            .map(|entry| ClipboardEntry(entry.clone()))
+            .collect();
+
+        self.results = SelectableVec::new(results);
+    }
+
+    fn query(&mut self) -> &mut String {
+        &mut self.input
+    }
+
+    fn insert_mode(&self) -> bool {
+        self.insert
+    }
+
+    fn set_insert_mode(&mut self, insert_mode: bool) {
+        self.insert = insert_mode;
+    }
+
+    fn results(&self) -> Iter<'_, ClipboardEntry> {
+        self.results.iter()
+    }
+
+    fn selection(&self) -> Option<&ClipboardEntry> {
+        self.results.selection()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.results.selected_index()
+    }
+
+    fn select_previous(&mut self) {
+        self.results.select_previous();
+    }
+
+    fn select_next(&mut self) {
+        self.results.select_next();
+    }
+
+    fn config(&self) -> &SearchSelectConfig {
+        &self.config
+    }
+
+    fn message(&mut self) -> Option<String> {
+        if self.entries.is_empty() {
+            Some(String::from("Nothing has been copied or deleted yet."))
+        } else if self.results().count() == 0 {
+            Some(String::from("No matching entries found."))
+        } else {
+            None
+        }
+    }
+}
+
+// Newtype to make clipboard content presentable (via the Display trait),
+// which is required for any type used in search/select mode. Only the
+// first line is shown, since results are limited to a single line each.
+pub struct ClipboardEntry(pub ClipboardContent);
+
+impl fmt::Display for ClipboardEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (kind, data) = match self.0 {
+            ClipboardContent::Inline(ref data) => ("inline", data),
+            ClipboardContent::Block(ref data) => ("block", data),
+            ClipboardContent::Rectangle(ref data) => ("rectangle", data),
+            ClipboardContent::None => return Ok(()),
+        };
+        let mut lines = data.lines();
+        write!(f, "{}: {}", kind, lines.next().unwrap_or(""))?;
+
+        let remaining_lines = lines.count();
+        if remaining_lines > 0 {
+            write!(f, " (+{} lines)", remaining_lines)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClipboardEntry, ClipboardHistoryMode};
+    use crate::models::application::ClipboardContent;
+    use crate::models::application::modes::{SearchSelectMode, SearchSelectConfig};
+
+    #[test]
+    fn search_filters_entries_preserving_their_order() {
+        let mut mode = ClipboardHistoryMode::new(
+            vec![
+                ClipboardContent::Block(String::from("amp\neditor\n")),
+                ClipboardContent::Inline(String::from("scribe")),
+                ClipboardContent::Inline(String::from("Amp")),
+            ],
+            SearchSelectConfig::default()
+        );
+        mode.query().push_str("amp");
+        mode.search();
+
+        let results: Vec<String> = mode.results().map(|r| r.to_string()).collect();
+        assert_eq!(results, vec!["block: amp (+1 lines)", "inline: Amp"]);
+    }
+
+    #[test]
+    fn entries_are_listed_without_a_query() {
+        let mut mode = ClipboardHistoryMode::new(
+            vec![ClipboardContent::Inline(String::from("amp"))],
+            SearchSelectConfig::default()
+        );
+        mode.search();
+
+        assert!(mode.message().is_none());
+        assert_eq!(mode.selection().map(|ClipboardEntry(c)| c), Some(&ClipboardContent::Inline(String::from("amp"))));
+    }
+}
@@ -0,0 +1,79 @@
+use fragment;
+use crate::util::SelectableVec;
+use scribe::buffer::Position;
+use std::slice::Iter;
+use crate::models::application::modes::SearchSelectConfig;
+
+/// Completes the partial identifier preceding the cursor (which starts at
+/// the `start` position) using identifiers found in the open buffers.
+pub struct CompletionMode {
+    pub start: Position,
+    identifiers: Vec<String>,
+    results: SelectableVec<String>,
+    config: SearchSelectConfig,
+}
+
+impl CompletionMode {
+    pub fn new(start: Position, identifiers: Vec<String>, config: SearchSelectConfig) -> CompletionMode {
+        CompletionMode {
+            start,
+            identifiers,
+            results: SelectableVec::new(Vec::new()),
+            config,
+        }
+    }
+
+    /// Finds identifiers matching the partial identifier, other than the
+    /// partial identifier itself, which has nothing left to complete.
+    pub fn search(&mut self, query: &str) {
+        let candidates = self.identifiers.iter().filter(|identifier| *identifier != query);
+        let results = fragment::matching::find(query, candidates, self.config.max_results);
+
+        self.results = SelectableVec::new(
+            results
+            .into_iter()
+            .map(|result| (*result).clone())
+            .collect()
+        );
+    }
+
+    pub fn results(&self) -> Iter<'_, String> {
+        self.results.iter()
+    }
+
+    pub fn selection(&self) -> Option<&String> {
+        self.results.selection()
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.results.selected_index()
+    }
+
+    pub fn select_previous(&mut self) {
+        self.results.select_previous();
+    }
+
+    pub fn select_next(&mut self) {
+        self.results.select_next();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::application::modes::SearchSelectConfig;
+    use scribe::buffer::Position;
+    use super::CompletionMode;
+
+    #[test]
+    fn search_excludes_the_partial_identifier() {
+        let identifiers = vec!["amp".to_string(), "ampersand".to_string(), "scribe".to_string()];
+        let mut mode = CompletionMode::new(
+            Position{ line: 0, offset: 0 },
+            identifiers,
+            SearchSelectConfig::default()
+        );
+        mode.search("amp");
+
+        assert_eq!(mode.results().collect::<Vec<&String>>(), vec!["ampersand"]);
+    }
+}
@@ -0,0 +1,346 @@
+use bloodhound::ExclusionPattern;
+use crate::models::application::Event;
+use crate::models::application::modes::{SearchSelectMode, SearchSelectConfig};
+use crate::util::SelectableVec;
+use regex::{self, RegexBuilder};
+use scribe::buffer::Position;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::slice::Iter;
+use std::sync::mpsc::Sender;
+use std::thread;
+use unicode_segmentation::UnicodeSegmentation;
+
+// Files larger than this are almost always generated or minified,
+// and aren't worth the time required to search through them.
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+#[derive(PartialEq)]
+pub enum GrepModeIndex {
+    Complete(ContentIndex),
+    Indexing(PathBuf)
+}
+
+/// Searches the contents of every file in the workspace, listing matching
+/// lines. The files to search are listed on a separate thread.
+pub struct GrepMode {
+    pub insert: bool,
+    pub input: String,
+    index: GrepModeIndex,
+    pub results: SelectableVec<GrepResult>,
+    config: SearchSelectConfig,
+}
+
+impl GrepMode {
+    pub fn new(path: PathBuf, exclusions: Option<Vec<ExclusionPattern>>, events: Sender<Event>, config: SearchSelectConfig) -> GrepMode {
+        // Build and populate the index in a separate thread.
+        let index_path = path.clone();
+        thread::spawn(move || {
+            let index = ContentIndex::new(&index_path, exclusions.as_ref());
+            let _ = events.send(
+                Event::GrepModeIndexComplete(index)
+            );
+        });
+
+        GrepMode {
+            insert: true,
+            input: String::new(),
+            index: GrepModeIndex::Indexing(path),
+            results: SelectableVec::new(Vec::new()),
+            config,
+        }
+    }
+
+    pub fn set_index(&mut self, index: ContentIndex) {
+        self.index = GrepModeIndex::Complete(index)
+    }
+}
+
+impl fmt::Display for GrepMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "GREP")
+    }
+}
+
+impl SearchSelectMode<GrepResult> for GrepMode {
+    fn search(&mut self) {
+        let results =
+            if let GrepModeIndex::Complete(ref index) = self.index {
+                index.find(&self.input, self.config.max_results)
+            } else {
+                vec![]
+            };
+
+        self.results = SelectableVec::new(results);
+    }
+
+    fn query(&mut self) -> &mut String {
+        &mut self.input
+    }
+
+    fn insert_mode(&self) -> bool {
+        self.insert
+    }
+
+    fn set_insert_mode(&mut self, insert_mode: bool) {
+        self.insert = insert_mode;
+    }
+
+    fn results(&self) -> Iter<'_, GrepResult> {
+        self.results.iter()
+    }
+
+    fn selection(&self) -> Option<&GrepResult> {
+        self.results.selection()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.results.selected_index()
+    }
+
+    fn select_previous(&mut self) {
+        self.results.select_previous();
+    }
+
+    fn select_next(&mut self) {
+        self.results.select_next();
+    }
+
+    fn config(&self) -> &SearchSelectConfig {
+        &self.config
+    }
+
+    fn message(&mut self) -> Option<String> {
+        if let GrepModeIndex::Indexing(ref path) = self.index {
+            Some(format!("Indexing {}", path.to_string_lossy()))
+        } else if self.query().is_empty() {
+            Some(String::from("Enter a search query to start."))
+        } else if self.results().count() == 0 {
+            Some(String::from("No matching lines found."))
+        } else {
+            None
+        }
+    }
+}
+
+/// A line matching a grep mode query.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GrepResult {
+    pub path: PathBuf,
+    pub position: Position,
+    pub content: String,
+}
+
+impl fmt::Display for GrepResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}",
+            self.path.to_string_lossy(),
+            self.position.line + 1,
+            self.content.trim()
+        )
+    }
+}
+
+/// The paths of every (non-excluded) file beneath a path, relative to it.
+/// File contents aren't held in the index; they're read one file at a time
+/// while searching, until enough matching lines have been found.
+#[derive(Debug, PartialEq)]
+pub struct ContentIndex {
+    root: PathBuf,
+    paths: Vec<PathBuf>,
+}
+
+impl ContentIndex {
+    pub fn new(path: &Path, exclusions: Option<&Vec<ExclusionPattern>>) -> ContentIndex {
+        let mut paths = Vec::new();
+        index_directory(path, path, exclusions, &mut paths);
+        paths.sort();
+
+        ContentIndex { root: path.to_path_buf(), paths }
+    }
+
+    /// Finds lines containing the query, ignoring case
+    /// unless the query contains an uppercase character.
+    pub fn find(&self, query: &str, limit: usize) -> Vec<GrepResult> {
+        let mut results = Vec::new();
+        if query.is_empty() {
+            return results;
+        }
+
+        // Matching case-insensitively on the original lines, rather than
+        // lowercased copies, keeps offsets valid for characters whose
+        // lowercase forms have a different length (e.g. 'İ').
+        let ignore_case = !query.chars().any(char::is_uppercase);
+        let expression = match RegexBuilder::new(&regex::quote(query))
+            .case_insensitive(ignore_case)
+            .compile() {
+            Ok(expression) => expression,
+            Err(_) => return results,
+        };
+
+        for path in &self.paths {
+            // Binary (non-UTF-8) files can't be read as strings, and are skipped.
+            let content = match fs::read_to_string(self.root.join(path)) {
+                Ok(content) => content,
+                Err(_) => continue,
+            };
+
+            for (line_number, line) in content.lines().enumerate() {
+                if let Some((index, _)) = expression.find(line) {
+                    results.push(GrepResult {
+                        path: path.clone(),
+                        position: Position {
+                            line: line_number,
+                            offset: line[..index].graphemes(true).count(),
+                        },
+                        content: line.to_string(),
+                    });
+
+                    if results.len() == limit {
+                        return results;
+                    }
+                }
+            }
+        }
+
+        results
+    }
+}
+
+// Recursively lists the files beneath the specified directory, skipping
+// those matching any of the exclusion patterns, as well as large files.
+fn index_directory(root: &Path, directory: &Path, exclusions: Option<&Vec<ExclusionPattern>>, paths: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        let excluded = exclusions.map(|exclusions| {
+            exclusions.iter().any(|exclusion| {
+                exclusion.matches(path.to_string_lossy().as_ref())
+            })
+        }).unwrap_or(false);
+        if excluded {
+            continue;
+        }
+
+        // Entry metadata doesn't follow symbolic links,
+        // which could otherwise lead us around in circles.
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+
+        if metadata.is_dir() {
+            index_directory(root, &path, exclusions, paths);
+        } else if metadata.is_file() && metadata.len() <= MAX_FILE_SIZE {
+            if let Ok(relative_path) = path.strip_prefix(root) {
+                paths.push(relative_path.to_path_buf());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bloodhound::ExclusionPattern;
+    use scribe::buffer::Position;
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process;
+    use super::{ContentIndex, GrepResult};
+
+    // Builds a uniquely-named directory with sample files to be indexed,
+    // qualified by the process ID, so that concurrent test runs don't share it.
+    fn sample_directory(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("amp_grep_mode_{}_{}", process::id(), name));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(path.join("src")).unwrap();
+        fs::write(path.join("README"), "Amp is a text editor.\n").unwrap();
+        fs::write(path.join("src/lib.rs"), "mod amp;\n\nfn main() {\n    editor();\n}\n").unwrap();
+        fs::write(path.join("binary"), [0xff, 0xfe, 0x00]).unwrap();
+
+        path
+    }
+
+    #[test]
+    fn find_returns_matching_lines_with_their_positions() {
+        let path = sample_directory("positions");
+        let index = ContentIndex::new(&path, None);
+
+        assert_eq!(
+            index.find("editor", 5),
+            vec![
+                GrepResult {
+                    path: PathBuf::from("README"),
+                    position: Position{ line: 0, offset: 14 },
+                    content: String::from("Amp is a text editor."),
+                },
+                GrepResult {
+                    path: PathBuf::from("src/lib.rs"),
+                    position: Position{ line: 3, offset: 4 },
+                    content: String::from("    editor();"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn find_ignores_case_for_lowercase_queries() {
+        let path = sample_directory("lowercase");
+        let index = ContentIndex::new(&path, None);
+
+        assert_eq!(index.find("amp", 5).len(), 2);
+    }
+
+    #[test]
+    fn find_respects_case_for_queries_with_uppercase_characters() {
+        let path = sample_directory("uppercase");
+        let index = ContentIndex::new(&path, None);
+
+        assert_eq!(index.find("Amp", 5).len(), 1);
+    }
+
+    #[test]
+    fn find_maps_offsets_for_case_insensitive_matches_on_the_original_line() {
+        let path = sample_directory("offsets");
+        fs::write(path.join("README"), "İstanbul amp\n").unwrap();
+        let index = ContentIndex::new(&path, None);
+
+        assert_eq!(index.find("amp", 1)[0].position, Position{ line: 0, offset: 9 });
+    }
+
+    #[test]
+    fn find_respects_the_result_limit() {
+        let path = sample_directory("limit");
+        let index = ContentIndex::new(&path, None);
+
+        assert_eq!(index.find("amp", 1).len(), 1);
+    }
+
+    #[test]
+    fn new_skips_excluded_paths() {
+        let path = sample_directory("exclusions");
+        let exclusions = vec![ExclusionPattern::new("**/src").unwrap()];
+        let index = ContentIndex::new(&path, Some(&exclusions));
+
+        assert_eq!(index.find("amp", 5).len(), 1);
+    }
+
+    #[test]
+    fn display_includes_path_line_number_and_trimmed_content() {
+        let result = GrepResult {
+            path: PathBuf::from("src/lib.rs"),
+            position: Position{ line: 3, offset: 4 },
+            content: String::from("    editor();"),
+        };
+
+        assert_eq!(result.to_string(), "src/lib.rs:4: editor();");
+    }
+}
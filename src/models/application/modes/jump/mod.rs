@@ -6,6 +6,7 @@ use crate::util::movement_lexer;
 use std::collections::HashMap;
 use scribe::buffer::{Distance, Position};
 use crate::models::application::modes::select::SelectMode;
+use crate::models::application::modes::select_block::SelectBlockMode;
 use crate::models::application::modes::select_line::SelectLineMode;
 use self::tag_generator::TagGenerator;
 use self::single_character_tag_generator::SingleCharacterTagGenerator;
@@ -17,6 +18,7 @@ pub enum SelectModeOptions {
     None,
     Select(SelectMode),
     SelectLine(SelectLineMode),
+    SelectBlock(SelectBlockMode),
 }
 
 enum MappedLexemeValue {
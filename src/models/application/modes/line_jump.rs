@@ -1,6 +1,9 @@
+use crate::models::application::HistoryPosition;
+
 #[derive(Default)]
 pub struct LineJumpMode {
     pub input: String,
+    pub history: HistoryPosition,
 }
 
 impl LineJumpMode {
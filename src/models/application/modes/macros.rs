@@ -0,0 +1,66 @@
+use crate::models::application::MAX_COUNT;
+use std::fmt;
+
+/// What to do with the macro named by the next key press.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MacroAction {
+    Record,
+    Replay,
+}
+
+/// Prompts for the single-character name of a macro to record or replay.
+/// Replays can be preceded by a count, to run the macro repeatedly.
+pub struct MacroMode {
+    pub action: MacroAction,
+    pub count: String,
+}
+
+impl MacroMode {
+    pub fn new(action: MacroAction) -> MacroMode {
+        MacroMode {
+            action,
+            count: String::new(),
+        }
+    }
+
+    /// The number of times to replay the macro, which defaults to once
+    /// and is capped at the largest count accepted in normal mode.
+    pub fn count(&self) -> usize {
+        if self.count.is_empty() {
+            return 1;
+        }
+
+        // Digits only fail to parse when they overflow.
+        self.count.parse().unwrap_or(MAX_COUNT).min(MAX_COUNT)
+    }
+}
+
+impl fmt::Display for MacroMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.action {
+            MacroAction::Record => write!(f, "Record macro: "),
+            MacroAction::Replay => write!(f, "Replay macro: {}", self.count),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MacroAction, MacroMode, MAX_COUNT};
+
+    #[test]
+    fn count_defaults_to_one() {
+        let mut mode = MacroMode::new(MacroAction::Replay);
+        assert_eq!(mode.count(), 1);
+
+        mode.count.push_str("12");
+        assert_eq!(mode.count(), 12);
+    }
+
+    #[test]
+    fn count_is_capped() {
+        let mut mode = MacroMode::new(MacroAction::Replay);
+        mode.count.push_str("99999999999999999999999");
+        assert_eq!(mode.count(), MAX_COUNT);
+    }
+}
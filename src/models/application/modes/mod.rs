@@ -1,27 +1,51 @@
+mod align;
+mod clipboard_history;
+mod completion;
 mod confirm;
 mod command;
+mod grep;
 pub mod jump;
 mod line_jump;
+mod macros;
 pub mod open;
 mod path;
+mod register;
+mod replace;
 mod search;
 mod search_select;
 mod select;
+mod select_block;
 mod select_line;
+mod snippet;
+mod surround;
 mod symbol_jump;
 mod syntax;
+mod tab_stop;
+mod text_object;
 mod theme;
 
+pub use self::align::AlignMode;
+pub use self::clipboard_history::{ClipboardEntry, ClipboardHistoryMode};
+pub use self::completion::CompletionMode;
 pub use self::confirm::ConfirmMode;
 pub use self::command::CommandMode;
+pub use self::grep::{ContentIndex, GrepMode};
 pub use self::jump::JumpMode;
 pub use self::line_jump::LineJumpMode;
+pub use self::macros::{MacroAction, MacroMode};
 pub use self::path::PathMode;
 pub use self::open::OpenMode;
-pub use self::search::SearchMode;
+pub use self::register::{RegisterAction, RegisterMode};
+pub use self::replace::ReplaceMode;
+pub use self::search::{SearchMode, SearchOptions};
 pub use self::search_select::{SearchSelectMode, SearchSelectConfig};
 pub use self::select::SelectMode;
+pub use self::select_block::SelectBlockMode;
 pub use self::select_line::SelectLineMode;
+pub use self::snippet::SnippetMode;
+pub use self::surround::{SurroundAction, SurroundMode};
 pub use self::symbol_jump::SymbolJumpMode;
 pub use self::syntax::SyntaxMode;
+pub use self::tab_stop::TabStopMode;
+pub use self::text_object::{TextObjectMode, TextObjectOperation};
 pub use self::theme::ThemeMode;
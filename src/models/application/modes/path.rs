@@ -1,15 +1,18 @@
+use crate::models::application::HistoryPosition;
 use std::fmt;
 
 pub struct PathMode {
     pub input: String,
     pub save_on_accept: bool,
+    pub history: HistoryPosition,
 }
 
 impl PathMode {
     pub fn new(initial_path: String) -> PathMode {
         PathMode {
             input: initial_path,
-            save_on_accept: false
+            save_on_accept: false,
+            history: HistoryPosition::default(),
         }
     }
     pub fn push_char(&mut self, c: char) {
@@ -0,0 +1,31 @@
+use crate::models::application::ClipboardContent;
+use std::fmt;
+
+/// What to do with the register named by the next key press. Selections
+/// are captured up front, since the select mode is left to prompt for it.
+#[derive(Debug, PartialEq)]
+pub enum RegisterAction {
+    Copy(ClipboardContent),
+    Paste,
+}
+
+/// Prompts for the single-character name of a register,
+/// to copy the selection into, or paste from.
+pub struct RegisterMode {
+    pub action: RegisterAction,
+}
+
+impl RegisterMode {
+    pub fn new(action: RegisterAction) -> RegisterMode {
+        RegisterMode { action }
+    }
+}
+
+impl fmt::Display for RegisterMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.action {
+            RegisterAction::Copy(_) => write!(f, "Copy to register: "),
+            RegisterAction::Paste => write!(f, "Paste from register: "),
+        }
+    }
+}
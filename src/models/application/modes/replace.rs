@@ -0,0 +1,34 @@
+use crate::models::application::modes::SearchMode;
+use std::fmt;
+
+/// Replaces search results one at a time, prompting for the replacement
+/// text first, and then for a decision on each individual match.
+pub struct ReplaceMode {
+    pub insert: bool,
+    pub input: Option<String>,
+    pub search: SearchMode,
+
+    /// The number of matches that haven't yet been replaced or skipped.
+    pub remaining: usize,
+}
+
+impl ReplaceMode {
+    pub fn new(search: SearchMode) -> ReplaceMode {
+        ReplaceMode {
+            insert: true,
+            input: None,
+            search,
+            remaining: 0,
+        }
+    }
+
+    pub fn insert_mode(&self) -> bool {
+        self.insert
+    }
+}
+
+impl fmt::Display for ReplaceMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "REPLACE")
+    }
+}
@@ -1,21 +1,54 @@
-
 use crate::errors::*;
+use crate::models::application::HistoryPosition;
 use crate::util::SelectableVec;
+use regex::{Regex, RegexBuilder};
+use std::collections::HashMap;
 use std::fmt;
-use scribe::buffer::{Buffer, Distance, Range};
+use scribe::buffer::{Buffer, Distance, Position, Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Options that qualify how a search query is matched against buffer
+/// content. These outlive any single search mode instance, so that
+/// re-entering search mode (e.g. to jump to the next match from normal
+/// mode) matches the query the same way it was matched originally.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SearchOptions {
+    pub regex: bool,
+    pub case_insensitive: bool,
+
+    /// Ignores case unless the query contains an uppercase
+    /// character. Takes precedence over `case_insensitive`.
+    pub smart_case: bool,
+    pub whole_word: bool,
+}
+
+impl SearchOptions {
+    /// Whether or not the specified query should be matched case-insensitively.
+    pub fn ignore_case(&self, query: &str) -> bool {
+        if self.smart_case {
+            !query.chars().any(char::is_uppercase)
+        } else {
+            self.case_insensitive
+        }
+    }
+}
 
 pub struct SearchMode {
     pub insert: bool,
     pub input: Option<String>,
+    pub options: SearchOptions,
     pub results: Option<SelectableVec<Range>>,
+    pub history: HistoryPosition,
 }
 
 impl SearchMode {
-    pub fn new(query: Option<String>) -> SearchMode {
+    pub fn new(query: Option<String>, options: SearchOptions) -> SearchMode {
         SearchMode {
             insert: true,
             input: query,
+            options,
             results: None,
+            history: HistoryPosition::default(),
         }
     }
 
@@ -26,23 +59,81 @@ impl SearchMode {
     // Searches the specified buffer for the input string
     // and stores the result as a collection of ranges.
     pub fn search(&mut self, buffer: &Buffer) -> Result<()> {
-        let query = self.input.as_ref().ok_or(SEARCH_QUERY_MISSING)?;
-        let distance = Distance::of_str(&query);
-
-        // Buffer search returns match starting positions, but we'd like ranges.
-        // This maps the positions to ranges using the search query distance
-        // before storing them.
-        self.results = Some(
-            SelectableVec::new(
-                buffer.search(&query)
-                    .into_iter()
-                    .map(|start| Range::new(start, start + distance))
-                    .collect()
-            )
-        );
+        let query = self.input
+            .as_ref()
+            .filter(|query| !query.is_empty())
+            .ok_or(SEARCH_QUERY_MISSING)?;
+
+        let results = if let Some(expression) = self.expression()? {
+            regex_search(buffer, &expression)
+        } else {
+            literal_search(buffer, query)
+        };
+        self.results = Some(SelectableVec::new(results));
 
         Ok(())
     }
+
+    /// Builds the text that will replace each of the specified matches,
+    /// expanding capture group references (e.g. `$1`) in the template
+    /// when the query is a regular expression.
+    pub fn replacements(&self, buffer: &Buffer, matches: &[Range], template: &str) -> Result<Vec<String>> {
+        if !self.options.regex {
+            return Ok(vec![template.to_string(); matches.len()]);
+        }
+        let expression = self.expression()?.ok_or(SEARCH_QUERY_MISSING)?;
+
+        // Expand the template for every match in the buffer, keyed by its
+        // start position, so that we only need a single pass over the data.
+        let data = buffer.data();
+        let mut positions = PositionMapper::new(&data);
+        let mut expansions = HashMap::new();
+        for captures in expression.captures_iter(&data) {
+            if let Some((start, _)) = captures.pos(0) {
+                let position = positions.map(start);
+                expansions.insert((position.line, position.offset), captures.expand(template));
+            }
+        }
+
+        matches.iter().map(|range| {
+            let start = range.start();
+            expansions
+                .remove(&(start.line, start.offset))
+                .ok_or_else(|| "Search result no longer matches the query".into())
+        }).collect()
+    }
+
+    // Compiles the query into a regular expression, or returns None if
+    // the current options can be satisfied using a literal search.
+    fn expression(&self) -> Result<Option<Regex>> {
+        let query = self.input.as_ref().ok_or(SEARCH_QUERY_MISSING)?;
+        let ignore_case = self.options.ignore_case(query);
+        if !self.options.regex && !self.options.whole_word && !ignore_case {
+            return Ok(None);
+        }
+
+        // Literal queries are escaped so that they can
+        // take advantage of the regex-based options.
+        let mut pattern = if self.options.regex {
+            query.clone()
+        } else {
+            regex::quote(query)
+        };
+        if self.options.whole_word {
+            pattern = format!(r"\b(?:{})\b", pattern);
+        }
+
+        // Let anchors match at line boundaries, rather than only
+        // at the start and end of the buffer, which is more in
+        // line with what users expect from a text editor.
+        let expression = RegexBuilder::new(&pattern)
+            .case_insensitive(ignore_case)
+            .multi_line(true)
+            .compile()
+            .map_err(|e| format!("Invalid regular expression: {}", e))?;
+
+        Ok(Some(expression))
+    }
 }
 
 impl fmt::Display for SearchMode {
@@ -51,17 +142,77 @@ impl fmt::Display for SearchMode {
     }
 }
 
+fn literal_search(buffer: &Buffer, query: &str) -> Vec<Range> {
+    let distance = Distance::of_str(&query);
+
+    // Buffer search returns match starting positions, but we'd like ranges.
+    // This maps the positions to ranges using the search query distance.
+    buffer.search(&query)
+        .into_iter()
+        .map(|start| Range::new(start, start + distance))
+        .collect()
+}
+
+fn regex_search(buffer: &Buffer, expression: &Regex) -> Vec<Range> {
+    let data = buffer.data();
+    let mut positions = PositionMapper::new(&data);
+
+    // Empty matches (e.g. a lone "^") can't be selected or
+    // navigated to in any meaningful way, so we skip them.
+    expression.find_iter(&data)
+        .filter(|&(start, end)| start != end)
+        .map(|(start, end)| {
+            let start_position = positions.map(start);
+            Range::new(start_position, positions.map(end))
+        })
+        .collect()
+}
+
+/// Translates byte offsets in a buffer's data to their (grapheme-based)
+/// position equivalents. Offsets must be mapped in ascending order,
+/// which lets us walk the data once, regardless of the match count.
+struct PositionMapper<'a> {
+    data: &'a str,
+    byte_offset: usize,
+    position: Position,
+}
+
+impl<'a> PositionMapper<'a> {
+    fn new(data: &'a str) -> PositionMapper<'a> {
+        PositionMapper {
+            data,
+            byte_offset: 0,
+            position: Position { line: 0, offset: 0 },
+        }
+    }
+
+    fn map(&mut self, byte_offset: usize) -> Position {
+        for grapheme in self.data[self.byte_offset..byte_offset].graphemes(true) {
+            // Windows line endings are a single grapheme.
+            if grapheme == "\n" || grapheme == "\r\n" {
+                self.position.line += 1;
+                self.position.offset = 0;
+            } else {
+                self.position.offset += 1;
+            }
+        }
+        self.byte_offset = byte_offset;
+
+        self.position
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use scribe::buffer::{Buffer, Position, Range};
-    use super::SearchMode;
+    use super::{SearchMode, SearchOptions};
 
     #[test]
     fn search_populates_results_with_correct_ranges() {
         let mut buffer = Buffer::new();
         buffer.insert("test\ntest");
 
-        let mut mode = SearchMode::new(Some(String::from("test")));
+        let mut mode = SearchMode::new(Some(String::from("test")), SearchOptions::default());
         mode.search(&buffer).unwrap();
 
         assert_eq!(
@@ -78,4 +229,215 @@ mod tests {
             ]
         );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn regex_search_populates_results_with_variable_length_ranges() {
+        let mut buffer = Buffer::new();
+        buffer.insert("amp édits\nediting");
+
+        let options = SearchOptions { regex: true, ..Default::default() };
+        let mut mode = SearchMode::new(Some(String::from(r"[eé]dit\w*")), options);
+        mode.search(&buffer).unwrap();
+
+        assert_eq!(
+            *mode.results.unwrap(),
+            vec![
+                Range::new(
+                    Position{ line: 0, offset: 4 },
+                    Position{ line: 0, offset: 9 },
+                ),
+                Range::new(
+                    Position{ line: 1, offset: 0 },
+                    Position{ line: 1, offset: 7 },
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn regex_search_maps_matches_following_crlf_line_endings() {
+        let mut buffer = Buffer::new();
+        buffer.insert("amp\r\neditor\r\namp");
+
+        let options = SearchOptions { regex: true, ..Default::default() };
+        let mut mode = SearchMode::new(Some(String::from("amp|dit")), options);
+        mode.search(&buffer).unwrap();
+
+        assert_eq!(
+            *mode.results.unwrap(),
+            vec![
+                Range::new(
+                    Position{ line: 0, offset: 0 },
+                    Position{ line: 0, offset: 3 },
+                ),
+                Range::new(
+                    Position{ line: 1, offset: 1 },
+                    Position{ line: 1, offset: 4 },
+                ),
+                Range::new(
+                    Position{ line: 2, offset: 0 },
+                    Position{ line: 2, offset: 3 },
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn regex_search_anchors_match_at_line_boundaries() {
+        let mut buffer = Buffer::new();
+        buffer.insert("amp\neditor\namp");
+
+        let options = SearchOptions { regex: true, ..Default::default() };
+        let mut mode = SearchMode::new(Some(String::from("^amp$")), options);
+        mode.search(&buffer).unwrap();
+
+        assert_eq!(
+            *mode.results.unwrap(),
+            vec![
+                Range::new(
+                    Position{ line: 0, offset: 0 },
+                    Position{ line: 0, offset: 3 },
+                ),
+                Range::new(
+                    Position{ line: 2, offset: 0 },
+                    Position{ line: 2, offset: 3 },
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn regex_search_skips_empty_matches() {
+        let mut buffer = Buffer::new();
+        buffer.insert("amp\neditor");
+
+        let options = SearchOptions { regex: true, ..Default::default() };
+        let mut mode = SearchMode::new(Some(String::from("^")), options);
+        mode.search(&buffer).unwrap();
+
+        assert!(mode.results.unwrap().is_empty());
+    }
+
+    #[test]
+    fn regex_search_returns_an_error_for_invalid_patterns() {
+        let mut buffer = Buffer::new();
+        buffer.insert("amp");
+
+        let options = SearchOptions { regex: true, ..Default::default() };
+        let mut mode = SearchMode::new(Some(String::from("(amp")), options);
+
+        assert!(mode.search(&buffer).is_err());
+        assert!(mode.results.is_none());
+    }
+
+    #[test]
+    fn case_insensitive_search_ignores_case() {
+        let mut buffer = Buffer::new();
+        buffer.insert("Amp\namp");
+
+        let options = SearchOptions { case_insensitive: true, ..Default::default() };
+        let mut mode = SearchMode::new(Some(String::from("AMP")), options);
+        mode.search(&buffer).unwrap();
+
+        assert_eq!(mode.results.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn smart_case_search_ignores_case_for_lowercase_queries() {
+        let mut buffer = Buffer::new();
+        buffer.insert("Amp\namp");
+
+        let options = SearchOptions { smart_case: true, ..Default::default() };
+        let mut mode = SearchMode::new(Some(String::from("amp")), options);
+        mode.search(&buffer).unwrap();
+
+        assert_eq!(mode.results.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn smart_case_search_respects_case_for_queries_with_uppercase_characters() {
+        let mut buffer = Buffer::new();
+        buffer.insert("Amp\namp");
+
+        let options = SearchOptions { smart_case: true, case_insensitive: true, ..Default::default() };
+        let mut mode = SearchMode::new(Some(String::from("Amp")), options);
+        mode.search(&buffer).unwrap();
+
+        assert_eq!(
+            *mode.results.unwrap(),
+            vec![
+                Range::new(
+                    Position{ line: 0, offset: 0 },
+                    Position{ line: 0, offset: 3 },
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn whole_word_search_skips_partial_matches() {
+        let mut buffer = Buffer::new();
+        buffer.insert("amp.rs\nsamples amp");
+
+        let options = SearchOptions { whole_word: true, ..Default::default() };
+        let mut mode = SearchMode::new(Some(String::from("amp")), options);
+        mode.search(&buffer).unwrap();
+
+        assert_eq!(
+            *mode.results.unwrap(),
+            vec![
+                Range::new(
+                    Position{ line: 0, offset: 0 },
+                    Position{ line: 0, offset: 3 },
+                ),
+                Range::new(
+                    Position{ line: 1, offset: 8 },
+                    Position{ line: 1, offset: 11 },
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn whole_word_search_escapes_literal_queries() {
+        let mut buffer = Buffer::new();
+        buffer.insert("a.b axb");
+
+        let options = SearchOptions { whole_word: true, ..Default::default() };
+        let mut mode = SearchMode::new(Some(String::from("a.b")), options);
+        mode.search(&buffer).unwrap();
+
+        assert_eq!(mode.results.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn replacements_expand_capture_groups_for_regex_queries() {
+        let mut buffer = Buffer::new();
+        buffer.insert("amp editor\nscribe buffer");
+
+        let options = SearchOptions { regex: true, ..Default::default() };
+        let mut mode = SearchMode::new(Some(String::from(r"(\w+) (\w+)")), options);
+        mode.search(&buffer).unwrap();
+
+        let matches = mode.results.as_ref().unwrap().as_slice().to_vec();
+        assert_eq!(
+            mode.replacements(&buffer, &matches, "$2 $1").unwrap(),
+            vec![String::from("editor amp"), String::from("buffer scribe")]
+        );
+    }
+
+    #[test]
+    fn replacements_use_template_verbatim_for_literal_queries() {
+        let mut buffer = Buffer::new();
+        buffer.insert("amp");
+
+        let mut mode = SearchMode::new(Some(String::from("amp")), SearchOptions::default());
+        mode.search(&buffer).unwrap();
+
+        let matches = mode.results.as_ref().unwrap().as_slice().to_vec();
+        assert_eq!(
+            mode.replacements(&buffer, &matches, "$1").unwrap(),
+            vec![String::from("$1")]
+        );
+    }
+}
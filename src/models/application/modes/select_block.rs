@@ -0,0 +1,70 @@
+use crate::util::columns;
+use scribe::buffer::{Position, Range};
+
+/// Selects a rectangle spanning the lines and display columns between
+/// the anchor and the cursor, inclusive of the characters at each. The
+/// buffer's tab width is captured when the mode is entered, to determine
+/// the columns at which tabs are displayed.
+pub struct SelectBlockMode {
+    pub anchor: Position,
+    tab_width: usize,
+}
+
+impl SelectBlockMode {
+    pub fn new(anchor: Position, tab_width: usize) -> SelectBlockMode {
+        SelectBlockMode { anchor, tab_width }
+    }
+
+    /// The selected range on each of the rectangle's lines, from top to
+    /// bottom. Columns are tab and wide character-aware, so characters
+    /// partially within the rectangle are selected in their entirety, and
+    /// lines that end before the rectangle has started have empty ranges.
+    pub fn ranges(&self, data: &str, cursor: &Position) -> Vec<Range> {
+        let tab_width = self.tab_width;
+        let lines: Vec<&str> = data.split('\n').collect();
+        let line = |number: usize| lines.get(number).cloned().unwrap_or("");
+        let bounds = |position: &Position| {
+            let column = columns::column(line(position.line), position.offset, tab_width);
+
+            (column, column + columns::width_at(line(position.line), position.offset, tab_width))
+        };
+        let (anchor_start, anchor_end) = bounds(&self.anchor);
+        let (cursor_start, cursor_end) = bounds(cursor);
+        let first_column = anchor_start.min(cursor_start);
+        let end_column = anchor_end.max(cursor_end);
+
+        (self.anchor.line.min(cursor.line)..=self.anchor.line.max(cursor.line))
+            .map(|number| {
+                let (start, end) = columns::span(line(number), first_column, end_column, tab_width);
+
+                Range::new(
+                    Position{ line: number, offset: start },
+                    Position{ line: number, offset: end }
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use scribe::buffer::{Position, Range};
+    use super::SelectBlockMode;
+
+    fn range(line: usize, start: usize, end: usize) -> Range {
+        Range::new(Position{ line, offset: start }, Position{ line, offset: end })
+    }
+
+    #[test]
+    fn ranges_cover_the_columns_between_the_anchor_and_cursor() {
+        let mode = SelectBlockMode::new(Position{ line: 2, offset: 3 }, 2);
+        let ranges = mode.ranges("amp editor\n\tamp\na\n日本語", &Position{ line: 0, offset: 1 });
+
+        assert_eq!(ranges, vec![range(0, 1, 4), range(1, 0, 3), range(2, 1, 1)]);
+
+        let mode = SelectBlockMode::new(Position{ line: 3, offset: 1 }, 2);
+        let ranges = mode.ranges("amp editor\n\tamp\na\n日本語", &Position{ line: 1, offset: 1 });
+
+        assert_eq!(ranges, vec![range(1, 1, 3), range(2, 1, 1), range(3, 1, 2)]);
+    }
+}
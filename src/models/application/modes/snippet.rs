@@ -0,0 +1,96 @@
+use fragment;
+use crate::models::application::Snippet;
+use crate::models::application::modes::{SearchSelectMode, SearchSelectConfig};
+use crate::util::SelectableVec;
+use std::fmt;
+use std::slice::Iter;
+
+/// Lists the snippets defined for the current buffer's syntax, so that
+/// one can be inserted without typing its trigger. Every snippet is
+/// listed until a query is entered to narrow them down by trigger.
+pub struct SnippetMode {
+    insert: bool,
+    input: String,
+    snippets: Vec<Snippet>,
+    results: SelectableVec<Snippet>,
+    config: SearchSelectConfig,
+}
+
+impl SnippetMode {
+    pub fn new(snippets: Vec<Snippet>, config: SearchSelectConfig) -> SnippetMode {
+        SnippetMode {
+            insert: true,
+            input: String::new(),
+            snippets,
+            results: SelectableVec::new(Vec::new()),
+            config,
+        }
+    }
+}
+
+impl fmt::Display for SnippetMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SNIPPET")
+    }
+}
+
+impl SearchSelectMode<Snippet> for SnippetMode {
+    fn search(&mut self) {
+        let results = if self.input.is_empty() {
+            self.snippets.iter().take(self.config.max_results).cloned().collect()
+        } else {
+            fragment::matching::find(&self.input, &self.snippets, self.config.max_results)
+                .into_iter()
+                .map(|result| (*result).clone())
+                .collect()
+        };
+
+        self.results = SelectableVec::new(results);
+    }
+
+    fn query(&mut self) -> &mut String {
+        &mut self.input
+    }
+
+    fn insert_mode(&self) -> bool {
+        self.insert
+    }
+
+    fn set_insert_mode(&mut self, insert_mode: bool) {
+        self.insert = insert_mode;
+    }
+
+    fn results(&self) -> Iter<'_, Snippet> {
+        self.results.iter()
+    }
+
+    fn selection(&self) -> Option<&Snippet> {
+        self.results.selection()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.results.selected_index()
+    }
+
+    fn select_previous(&mut self) {
+        self.results.select_previous();
+    }
+
+    fn select_next(&mut self) {
+        self.results.select_next();
+    }
+
+    fn config(&self) -> &SearchSelectConfig {
+        &self.config
+    }
+
+    fn message(&mut self) -> Option<String> {
+        if self.snippets.is_empty() {
+            Some(String::from("No snippets are defined for this syntax."))
+        } else if self.results().count() == 0 {
+            Some(String::from("No matching snippets found."))
+        } else {
+            None
+        }
+    }
+}
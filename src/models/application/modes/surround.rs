@@ -0,0 +1,38 @@
+use scribe::buffer::Range;
+use std::fmt;
+
+/// What to do with the delimiter pair named by the next key press. The range
+/// being wrapped is captured up front, since the select mode is left to prompt
+/// for it, as are the delimiters of the pair being changed, once they're found.
+#[derive(Debug, PartialEq)]
+pub enum SurroundAction {
+    Add(Range),
+    Change(Option<(Range, Range)>),
+    Delete,
+}
+
+/// Prompts for the key naming a delimiter pair, or, after `t`, the
+/// contents of an HTML opening tag, which is entered with a newline.
+pub struct SurroundMode {
+    pub action: SurroundAction,
+    pub tag: Option<String>,
+}
+
+impl SurroundMode {
+    pub fn new(action: SurroundAction) -> SurroundMode {
+        SurroundMode { action, tag: None }
+    }
+}
+
+impl fmt::Display for SurroundMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.action, &self.tag) {
+            (SurroundAction::Add(_), Some(tag)) => write!(f, "Surround with tag: <{}", tag),
+            (SurroundAction::Add(_), None) => write!(f, "Surround with: "),
+            (SurroundAction::Change(Some(_)), Some(tag)) => write!(f, "Change surrounding to tag: <{}", tag),
+            (SurroundAction::Change(Some(_)), None) => write!(f, "Change surrounding to: "),
+            (SurroundAction::Change(None), _) => write!(f, "Change surrounding: "),
+            (SurroundAction::Delete, _) => write!(f, "Delete surrounding: "),
+        }
+    }
+}
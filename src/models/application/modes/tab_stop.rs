@@ -0,0 +1,114 @@
+use scribe::buffer::{Position, Range};
+
+/// Tracks the placeholders of an expanded snippet, grouped into tab stops
+/// visited in order; the last one is where the cursor ends up. Each tab
+/// stop's first range is edited directly, and the rest mirror it.
+pub struct TabStopMode {
+    tab_stops: Vec<Vec<Range>>,
+    index: usize,
+
+    /// Whether the current placeholder still holds its default content,
+    /// which is replaced (rather than added to) when typing.
+    pub pristine: bool,
+}
+
+impl TabStopMode {
+    pub fn new(tab_stops: Vec<Vec<Range>>) -> TabStopMode {
+        let mut mode = TabStopMode {
+            tab_stops,
+            index: 0,
+            pristine: false,
+        };
+        mode.select(0);
+
+        mode
+    }
+
+    /// The current tab stop's ranges, starting with the one being edited.
+    pub fn ranges(&self) -> &[Range] {
+        self.tab_stops
+            .get(self.index)
+            .map(|ranges| ranges.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Whether the current tab stop is the last one.
+    pub fn is_final(&self) -> bool {
+        self.index + 1 >= self.tab_stops.len()
+    }
+
+    pub fn select_next(&mut self) {
+        if !self.is_final() {
+            self.select(self.index + 1);
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        if self.index > 0 {
+            self.select(self.index - 1);
+        }
+    }
+
+    /// Updates the length of one of the current tab stop's ranges, after its
+    /// content has been replaced, shifting any ranges that follow it.
+    pub fn resize_range(&mut self, index: usize, length: usize) {
+        let range = match self.ranges().get(index) {
+            Some(range) => range.clone(),
+            None => return,
+        };
+        let start = range.start();
+        let end = range.end();
+        let new_end = Position{ line: end.line, offset: start.offset + length };
+
+        for (stop_index, ranges) in self.tab_stops.iter_mut().enumerate() {
+            for (range_index, other) in ranges.iter_mut().enumerate() {
+                if stop_index == self.index && range_index == index {
+                    *other = Range::new(start, new_end);
+                } else if other.start().line == end.line && other.start().offset >= end.offset {
+                    let shift = |position: Position| if position.line == end.line {
+                        Position{ line: position.line, offset: position.offset - end.offset + new_end.offset }
+                    } else {
+                        position
+                    };
+                    *other = Range::new(shift(other.start()), shift(other.end()));
+                }
+            }
+        }
+    }
+
+    fn select(&mut self, index: usize) {
+        self.index = index;
+        self.pristine = self.ranges()
+            .first()
+            .map(|range| range.start() != range.end())
+            .unwrap_or(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use scribe::buffer::{Position, Range};
+    use super::TabStopMode;
+
+    fn range(line: usize, start: usize, end: usize) -> Range {
+        Range::new(Position{ line, offset: start }, Position{ line, offset: end })
+    }
+
+    #[test]
+    fn resize_range_shifts_following_ranges_on_the_same_line() {
+        let mut mode = TabStopMode::new(vec![
+            vec![range(0, 3, 7), range(1, 4, 8)],
+            vec![range(0, 8, 8)],
+            vec![range(1, 8, 8)],
+        ]);
+        mode.resize_range(0, 2);
+        mode.resize_range(1, 2);
+
+        assert_eq!(mode.ranges(), &[range(0, 3, 5), range(1, 4, 6)]);
+        mode.select_next();
+        assert_eq!(mode.ranges(), &[range(0, 6, 6)]);
+        mode.select_next();
+        assert_eq!(mode.ranges(), &[range(1, 6, 6)]);
+        assert!(mode.is_final());
+    }
+}
@@ -0,0 +1,53 @@
+use std::fmt;
+
+/// What to do with a text object once it's been found.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TextObjectOperation {
+    Select,
+    Delete,
+    Change,
+    Copy,
+}
+
+/// Prompts for the kind of text object to select, either inside
+/// or around its delimiters (e.g. with or without its brackets).
+/// When entered from normal mode, the operation to apply and the
+/// inside/around choice are prompted for first.
+pub struct TextObjectMode {
+    pub around: Option<bool>,
+    pub operation: TextObjectOperation,
+}
+
+impl TextObjectMode {
+    pub fn new(around: bool) -> TextObjectMode {
+        TextObjectMode {
+            around: Some(around),
+            operation: TextObjectOperation::Select,
+        }
+    }
+
+    pub fn pending() -> TextObjectMode {
+        TextObjectMode {
+            around: None,
+            operation: TextObjectOperation::Select,
+        }
+    }
+}
+
+impl fmt::Display for TextObjectMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let operation = match self.operation {
+            TextObjectOperation::Select => "Select",
+            TextObjectOperation::Delete => "Delete",
+            TextObjectOperation::Change => "Change",
+            TextObjectOperation::Copy => "Copy",
+        };
+
+        match self.around {
+            Some(true) => write!(f, "{} around: ", operation),
+            Some(false) => write!(f, "{} inside: ", operation),
+            None if self.operation == TextObjectOperation::Select => write!(f, "Text object: "),
+            None => write!(f, "{}: ", operation),
+        }
+    }
+}
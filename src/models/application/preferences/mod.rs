@@ -5,23 +5,38 @@ use crate::errors::*;
 use crate::input::KeyMap;
 use crate::models::application::modes::open;
 use scribe::Buffer;
+use std::collections::HashMap;
 use std::fs::OpenOptions;
 use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use crate::yaml::yaml::{Hash, Yaml, YamlLoader};
-use crate::models::application::modes::SearchSelectConfig;
+use crate::models::application::modes::{SearchOptions, SearchSelectConfig};
+use crate::util::indent::IndentRules;
 
 const APP_INFO: AppInfo = AppInfo {
     name: "amp",
     author: "Jordan MacDonald",
 };
+const AUTO_PAIRS_KEY: &str = "auto_pairs";
+const DEFAULT_AUTO_PAIRS: [&str; 6] = ["()", "[]", "{}", "\"\"", "''", "``"];
+const BLOCK_COMMENT_END_KEY: &str = "block_comment_end";
+const BLOCK_COMMENT_START_KEY: &str = "block_comment_start";
+const DECREASE_INDENT_PATTERN_KEY: &str = "decrease_indent_pattern";
 const FILE_NAME: &str = "config.yml";
+const INCREASE_INDENT_PATTERN_KEY: &str = "increase_indent_pattern";
 const LINE_COMMENT_PREFIX_KEY: &str = "line_comment_prefix";
 const LINE_LENGTH_GUIDE_KEY: &str = "line_length_guide";
 const LINE_WRAPPING_KEY: &str = "line_wrapping";
 const OPEN_MODE_KEY: &str = "open_mode";
 const OPEN_MODE_EXCLUSIONS_KEY: &str = "exclusions";
+const SEARCH_KEY: &str = "search";
+const SEARCH_CASE_INSENSITIVE_KEY: &str = "case_insensitive";
+const SEARCH_REGEX_KEY: &str = "regex";
+const SEARCH_SMART_CASE_KEY: &str = "smart_case";
+const SEARCH_WHOLE_WORD_KEY: &str = "whole_word";
 const SEARCH_SELECT_KEY: &str = "search_select";
+const SNIPPET_PATH: &str = "snippets";
 const SOFT_TABS_KEY: &str = "soft_tabs";
 const SYNTAX_PATH: &str = "syntaxes";
 const TAB_WIDTH_KEY: &str = "tab_width";
@@ -29,6 +44,7 @@ const THEME_KEY: &str = "theme";
 const THEME_PATH: &str = "themes";
 const TYPES_KEY: &str = "types";
 const TYPES_SYNTAX_KEY: &str = "syntax";
+const UNDO_PATH: &str = "undo";
 
 /// Loads, creates, and provides default values for application preferences.
 /// Values are immutable once loaded, with the exception of those that provide
@@ -38,16 +54,23 @@ pub struct Preferences {
     data: Option<Yaml>,
     keymap: KeyMap,
     theme: Option<String>,
+    indent_rules: HashMap<String, Rc<IndentRules>>,
+    indent_rules_error: Option<String>,
 }
 
 impl Preferences {
     /// Builds a new in-memory instance with default values.
     pub fn new(data: Option<Yaml>) -> Preferences {
+        let default = load_default_document().expect("Failed to load default preferences!");
+        let (indent_rules, indent_rules_error) = compile_indent_rules(&default, data.as_ref());
+
         Preferences {
-            default: load_default_document().expect("Failed to load default preferences!"),
+            default,
             data,
             keymap: KeyMap::default().expect("Failed to load default keymap!"),
-            theme: None
+            theme: None,
+            indent_rules,
+            indent_rules_error,
         }
     }
 
@@ -58,8 +81,9 @@ impl Preferences {
         let keymap = load_keymap(
             data.as_ref().and_then(|data| data["keymap"].as_hash())
         )?;
+        let (indent_rules, indent_rules_error) = compile_indent_rules(&default, data.as_ref());
 
-        Ok(Preferences { default, data, keymap, theme: None })
+        Ok(Preferences { default, data, keymap, theme: None, indent_rules, indent_rules_error })
     }
 
     /// Reloads all user preferences from disk and merges them with defaults.
@@ -70,10 +94,14 @@ impl Preferences {
             data.as_ref().and_then(|data| data["keymap"].as_hash())
         )?;
 
+        let (indent_rules, indent_rules_error) = compile_indent_rules(&default, data.as_ref());
+
         self.default = default;
         self.data = data;
         self.keymap = keymap;
         self.theme = None;
+        self.indent_rules = indent_rules;
+        self.indent_rules_error = indent_rules_error;
 
         Ok(())
     }
@@ -95,6 +123,18 @@ impl Preferences {
             .chain_err(|| "Couldn't create syntax directory or build a path to it.")
     }
 
+    /// A path pointing to the user snippet directory.
+    pub fn snippet_path() -> Result<PathBuf> {
+        app_dir(AppDataType::UserConfig, &APP_INFO, SNIPPET_PATH)
+            .chain_err(|| "Couldn't create snippet directory or build a path to it.")
+    }
+
+    /// A path pointing to the persisted undo history directory.
+    pub fn undo_path() -> Result<PathBuf> {
+        app_dir(AppDataType::UserData, &APP_INFO, UNDO_PATH)
+            .chain_err(|| "Couldn't create undo history directory or build a path to it.")
+    }
+
     /// Returns the preference file loaded into a buffer for editing.
     /// If the file doesn't already exist, it will return a new in-memory buffer
     /// with a pre-populated path, creating the parent config directories
@@ -176,6 +216,16 @@ impl Preferences {
         result
     }
 
+    /// The options used when first entering search mode.
+    pub fn search_options(&self) -> SearchOptions {
+        SearchOptions {
+            regex: self.search_option(SEARCH_REGEX_KEY),
+            case_insensitive: self.search_option(SEARCH_CASE_INSENSITIVE_KEY),
+            smart_case: self.search_option(SEARCH_SMART_CASE_KEY),
+            whole_word: self.search_option(SEARCH_WHOLE_WORD_KEY),
+        }
+    }
+
     pub fn soft_tabs(&self, path: Option<&PathBuf>) -> bool {
         self.data
             .as_ref()
@@ -270,6 +320,81 @@ impl Preferences {
             .map(|prefix| prefix.to_owned())
     }
 
+    pub fn block_comment_delimiters(&self, path: &PathBuf) -> Option<(String, String)> {
+        let extension = path_extension(Some(path))?;
+        let delimiter = |key: &str| {
+            self.data
+                .as_ref()
+                .and_then(|data| data[TYPES_KEY][extension][key].as_str())
+                .or_else(|| self.default[TYPES_KEY][extension][key].as_str())
+                .map(|delimiter| delimiter.to_owned())
+        };
+
+        Some((delimiter(BLOCK_COMMENT_START_KEY)?, delimiter(BLOCK_COMMENT_END_KEY)?))
+    }
+
+    /// Rules used to indent new lines and outdent closing lines in the
+    /// specified file, compiled when preferences are loaded. Types without
+    /// any patterns (or with an invalid one) use plain indentation.
+    pub fn indent_rules(&self, path: Option<&PathBuf>) -> Rc<IndentRules> {
+        path_extension(path)
+            .and_then(|extension| self.indent_rules.get(extension))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Describes the invalid indent patterns found when preferences were
+    /// loaded, if any. It's only returned once, so that it's reported once.
+    pub fn take_indent_rules_error(&mut self) -> Option<String> {
+        self.indent_rules_error.take()
+    }
+
+    /// The opening and closing characters paired when typing in the specified
+    /// file, which is empty unless auto-pairing is enabled. A type's pair set
+    /// takes precedence over the top-level one, which falls back to defaults.
+    pub fn auto_pairs(&self, path: Option<&PathBuf>) -> Vec<(String, String)> {
+        let extension = path_extension(path);
+        let type_setting = |document: &Yaml| -> Option<Yaml> {
+            extension.map(|extension| document[TYPES_KEY][extension][AUTO_PAIRS_KEY].clone())
+        };
+        let user_type_setting = self.data.as_ref().and_then(&type_setting);
+        let user_setting = self.data.as_ref().map(|data| data[AUTO_PAIRS_KEY].clone());
+
+        let enabled = match (&user_type_setting, &user_setting) {
+            (Some(Yaml::Boolean(enabled)), _) => *enabled,
+            (Some(Yaml::Array(_)), _) => true,
+            (_, Some(Yaml::Boolean(enabled))) => *enabled,
+            (_, Some(Yaml::Array(_))) => true,
+            _ => self.default[AUTO_PAIRS_KEY].as_bool()
+                .expect("Couldn't find default auto pairs setting!"),
+        };
+        if !enabled {
+            return Vec::new();
+        }
+
+        let pairs: Vec<String> = vec![user_type_setting, type_setting(&self.default), user_setting]
+            .into_iter()
+            .find_map(|setting| match setting {
+                Some(Yaml::Array(pairs)) => Some(pairs),
+                _ => None,
+            })
+            .map(|pairs| {
+                pairs.iter().filter_map(|pair| pair.as_str().map(String::from)).collect()
+            })
+            .unwrap_or_else(|| DEFAULT_AUTO_PAIRS.iter().map(|pair| pair.to_string()).collect());
+
+        pairs
+            .iter()
+            .filter_map(|pair| {
+                let characters: Vec<char> = pair.chars().collect();
+                match characters.as_slice() {
+                    [open, close] => Some((open.to_string(), close.to_string())),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
     pub fn syntax_definition_name(&self, path: &Path) -> Option<String> {
         self.data
             .as_ref()
@@ -292,6 +417,16 @@ impl Preferences {
             })
     }
 
+    fn search_option(&self, key: &str) -> bool {
+        self.data
+            .as_ref()
+            .and_then(|data| data[SEARCH_KEY][key].as_bool())
+            .unwrap_or_else(|| {
+                self.default[SEARCH_KEY][key].as_bool()
+                    .expect("Couldn't find default search option setting!")
+            })
+    }
+
     fn default_open_mode_exclusions(&self) -> Result<Option<Vec<ExclusionPattern>>> {
         let exclusions = self.default[OPEN_MODE_KEY][OPEN_MODE_EXCLUSIONS_KEY]
             .as_vec()
@@ -336,6 +471,49 @@ fn load_default_document() -> Result<Yaml> {
         .chain_err(|| "No default preferences document found")
 }
 
+/// Compiles the indent rules for every type with indent patterns, with user
+/// patterns taking precedence over defaults. Types with an invalid pattern
+/// are left out, and described by the returned error message.
+fn compile_indent_rules(default: &Yaml, data: Option<&Yaml>) -> (HashMap<String, Rc<IndentRules>>, Option<String>) {
+    let mut rules = HashMap::new();
+    let mut errors = Vec::new();
+    let documents: Vec<&Yaml> = data.into_iter().chain(Some(default)).collect();
+    let extensions: Vec<&str> = documents
+        .iter()
+        .filter_map(|document| document[TYPES_KEY].as_hash())
+        .flat_map(|types| types.keys().filter_map(|key| key.as_str()))
+        .collect();
+
+    for extension in extensions {
+        if rules.contains_key(extension) {
+            continue;
+        }
+
+        let pattern = |key: &str| {
+            documents.iter().find_map(|document| document[TYPES_KEY][extension][key].as_str())
+        };
+        let (increase, decrease) = (pattern(INCREASE_INDENT_PATTERN_KEY), pattern(DECREASE_INDENT_PATTERN_KEY));
+        if increase.is_none() && decrease.is_none() {
+            continue;
+        }
+
+        match IndentRules::new(increase, decrease) {
+            Ok(indent_rules) => {
+                rules.insert(extension.to_string(), Rc::new(indent_rules));
+            }
+            Err(error) => errors.push(format!("{} for \"{}\" files", error, extension)),
+        }
+    }
+
+    let error = if errors.is_empty() {
+        None
+    } else {
+        Some(errors.join("; "))
+    };
+
+    (rules, error)
+}
+
 /// Loads default keymaps, merging in the provided overrides.
 fn load_keymap(keymap_overrides: Option<&Hash>) -> Result<KeyMap> {
     let mut keymap = KeyMap::default()?;
@@ -357,7 +535,8 @@ fn path_extension(path: Option<&PathBuf>) -> Option<&str> {
 
 #[cfg(test)]
 mod tests {
-    use super::{ExclusionPattern, Preferences, YamlLoader};
+    use super::{ExclusionPattern, Preferences, SearchOptions, YamlLoader};
+    use std::collections::HashMap;
     use std::path::{Path, PathBuf};
     use crate::input::KeyMap;
     use crate::yaml::yaml::{Hash, Yaml};
@@ -609,6 +788,46 @@ mod tests {
         assert!(preferences.open_mode_exclusions().unwrap().is_none());
     }
 
+    #[test]
+    fn auto_pairs_returns_nothing_when_disabled() {
+        let preferences = Preferences::new(None);
+
+        assert!(preferences.auto_pairs(Some(&PathBuf::from("preferences.rs"))).is_empty());
+    }
+
+    #[test]
+    fn auto_pairs_returns_type_specific_pairs_over_top_level_ones() {
+        let data = YamlLoader::load_from_str(
+            "auto_pairs: [\"<>\"]\ntypes:\n  rs:\n    auto_pairs: [\"||\"]\n  md:\n    auto_pairs: false").unwrap();
+        let preferences = Preferences::new(data.into_iter().next());
+
+        assert_eq!(preferences.auto_pairs(Some(&PathBuf::from("preferences.rs"))),
+                   vec![("|".to_string(), "|".to_string())]);
+        assert_eq!(preferences.auto_pairs(Some(&PathBuf::from("preferences.txt"))),
+                   vec![("<".to_string(), ">".to_string())]);
+        assert!(preferences.auto_pairs(Some(&PathBuf::from("preferences.md"))).is_empty());
+    }
+
+    #[test]
+    fn auto_pairs_falls_back_to_default_type_specific_pairs() {
+        let data = YamlLoader::load_from_str("auto_pairs: true").unwrap();
+        let preferences = Preferences::new(data.into_iter().next());
+
+        assert_eq!(preferences.auto_pairs(Some(&PathBuf::from("preferences.rs"))).len(), 4);
+        assert_eq!(preferences.auto_pairs(None).len(), 6);
+    }
+
+    #[test]
+    fn block_comment_delimiters_returns_user_defined_type_specific_data() {
+        let data = YamlLoader::load_from_str(
+            "types:\n  rs:\n    block_comment_start: /*\n    block_comment_end: \"*/\"").unwrap();
+        let preferences = Preferences::new(data.into_iter().next());
+
+        assert_eq!(preferences.block_comment_delimiters(&PathBuf::from("preferences.rs")),
+                   Some(("/*".into(), "*/".into())));
+        assert_eq!(preferences.block_comment_delimiters(&PathBuf::from("preferences.go")), None);
+    }
+
     #[test]
     fn line_comment_prefix_returns_correct_default_type_specific_data() {
         let preferences = Preferences::new(None);
@@ -643,6 +862,29 @@ mod tests {
                    None);
     }
 
+    #[test]
+    fn search_options_returns_defaults_when_user_defined_data_not_found() {
+        let preferences = Preferences::new(None);
+
+        assert_eq!(preferences.search_options(), SearchOptions::default());
+    }
+
+    #[test]
+    fn search_options_returns_user_defined_data() {
+        let data = YamlLoader::load_from_str("search:\n  smart_case: true\n  whole_word: true").unwrap();
+        let preferences = Preferences::new(data.into_iter().nth(0));
+
+        assert_eq!(
+            preferences.search_options(),
+            SearchOptions {
+                regex: false,
+                case_insensitive: false,
+                smart_case: true,
+                whole_word: true,
+            }
+        );
+    }
+
     #[test]
     fn reload_clears_in_memory_theme() {
         // Create an on-disk preferences file first, if one doesn't already exist.
@@ -671,11 +913,28 @@ mod tests {
             default: Yaml::Null,
             data: None,
             keymap: KeyMap::from(&Hash::new()).unwrap(),
-            theme: None
+            theme: None,
+            indent_rules: HashMap::new(),
+            indent_rules_error: None,
         };
 
         // Reload the preferences, ensuring that it refreshes the keymap.
         preferences.reload().unwrap();
         assert!(preferences.keymap().get("normal").is_some());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn indent_rules_fall_back_to_plain_indentation_for_invalid_patterns() {
+        let data = YamlLoader::load_from_str("types:\n  rs:\n    increase_indent_pattern: '('").unwrap();
+        let mut preferences = Preferences::new(data.into_iter().next());
+
+        let rules = preferences.indent_rules(Some(&PathBuf::from("main.rs")));
+        assert_eq!(rules.indentation_after("  fn amp() {", "  "), "  ");
+        let rules = preferences.indent_rules(Some(&PathBuf::from("main.c")));
+        assert_eq!(rules.indentation_after("  int amp() {", "  "), "    ");
+
+        // The error is only reported once.
+        assert!(preferences.take_indent_rules_error().unwrap().contains("\"rs\" files"));
+        assert_eq!(preferences.take_indent_rules_error(), None);
+    }
+}
@@ -0,0 +1,215 @@
+use crate::errors::*;
+use crate::models::application::Preferences;
+use crate::yaml::yaml::{Yaml, YamlLoader};
+use fragment::matching::AsStr;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+const FILE_EXTENSION: &str = "yml";
+
+/// A body of text inserted in place of its trigger, with numbered
+/// placeholders visited using tab stops. Placeholders are written as `$1`,
+/// `${1}`, or `${1:default}`; repeating a number mirrors the placeholder,
+/// and `$0` marks where the cursor ends up. A literal dollar sign is `\$`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snippet {
+    pub trigger: String,
+    pub content: String,
+    pub placeholders: Vec<Placeholder>,
+}
+
+/// A numbered byte range in a snippet's content.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Placeholder {
+    pub number: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Snippet {
+    /// Loads the snippets defined for the named syntax, from a YAML file of
+    /// the same name in the user snippet directory (e.g. `Rust.yml`), which
+    /// maps triggers to snippet bodies. A syntax without one has no snippets.
+    pub fn load(syntax_name: &str) -> Result<Vec<Snippet>> {
+        let directory = Preferences::snippet_path()?;
+
+        load_from(&directory.join(format!("{}.{}", syntax_name, FILE_EXTENSION)))
+    }
+
+    pub fn parse(trigger: &str, body: &str) -> Result<Snippet> {
+        let segments = segments(body)?;
+
+        // Mirrors take on the default content of their first
+        // occurrence that has one, so that they start out in sync.
+        let default = |number: usize| -> &str {
+            segments
+                .iter()
+                .find_map(|segment| match segment {
+                    Segment::Placeholder(n, default) if *n == number && !default.is_empty() => {
+                        Some(default.as_str())
+                    },
+                    _ => None,
+                })
+                .unwrap_or("")
+        };
+
+        let mut content = String::new();
+        let mut placeholders = Vec::new();
+        for segment in &segments {
+            match segment {
+                Segment::Text(text) => content.push_str(text),
+                Segment::Placeholder(number, _) => {
+                    let start = content.len();
+                    content.push_str(default(*number));
+                    placeholders.push(Placeholder{ number: *number, start, end: content.len() });
+                },
+            }
+        }
+
+        Ok(Snippet{ trigger: trigger.to_string(), content, placeholders })
+    }
+}
+
+impl fmt::Display for Snippet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.trigger)
+    }
+}
+
+impl AsStr for Snippet {
+    fn as_str(&self) -> &str {
+        &self.trigger
+    }
+}
+
+fn load_from(path: &Path) -> Result<Vec<Snippet>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let data = fs::read_to_string(path)
+        .chain_err(|| format!("Couldn't read snippet file {}", path.to_string_lossy()))?;
+
+    parse(&data)
+        .chain_err(|| format!("Couldn't parse snippet file {}", path.to_string_lossy()))
+}
+
+fn parse(data: &str) -> Result<Vec<Snippet>> {
+    let document = YamlLoader::load_from_str(data)
+        .chain_err(|| "Snippet file isn't valid YAML")?
+        .into_iter()
+        .next();
+    let definitions = match document {
+        Some(Yaml::Hash(definitions)) => definitions,
+        Some(_) => bail!("Snippet file should map triggers to snippets"),
+        None => return Ok(Vec::new()),
+    };
+
+    let mut snippets = Vec::new();
+    for (trigger, body) in definitions {
+        let trigger = trigger.as_str().ok_or("Snippet triggers should be strings")?;
+        let body = body.as_str()
+            .ok_or_else(|| format!("The \"{}\" snippet should be a string", trigger))?;
+
+        snippets.push(
+            Snippet::parse(trigger, body)
+                .chain_err(|| format!("Couldn't parse the \"{}\" snippet", trigger))?
+        );
+    }
+
+    Ok(snippets)
+}
+
+enum Segment {
+    Text(String),
+    Placeholder(usize, String),
+}
+
+// Splits a snippet body into literal text and placeholders.
+fn segments(body: &str) -> Result<Vec<Segment>> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut characters = body.chars().peekable();
+
+    while let Some(character) = characters.next() {
+        match character {
+            '\\' if characters.peek() == Some(&'$') => {
+                characters.next();
+                text.push('$');
+            },
+            '$' => {
+                let braced = characters.peek() == Some(&'{');
+                if braced {
+                    characters.next();
+                }
+
+                let mut digits = String::new();
+                while let Some(digit) = characters.peek().copied().filter(char::is_ascii_digit) {
+                    digits.push(digit);
+                    characters.next();
+                }
+                if digits.is_empty() {
+                    bail!("Expected a placeholder number after \"$\"");
+                }
+
+                let mut default = String::new();
+                if braced {
+                    if characters.peek() == Some(&':') {
+                        characters.next();
+                        while let Some(character) = characters.peek().copied().filter(|c| *c != '}') {
+                            default.push(character);
+                            characters.next();
+                        }
+                    }
+                    if characters.next() != Some('}') {
+                        bail!("Unterminated placeholder \"${{{}\"", digits);
+                    }
+                }
+
+                segments.push(Segment::Text(text.split_off(0)));
+                segments.push(Segment::Placeholder(
+                    digits.parse().chain_err(|| "Invalid placeholder number")?,
+                    default
+                ));
+            },
+            _ => text.push(character),
+        }
+    }
+    segments.push(Segment::Text(text));
+
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Placeholder, Snippet};
+
+    #[test]
+    fn parse_finds_placeholders_and_mirrors_their_defaults() {
+        let snippet = Snippet::parse("fn", "fn ${1:name}(${2}) -> \\$1 {\n    $1$0\n}").unwrap();
+
+        assert_eq!(snippet.content, "fn name() -> $1 {\n    name\n}");
+        assert_eq!(snippet.placeholders, vec![
+            Placeholder{ number: 1, start: 3, end: 7 },
+            Placeholder{ number: 2, start: 8, end: 8 },
+            Placeholder{ number: 1, start: 22, end: 26 },
+            Placeholder{ number: 0, start: 26, end: 26 },
+        ]);
+    }
+
+    #[test]
+    fn parse_rejects_unterminated_placeholders() {
+        assert!(Snippet::parse("fn", "fn ${1:name").is_err());
+    }
+
+    #[test]
+    fn parse_reads_triggers_and_bodies_from_yaml() {
+        let snippets = super::parse("fn: fn $1() {}\nif: if $1 {}").unwrap();
+
+        assert_eq!(
+            snippets.iter().map(|snippet| snippet.trigger.as_str()).collect::<Vec<&str>>(),
+            vec!["fn", "if"]
+        );
+    }
+}
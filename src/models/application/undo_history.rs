@@ -0,0 +1,782 @@
+use crate::errors::*;
+use crate::models::application::Preferences;
+use crate::yaml::YamlEmitter;
+use crate::yaml::yaml::{Hash, Yaml, YamlLoader};
+use scribe::Buffer;
+use scribe::buffer::{Position, Range};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::process;
+use unicode_segmentation::UnicodeSegmentation;
+
+const CHANGES_KEY: &str = "changes";
+const CONTENT_HASH_KEY: &str = "hash";
+const FILE_EXTENSION: &str = "yml";
+const INDEX_KEY: &str = "index";
+const PATH_KEY: &str = "path";
+
+/// Persists the undo/redo history of file-backed buffers to the app data
+/// directory, so that it can be restored when they're reopened. Histories
+/// are keyed by path, and tied to a hash of the file's content; they're
+/// discarded if the file has changed by the time it's reopened.
+///
+/// Buffers don't expose their histories, and their change callbacks only
+/// report where changes start, so each buffer's content is kept and compared
+/// with its latest content (from the first changed line onwards) to record
+/// changes as buffers are edited. Histories are written out when they're closed.
+pub struct UndoHistory {
+    directory: Option<PathBuf>,
+    recordings: HashMap<PathBuf, Recording>,
+}
+
+// The changes made to a buffer since it was opened, the number of them that
+// lead up to its current content, and a copy of it to diff the next one with.
+struct Recording {
+    changes: Vec<Change>,
+    index: usize,
+    content: String,
+    revision: Option<usize>,
+    saved_index: Option<usize>,
+}
+
+// A single step in a buffer's history, replacing the removed
+// content at the start position with the inserted content.
+#[derive(Debug, PartialEq)]
+struct Change {
+    start: Position,
+    removed: String,
+    inserted: String,
+}
+
+impl UndoHistory {
+    /// Builds a new instance, storing histories in the specified directory,
+    /// if one is provided. Without one, nothing is recorded, saved or restored.
+    pub fn new(directory: Option<PathBuf>) -> UndoHistory {
+        UndoHistory { directory, recordings: HashMap::new() }
+    }
+
+    /// Stores histories in the app data directory. If the directory is
+    /// unavailable, nothing is recorded, saved or restored.
+    pub fn load() -> UndoHistory {
+        UndoHistory::new(Preferences::undo_path().ok())
+    }
+
+    /// Records the change made to the buffer since this was last called, if
+    /// any. A change that reverses the previous one in the recorded history,
+    /// or repeats the next one, is treated as undoing or redoing it. A revision
+    /// (see `View::buffer_revision`) that hasn't changed since the last call
+    /// skips comparing the buffer's content entirely, and a changed line (see
+    /// `View::take_changed_line`) skips comparing the lines before it.
+    pub fn record(&mut self, buffer: &Buffer, revision: Option<usize>, changed_line: Option<usize>) {
+        let path = match (&self.directory, &buffer.path) {
+            (Some(_), Some(path)) => path,
+            _ => return,
+        };
+
+        match self.recordings.get_mut(path) {
+            Some(recording) => {
+                if revision.is_some() && revision == recording.revision {
+                    return;
+                }
+                recording.revision = revision;
+                recording.update(buffer.data(), changed_line.unwrap_or(0));
+            }
+            None => {
+                let mut recording = Recording::new(buffer, Vec::new(), 0);
+                recording.revision = revision;
+                self.recordings.insert(path.clone(), recording);
+            }
+        }
+    }
+
+    /// Records the buffer's latest change, and marks its
+    /// content as matching its file, having just been saved.
+    pub fn saved(&mut self, buffer: &Buffer, revision: Option<usize>, changed_line: Option<usize>) {
+        self.record(buffer, revision, changed_line);
+        if let Some(recording) = buffer.path.as_ref().and_then(|path| self.recordings.get_mut(path)) {
+            recording.saved_index = Some(recording.index);
+        }
+    }
+
+    /// Starts the buffer's recording over, for buffers that have
+    /// lost their history (e.g. having been reloaded from disk).
+    pub fn reset(&mut self, buffer: &Buffer) {
+        if let (Some(_), Some(path)) = (&self.directory, &buffer.path) {
+            self.recordings.insert(path.clone(), Recording::new(buffer, Vec::new(), 0));
+        }
+    }
+
+    /// Writes the buffer's recorded history to disk, anchored at the point
+    /// where its content matches its file, and stops recording it. This
+    /// should only be used on buffers that are being closed.
+    pub fn save(&mut self, buffer: &Buffer) -> Result<()> {
+        let (history_path, path) = match (self.history_path(buffer), buffer.path.clone()) {
+            (Some(history_path), Some(path)) => (history_path, path),
+            _ => return Ok(()),
+        };
+
+        // Catch up on any edits made since the last recorded one.
+        self.record(buffer, None, None);
+        let recording = match self.recordings.remove(&path) {
+            Some(recording) => recording,
+            None => return Ok(()),
+        };
+        let file_content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(_) => return Ok(()),
+        };
+
+        // Anchor the history to the state that matches the
+        // file, since unsaved changes are discarded.
+        let index = if buffer.modified() {
+            recording.saved_index
+        } else {
+            Some(recording.index)
+        };
+        let index = match index {
+            Some(index) if !recording.changes.is_empty() => index,
+            _ => return remove(&history_path),
+        };
+        let anchored_content = recording.content_at(index).chain_err(|| {
+            format!("Undo history for {} is out of sync with its buffer", path.to_string_lossy())
+        })?;
+        if anchored_content != file_content {
+            // The file was changed by something else.
+            return remove(&history_path);
+        }
+
+        let changes = recording.changes
+            .into_iter()
+            .map(|change| Yaml::Array(vec![
+                Yaml::Integer(change.start.line as i64),
+                Yaml::Integer(change.start.offset as i64),
+                Yaml::String(change.removed),
+                Yaml::String(change.inserted),
+            ]))
+            .collect();
+
+        let mut document = Hash::new();
+        document.insert(
+            Yaml::String(PATH_KEY.to_string()),
+            Yaml::String(path.to_string_lossy().into_owned())
+        );
+        document.insert(Yaml::String(CONTENT_HASH_KEY.to_string()), Yaml::String(hash(&file_content)));
+        document.insert(Yaml::String(INDEX_KEY.to_string()), Yaml::Integer(index as i64));
+        document.insert(Yaml::String(CHANGES_KEY.to_string()), Yaml::Array(changes));
+
+        let mut data = String::new();
+        YamlEmitter::new(&mut data)
+            .dump(&Yaml::Hash(document))
+            .chain_err(|| "Couldn't serialize undo history")?;
+
+        fs::write(&history_path, data)
+            .chain_err(|| format!("Couldn't write undo history to {}", history_path.to_string_lossy()))
+    }
+
+    /// Restores a history saved for the buffer's file, provided the buffer
+    /// hasn't been modified, and the file hasn't changed since it was saved,
+    /// and starts recording the buffer's changes. Stale or unreadable
+    /// histories are discarded.
+    pub fn restore(&mut self, buffer: &mut Buffer) -> Result<()> {
+        self.reset(buffer);
+        let history_path = match self.history_path(buffer) {
+            Some(history_path) if history_path.exists() && !buffer.modified() => history_path,
+            _ => return Ok(()),
+        };
+
+        let content = buffer.data();
+        let (restored, changes, index) = match self.rebuild(&history_path, &content) {
+            Ok(Some(restored)) => restored,
+            result => {
+                remove(&history_path)?;
+                return result.map(|_| ());
+            },
+        };
+        replace_data_and_history(buffer, restored);
+
+        if let Some(path) = buffer.path.clone() {
+            self.recordings.insert(path, Recording::new(buffer, changes, index));
+        }
+
+        Ok(())
+    }
+
+    // Builds a buffer with the specified content, and the saved history
+    // leading up to it. Returns None if the history doesn't apply to it.
+    fn rebuild(&self, history_path: &Path, content: &str) -> Result<Option<(Buffer, Vec<Change>, usize)>> {
+        let data = fs::read_to_string(history_path)
+            .chain_err(|| format!("Couldn't read undo history from {}", history_path.to_string_lossy()))?;
+        let document = YamlLoader::load_from_str(&data)
+            .chain_err(|| "Couldn't parse undo history")?
+            .into_iter()
+            .next()
+            .ok_or("Undo history is empty")?;
+
+        if document[CONTENT_HASH_KEY].as_str() != Some(hash(content).as_str()) {
+            return Ok(None);
+        }
+        let index = document[INDEX_KEY].as_i64().ok_or("Undo history has no index")? as usize;
+        let changes = document[CHANGES_KEY]
+            .as_vec()
+            .ok_or("Undo history has no changes")?
+            .iter()
+            .map(Change::from_yaml)
+            .collect::<Option<Vec<Change>>>()
+            .ok_or("Undo history has an invalid change")?;
+        if index > changes.len() {
+            return Ok(None);
+        }
+
+        // Work back from the current content to the oldest one.
+        let mut oldest = content.to_string();
+        for change in changes[..index].iter().rev() {
+            oldest = match change.reverse().apply_to(&oldest) {
+                Some(previous) => previous,
+                None => return Ok(None),
+            };
+        }
+
+        // Buffers loaded from a file start out with an empty history,
+        // which we then replay every change onto, before undoing those
+        // that came after the current content, so they can be redone.
+        let scratch_path = env::temp_dir().join(format!(
+            "amp-undo-{}-{}.tmp",
+            process::id(),
+            hash(&history_path.to_string_lossy())
+        ));
+        fs::write(&scratch_path, &oldest)
+            .chain_err(|| "Couldn't write undo history scratch file")?;
+        let mut buffer = Buffer::from_file(&scratch_path)
+            .chain_err(|| "Couldn't read undo history scratch file")?;
+
+        for change in &changes {
+            change.apply(&mut buffer);
+        }
+        for _ in index..changes.len() {
+            buffer.undo();
+        }
+
+        // Saving marks the history as being in sync with the file.
+        let saved = buffer.save();
+        fs::remove_file(&scratch_path).ok();
+        saved.chain_err(|| "Couldn't write undo history scratch file")?;
+
+        if buffer.data() != content {
+            return Ok(None);
+        }
+
+        Ok(Some((buffer, changes, index)))
+    }
+
+    // Histories are named after a hash of their buffer's path.
+    fn history_path(&self, buffer: &Buffer) -> Option<PathBuf> {
+        let directory = self.directory.as_ref()?;
+        let path = buffer.path.as_ref()?;
+
+        Some(directory.join(format!("{}.{}", hash(&path.to_string_lossy()), FILE_EXTENSION)))
+    }
+
+    /// Removes histories saved for files that no longer exist. Relative paths
+    /// can't be checked from here, and histories that can't be read are
+    /// discarded when they're restored, so both are left alone.
+    pub fn prune(&self) -> Result<()> {
+        let directory = match self.directory {
+            Some(ref directory) => directory,
+            None => return Ok(()),
+        };
+        let entries = fs::read_dir(directory)
+            .chain_err(|| format!("Couldn't read undo history from {}", directory.to_string_lossy()))?;
+
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let history_path = entry.path();
+            if history_path.extension().and_then(|extension| extension.to_str()) != Some(FILE_EXTENSION) {
+                continue;
+            }
+
+            let path = fs::read_to_string(&history_path)
+                .ok()
+                .and_then(|data| YamlLoader::load_from_str(&data).ok())
+                .and_then(|documents| documents.into_iter().next())
+                .and_then(|document| document[PATH_KEY].as_str().map(PathBuf::from));
+            if let Some(path) = path {
+                if path.is_absolute() && !path.exists() {
+                    remove(&history_path)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Recording {
+    fn new(buffer: &Buffer, changes: Vec<Change>, index: usize) -> Recording {
+        Recording {
+            changes,
+            index,
+            content: buffer.data(),
+            revision: None,
+            saved_index: Some(index).filter(|_| !buffer.modified()),
+        }
+    }
+
+    // Moves the recording to the specified content, whose lines before the
+    // changed line match the current content, undoing or redoing a change if
+    // it leads there, or adding a new one (discarding any that could've been
+    // redone), otherwise. Changes are always the smallest ones between their
+    // contents, so one that leads back to an earlier content is the reverse
+    // of the change recorded from it.
+    fn update(&mut self, content: String, changed_line: usize) {
+        let change = Change::between(&self.content, &content, changed_line);
+        if change.removed.is_empty() && change.inserted.is_empty() {
+            return;
+        }
+
+        let undone = self.index
+            .checked_sub(1)
+            .map(|index| self.changes[index].reverse());
+        if undone.as_ref() == Some(&change) {
+            self.index -= 1;
+        } else if self.changes.get(self.index) == Some(&change) {
+            self.index += 1;
+        } else {
+            self.changes.truncate(self.index);
+            if self.saved_index.map(|index| index > self.index).unwrap_or(false) {
+                self.saved_index = None;
+            }
+            self.changes.push(change);
+            self.index += 1;
+        }
+
+        self.content = content;
+    }
+
+    // The content at the specified point in the history, found by
+    // undoing or redoing changes, starting from the current content.
+    fn content_at(&self, index: usize) -> Result<String> {
+        let mut content = self.content.clone();
+        if index < self.index {
+            for change in self.changes[index..self.index].iter().rev() {
+                content = change.reverse().apply_to(&content).ok_or("A recorded change couldn't be undone")?;
+            }
+        } else {
+            let changes = self.changes.get(self.index..index).ok_or("The recorded history is too short")?;
+            for change in changes {
+                content = change.apply_to(&content).ok_or("A recorded change couldn't be redone")?;
+            }
+        }
+
+        Ok(content)
+    }
+}
+
+impl Change {
+    // The smallest change that turns the previous content into the next, both
+    // of which share their lines before the changed line. The lines that
+    // differ are found by comparing bytes (lines always start on a grapheme
+    // boundary), before comparing their graphemes.
+    fn between(previous: &str, next: &str, changed_line: usize) -> Change {
+        let (first_line, first_line_start) = line_start(previous, changed_line)
+            .filter(|&start| next.is_char_boundary(start))
+            .map(|start| (changed_line, start))
+            .unwrap_or((0, 0));
+        let common_prefix = previous.as_bytes()[first_line_start..]
+            .iter()
+            .zip(next.as_bytes()[first_line_start..].iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let start = previous.as_bytes()[first_line_start..first_line_start + common_prefix]
+            .iter()
+            .rposition(|&byte| byte == b'\n')
+            .map(|index| first_line_start + index + 1)
+            .unwrap_or(first_line_start);
+
+        let limit = previous.len().min(next.len()) - start;
+        let common_suffix = previous
+            .bytes()
+            .rev()
+            .zip(next.bytes().rev())
+            .take(limit)
+            .take_while(|(a, b)| a == b)
+            .count();
+        let suffix = previous.as_bytes()[previous.len() - common_suffix..]
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .map(|index| common_suffix - index - 1)
+            .unwrap_or(0);
+
+        let previous_lines: Vec<&str> = previous[start..previous.len() - suffix].graphemes(true).collect();
+        let next_lines: Vec<&str> = next[start..next.len() - suffix].graphemes(true).collect();
+        let prefix = previous_lines
+            .iter()
+            .zip(next_lines.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let suffix = previous_lines[prefix..]
+            .iter()
+            .rev()
+            .zip(next_lines[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        let line = first_line + previous.as_bytes()[first_line_start..start]
+            .iter()
+            .filter(|&&byte| byte == b'\n')
+            .count();
+        Change {
+            start: end_position(Position{ line, offset: 0 }, &previous_lines[..prefix].concat()),
+            removed: previous_lines[prefix..previous_lines.len() - suffix].concat(),
+            inserted: next_lines[prefix..next_lines.len() - suffix].concat(),
+        }
+    }
+
+    fn from_yaml(data: &Yaml) -> Option<Change> {
+        Some(Change {
+            start: Position {
+                line: data[0].as_i64()? as usize,
+                offset: data[1].as_i64()? as usize,
+            },
+            removed: data[2].as_str()?.to_string(),
+            inserted: data[3].as_str()?.to_string(),
+        })
+    }
+
+    fn reverse(&self) -> Change {
+        Change {
+            start: self.start,
+            removed: self.inserted.clone(),
+            inserted: self.removed.clone(),
+        }
+    }
+
+    // Applies the change to a string, provided its removed content is there.
+    fn apply_to(&self, content: &str) -> Option<String> {
+        let mut position = Position::new();
+        let mut start = None;
+
+        for (index, grapheme) in content.grapheme_indices(true) {
+            if position == self.start {
+                start = Some(index);
+                break;
+            }
+            position = end_position(position, grapheme);
+        }
+        let start = start.or_else(|| Some(content.len()).filter(|_| position == self.start))?;
+
+        if !content[start..].starts_with(&self.removed) {
+            return None;
+        }
+
+        Some(format!(
+            "{}{}{}",
+            &content[..start],
+            self.inserted,
+            &content[start + self.removed.len()..]
+        ))
+    }
+
+    // Applies the change to a buffer, as a single undoable operation.
+    fn apply(&self, buffer: &mut Buffer) {
+        buffer.start_operation_group();
+        if !self.removed.is_empty() {
+            buffer.delete_range(Range::new(self.start, end_position(self.start, &self.removed)));
+        }
+        if !self.inserted.is_empty() {
+            buffer.cursor.move_to(self.start);
+            buffer.insert(self.inserted.as_str());
+        }
+        buffer.end_operation_group();
+    }
+}
+
+// The byte offset at which the line starts, if the content has that many lines.
+fn line_start(content: &str, line: usize) -> Option<usize> {
+    if line == 0 {
+        return Some(0);
+    }
+
+    content
+        .bytes()
+        .enumerate()
+        .filter(|&(_, byte)| byte == b'\n')
+        .nth(line - 1)
+        .map(|(index, _)| index + 1)
+}
+
+// Replaces the buffer's data and history with those of the rebuilt buffer.
+// Buffers don't expose either, so the rebuilt buffer takes the original's
+// place, and every other public field is moved across from the original (as
+// `Buffer::reload` does). The cursor is tied to the data it was created with,
+// so only its position is carried over.
+fn replace_data_and_history(buffer: &mut Buffer, rebuilt: Buffer) {
+    let Buffer { id, path, cursor, syntax_definition, change_callback, .. } = mem::replace(buffer, rebuilt);
+
+    buffer.id = id;
+    buffer.path = path;
+    buffer.cursor.move_to(*cursor);
+    buffer.syntax_definition = syntax_definition;
+    buffer.change_callback = change_callback;
+}
+
+// The position following the content, when it's inserted at the start
+// position. Offsets are in graphemes, with lines delimited by newlines.
+fn end_position(start: Position, content: &str) -> Position {
+    content.graphemes(true).fold(start, |position, grapheme| {
+        if grapheme == "\n" {
+            Position{ line: position.line + 1, offset: 0 }
+        } else {
+            Position{ line: position.line, offset: position.offset + 1 }
+        }
+    })
+}
+
+// A stable (FNV-1a) hash, so that names and
+// content checks don't vary between builds.
+fn hash(content: &str) -> String {
+    let hash = content.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+
+    format!("{:016x}", hash)
+}
+
+fn remove(path: &Path) -> Result<()> {
+    if path.exists() {
+        fs::remove_file(path)
+            .chain_err(|| format!("Couldn't remove undo history at {}", path.to_string_lossy()))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::application::Preferences;
+    use crate::view::View;
+    use scribe::Buffer;
+    use scribe::buffer::Position;
+    use std::cell::RefCell;
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::rc::Rc;
+    use std::sync::mpsc;
+    use super::{Change, UndoHistory};
+
+    fn directory(name: &str) -> PathBuf {
+        let directory = env::temp_dir().join(format!("amp-undo-history-{}", name));
+        fs::create_dir_all(&directory).unwrap();
+
+        directory
+    }
+
+    fn edit(buffer: &mut Buffer, position: Position, content: &str) {
+        buffer.start_operation_group();
+        buffer.cursor.move_to(position);
+        buffer.insert(content);
+        buffer.end_operation_group();
+    }
+
+    #[test]
+    fn restore_rebuilds_undo_and_redo_history() {
+        let directory = directory("restore");
+        let file_path = directory.join("file.txt");
+        fs::write(&file_path, "amp\n").unwrap();
+        let mut history = UndoHistory::new(Some(directory.clone()));
+
+        let mut buffer = Buffer::from_file(&file_path).unwrap();
+        history.restore(&mut buffer).unwrap();
+        edit(&mut buffer, Position{ line: 0, offset: 3 }, " editor");
+        history.record(&buffer, None, None);
+        edit(&mut buffer, Position{ line: 1, offset: 0 }, "日本\nscribe");
+        buffer.save().unwrap();
+        history.saved(&buffer, None, None);
+        edit(&mut buffer, Position{ line: 0, offset: 0 }, "the ");
+        history.record(&buffer, None, None);
+        buffer.undo();
+        history.save(&buffer).unwrap();
+
+        let mut buffer = Buffer::from_file(&file_path).unwrap();
+        history.restore(&mut buffer).unwrap();
+        assert_eq!(buffer.data(), "amp editor\n日本\nscribe");
+        assert!(!buffer.modified());
+
+        buffer.redo();
+        assert_eq!(buffer.data(), "the amp editor\n日本\nscribe");
+        buffer.undo();
+        buffer.undo();
+        assert_eq!(buffer.data(), "amp editor\n");
+        buffer.undo();
+        assert_eq!(buffer.data(), "amp\n");
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn save_anchors_history_to_the_saved_content() {
+        let directory = directory("anchor");
+        let file_path = directory.join("file.txt");
+        fs::write(&file_path, "amp").unwrap();
+        let mut history = UndoHistory::new(Some(directory.clone()));
+
+        let mut buffer = Buffer::from_file(&file_path).unwrap();
+        history.restore(&mut buffer).unwrap();
+        edit(&mut buffer, Position{ line: 0, offset: 3 }, " editor");
+        buffer.save().unwrap();
+        history.saved(&buffer, None, None);
+
+        // Unsaved changes can be redone, and empty operation groups are skipped.
+        buffer.start_operation_group();
+        buffer.end_operation_group();
+        edit(&mut buffer, Position{ line: 0, offset: 0 }, "the ");
+        history.save(&buffer).unwrap();
+
+        let mut buffer = Buffer::from_file(&file_path).unwrap();
+        history.restore(&mut buffer).unwrap();
+        assert_eq!(buffer.data(), "amp editor");
+        buffer.redo();
+        assert_eq!(buffer.data(), "the amp editor");
+        buffer.undo();
+        buffer.undo();
+        assert_eq!(buffer.data(), "amp");
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn record_follows_undo_and_redo_from_the_changed_line() {
+        let directory = directory("changed-line");
+        let file_path = directory.join("file.txt");
+        fs::write(&file_path, "amp\neditor\n").unwrap();
+        let mut history = UndoHistory::new(Some(directory.clone()));
+        let preferences = Rc::new(RefCell::new(Preferences::new(None)));
+        let (tx, _) = mpsc::channel();
+        let mut view = View::new(preferences, tx).unwrap();
+
+        let mut buffer = Buffer::from_file(&file_path).unwrap();
+        buffer.id = Some(0);
+        view.initialize_buffer(&mut buffer).unwrap();
+        history.restore(&mut buffer).unwrap();
+        let record = |history: &mut UndoHistory, buffer: &Buffer| {
+            history.record(buffer, view.buffer_revision(buffer), view.take_changed_line(buffer));
+        };
+
+        edit(&mut buffer, Position{ line: 1, offset: 0 }, "the ");
+        record(&mut history, &buffer);
+        edit(&mut buffer, Position{ line: 2, offset: 0 }, "scribe");
+        record(&mut history, &buffer);
+        buffer.undo();
+        record(&mut history, &buffer);
+        buffer.undo();
+        record(&mut history, &buffer);
+        buffer.redo();
+        record(&mut history, &buffer);
+
+        let recording = &history.recordings[&file_path];
+        assert_eq!(recording.changes.len(), 2);
+        assert_eq!(recording.index, 1);
+        assert_eq!(recording.content, "amp\nthe editor\n");
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn save_fails_when_the_recording_is_out_of_sync() {
+        let directory = directory("sync");
+        let file_path = directory.join("file.txt");
+        fs::write(&file_path, "amp").unwrap();
+        let mut history = UndoHistory::new(Some(directory.clone()));
+
+        let mut buffer = Buffer::from_file(&file_path).unwrap();
+        history.restore(&mut buffer).unwrap();
+        edit(&mut buffer, Position{ line: 0, offset: 3 }, " editor");
+        buffer.save().unwrap();
+        history.saved(&buffer, None, None);
+        edit(&mut buffer, Position{ line: 0, offset: 0 }, "the ");
+        history.record(&buffer, None, None);
+
+        let path = buffer.path.clone().unwrap();
+        history.recordings.get_mut(&path).unwrap().changes[1].inserted = "a ".to_string();
+        assert!(history.save(&buffer).is_err());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn restore_discards_history_when_the_file_has_changed() {
+        let directory = directory("discard");
+        let file_path = directory.join("file.txt");
+        fs::write(&file_path, "amp").unwrap();
+        let mut history = UndoHistory::new(Some(directory.clone()));
+
+        let mut buffer = Buffer::from_file(&file_path).unwrap();
+        history.restore(&mut buffer).unwrap();
+        edit(&mut buffer, Position{ line: 0, offset: 3 }, " editor");
+        buffer.save().unwrap();
+        history.saved(&buffer, None, None);
+        let history_path = history.history_path(&buffer).unwrap();
+        history.save(&buffer).unwrap();
+        assert!(history_path.exists());
+
+        fs::write(&file_path, "changed").unwrap();
+        let mut buffer = Buffer::from_file(&file_path).unwrap();
+        history.restore(&mut buffer).unwrap();
+        buffer.undo();
+        assert_eq!(buffer.data(), "changed");
+        assert!(!history_path.exists());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn prune_removes_histories_of_deleted_files() {
+        let directory = directory("prune");
+        let file_path = directory.join("file.txt");
+        fs::write(&file_path, "amp").unwrap();
+        let mut history = UndoHistory::new(Some(directory.clone()));
+
+        let mut buffer = Buffer::from_file(&file_path).unwrap();
+        history.restore(&mut buffer).unwrap();
+        edit(&mut buffer, Position{ line: 0, offset: 3 }, " editor");
+        buffer.save().unwrap();
+        history.saved(&buffer, None, None);
+        let history_path = history.history_path(&buffer).unwrap();
+        history.save(&buffer).unwrap();
+
+        history.prune().unwrap();
+        assert!(history_path.exists());
+
+        fs::remove_file(&file_path).unwrap();
+        history.prune().unwrap();
+        assert!(!history_path.exists());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn between_finds_the_smallest_change() {
+        let change = Change::between("amp\n日本 editor", "amp\n日本語 editor", 0);
+        assert_eq!(change, Change {
+            start: Position{ line: 1, offset: 2 },
+            removed: String::new(),
+            inserted: "語".to_string(),
+        });
+    }
+
+    #[test]
+    fn between_starts_comparing_at_the_changed_line() {
+        let change = Change::between("amp\namp\namp", "amp\namp\nthe amp", 2);
+        assert_eq!(change, Change {
+            start: Position{ line: 2, offset: 0 },
+            removed: String::new(),
+            inserted: "the ".to_string(),
+        });
+
+        // Changed lines past the end of the content are compared from the start.
+        let change = Change::between("amp", "the amp", 3);
+        assert_eq!(change.start, Position{ line: 0, offset: 0 });
+    }
+}
@@ -10,6 +10,14 @@ fn path_as_title(path: &Path) -> String {
     format!(" {}", path.to_string_lossy())
 }
 
+// Builds a mode's status line label, noting any macro being recorded.
+fn mode_label(mode: &str, recording: Option<&str>) -> String {
+    match recording {
+        Some(name) => format!(" {} (recording @{}) ", mode, name),
+        None => format!(" {} ", mode),
+    }
+}
+
 fn current_buffer_status_line_data(workspace: &mut Workspace) -> StatusLineData {
     let modified = workspace.current_buffer().map(|b| b.modified()).unwrap_or(false);
 
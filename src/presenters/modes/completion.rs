@@ -0,0 +1,38 @@
+use crate::errors::*;
+use crate::models::application::modes::CompletionMode;
+use crate::presenters::{current_buffer_status_line_data, mode_label};
+use scribe::Workspace;
+use scribe::buffer::Range;
+use crate::util::columns;
+use crate::view::{Colors, StatusLineData, Style, View};
+
+pub fn display(workspace: &mut Workspace, mode: &CompletionMode, view: &mut View, recording: Option<&str>) -> Result<()> {
+    let mut presenter = view.build_presenter()?;
+    let buffer_status = current_buffer_status_line_data(workspace);
+    let buf = workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let data = buf.data();
+    let partial_identifier = buf
+        .read(&Range::new(mode.start, *buf.cursor))
+        .unwrap_or_default();
+
+    // Draw the visible set of tokens to the terminal.
+    presenter.print_buffer(buf, &data, None, None)?;
+
+    // List the candidates below the partial identifier they'd replace.
+    let results: Vec<String> = mode.results().cloned().collect();
+    presenter.print_popup(&results, mode.selected_index(), columns::width(&partial_identifier));
+
+    presenter.print_status_line(&[
+        StatusLineData {
+            content: mode_label("INSERT", recording),
+            style: Style::Default,
+            colors: Colors::Insert,
+        },
+        buffer_status
+    ]);
+
+    // Render the changes to the screen.
+    presenter.present();
+
+    Ok(())
+}
@@ -1,21 +1,23 @@
 
 use crate::errors::*;
-use crate::presenters::current_buffer_status_line_data;
+use crate::presenters::{current_buffer_status_line_data, mode_label};
 use scribe::Workspace;
+use scribe::buffer::Position;
 use crate::view::{Colors, StatusLineData, Style, View};
 
-pub fn display(workspace: &mut Workspace, view: &mut View) -> Result<()> {
+pub fn display(workspace: &mut Workspace, view: &mut View, cursors: &[Position], recording: Option<&str>) -> Result<()> {
     let mut presenter = view.build_presenter()?;
     let buffer_status = current_buffer_status_line_data(workspace);
     let buf = workspace.current_buffer().ok_or(BUFFER_MISSING)?;
     let data = buf.data();
 
     // Draw the visible set of tokens to the terminal.
+    presenter.set_secondary_cursors(cursors.to_vec());
     presenter.print_buffer(buf, &data, None, None)?;
 
     presenter.print_status_line(&[
         StatusLineData {
-            content: " INSERT ".to_string(),
+            content: mode_label("INSERT", recording),
             style: Style::Default,
             colors: Colors::Insert,
         },
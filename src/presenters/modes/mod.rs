@@ -1,10 +1,15 @@
+pub mod completion;
 pub mod confirm;
 pub mod insert;
 pub mod jump;
 pub mod line_jump;
 pub mod path;
+pub mod prompt;
 pub mod normal;
+pub mod replace;
 pub mod search;
 pub mod search_select;
 pub mod select;
+pub mod select_block;
 pub mod select_line;
+pub mod tab_stop;
@@ -1,19 +1,20 @@
 
 use crate::errors::*;
 use scribe::Workspace;
-use scribe::buffer::Position;
-use crate::presenters::{current_buffer_status_line_data, git_status_line_data};
+use scribe::buffer::{Position, Range};
+use crate::presenters::{current_buffer_status_line_data, git_status_line_data, mode_label};
 use git2::Repository;
 use crate::view::{Colors, StatusLineData, Style, View};
 
-pub fn display(workspace: &mut Workspace, view: &mut View, repo: &Option<Repository>) -> Result<()> {
+pub fn display(workspace: &mut Workspace, view: &mut View, repo: &Option<Repository>, highlights: Option<&[Range]>, cursors: &[Position], recording: Option<&str>, count: Option<usize>) -> Result<()> {
     let mut presenter = view.build_presenter()?;
     let buffer_status = current_buffer_status_line_data(workspace);
 
     if let Some(buf) = workspace.current_buffer() {
         // Draw the visible set of tokens to the terminal.
         let data = buf.data();
-        presenter.print_buffer(buf, &data, None, None)?;
+        presenter.set_secondary_cursors(cursors.to_vec());
+        presenter.print_buffer(buf, &data, highlights, None)?;
 
         // Determine mode display color based on buffer modification status.
         let colors = if buf.modified() {
@@ -22,10 +23,15 @@ pub fn display(workspace: &mut Workspace, view: &mut View, repo: &Option<Reposit
             Colors::Inverted
         };
 
-        // Build the status line mode and buffer title display.
+        // Build the status line mode and buffer title display,
+        // including the pending count, if one is being entered.
+        let mode = match count {
+            Some(count) => format!("NORMAL {}", count),
+            None => String::from("NORMAL"),
+        };
         presenter.print_status_line(&[
             StatusLineData {
-                content: " NORMAL ".to_string(),
+                content: mode_label(&mode, recording),
                 style: Style::Default,
                 colors,
             },
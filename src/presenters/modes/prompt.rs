@@ -0,0 +1,38 @@
+use crate::errors::*;
+use std::fmt::Display;
+use scribe::Workspace;
+use scribe::buffer::Position;
+use unicode_segmentation::UnicodeSegmentation;
+use crate::view::{Colors, StatusLineData, Style, View};
+
+/// Draws the current buffer, with the mode's description as an input prompt
+/// in the status line, for modes that respond to a single key press.
+pub fn display(workspace: &mut Workspace, mode: &dyn Display, view: &mut View) -> Result<()> {
+    let mut presenter = view.build_presenter()?;
+    let buf = workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let data = buf.data();
+    presenter.print_buffer(buf, &data, None, None)?;
+
+    // Draw the status line as an input prompt.
+    let input_prompt = mode.to_string();
+    let input_prompt_len = input_prompt.graphemes(true).count();
+    presenter.print_status_line(&[
+        StatusLineData {
+            content: input_prompt,
+            style: Style::Default,
+            colors: Colors::Default,
+        }
+    ]);
+
+    // Move the cursor to the end of the prompt.
+    let cursor_line = presenter.height() - 1;
+    presenter.set_cursor(Some(Position {
+        line: cursor_line,
+        offset: input_prompt_len,
+    }));
+
+    // Render the changes to the screen.
+    presenter.present();
+
+    Ok(())
+}
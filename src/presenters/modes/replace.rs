@@ -0,0 +1,63 @@
+use crate::errors::*;
+use scribe::Workspace;
+use scribe::buffer::Position;
+use crate::models::application::modes::ReplaceMode;
+use unicode_segmentation::UnicodeSegmentation;
+use crate::view::{Colors, StatusLineData, Style, View};
+
+pub fn display(workspace: &mut Workspace, mode: &ReplaceMode, view: &mut View) -> Result<()> {
+    let mut presenter = view.build_presenter()?;
+
+    // Draw the visible set of tokens to the terminal.
+    let buffer = workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let data = buffer.data();
+    let results = mode.search.results.as_ref().map(|r| r.as_slice());
+    presenter.print_buffer(buffer, &data, results, None)?;
+
+    let mode_display = format!(" {} ", mode);
+    let replacement_input = format!(
+        " {}",
+        mode.input.as_ref().unwrap_or(&String::new())
+    );
+    let prompt_display = if mode.insert {
+        String::new()
+    } else {
+        format!(" {} remaining: (y)es (n)o (a)ll (q)uit", mode.remaining)
+    };
+
+    let cursor_offset =
+        mode_display.graphemes(true).count() +
+        replacement_input.graphemes(true).count();
+
+    presenter.print_status_line(&[
+        StatusLineData {
+            content: mode_display,
+            style: Style::Default,
+            colors: Colors::SearchMode,
+        },
+        StatusLineData {
+            content: replacement_input,
+            style: Style::Default,
+            colors: Colors::Focused,
+        },
+        StatusLineData {
+            content: prompt_display,
+            style: Style::Default,
+            colors: Colors::Focused,
+        },
+    ]);
+
+    // Move the cursor to the end of the replacement input.
+    if mode.insert {
+        let cursor_line = presenter.height() - 1;
+        presenter.set_cursor(Some(Position {
+            line: cursor_line,
+            offset: cursor_offset
+        }));
+    }
+
+    // Render the changes to the screen.
+    presenter.present();
+
+    Ok(())
+}
@@ -19,16 +19,22 @@ pub fn display(workspace: &mut Workspace, mode: &SearchMode, view: &mut View) ->
         " {}",
         mode.input.as_ref().unwrap_or(&String::new())
     );
-    let result_display = if mode.insert {
-        String::new()
-    } else if let Some(ref results) = mode.results {
-        if results.len() == 1 {
-            String::from("1 match")
-        } else {
-            format!("{} of {} matches", results.selected_index() + 1, results.len())
-        }
-    } else {
-        String::new()
+    let mut options_display = String::new();
+    if mode.options.regex {
+        options_display.push_str(" [regex]");
+    }
+    if mode.options.smart_case {
+        options_display.push_str(" [smart case]");
+    } else if mode.options.case_insensitive {
+        options_display.push_str(" [ignore case]");
+    }
+    if mode.options.whole_word {
+        options_display.push_str(" [whole word]");
+    }
+    let result_display = match mode.results {
+        Some(ref results) if results.is_empty() => String::from("no matches"),
+        Some(ref results) => format!("match {} of {}", results.selected_index() + 1, results.len()),
+        None => String::new(),
     };
 
     let cursor_offset =
@@ -46,6 +52,11 @@ pub fn display(workspace: &mut Workspace, mode: &SearchMode, view: &mut View) ->
             style: Style::Default,
             colors: Colors::Focused,
         },
+        StatusLineData {
+            content: options_display,
+            style: Style::Default,
+            colors: Colors::Focused,
+        },
         StatusLineData {
             content: result_display,
             style: Style::Default,
@@ -0,0 +1,30 @@
+use crate::errors::*;
+use crate::models::application::modes::SelectBlockMode;
+use scribe::Workspace;
+use crate::presenters::current_buffer_status_line_data;
+use crate::view::{Colors, StatusLineData, Style, View};
+
+pub fn display(workspace: &mut Workspace, mode: &SelectBlockMode, view: &mut View) -> Result<()> {
+    let mut presenter = view.build_presenter()?;
+    let buffer_status = current_buffer_status_line_data(workspace);
+    let buf = workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let data = buf.data();
+    let selected_ranges = mode.ranges(&data, &buf.cursor);
+
+    // Draw the visible set of tokens to the terminal.
+    presenter.print_buffer(buf, &data, Some(&selected_ranges), None)?;
+
+    presenter.print_status_line(&[
+        StatusLineData {
+            content: " SELECT BLOCK ".to_string(),
+            style: Style::Default,
+            colors: Colors::SelectMode,
+        },
+        buffer_status
+    ]);
+
+    // Render the changes to the screen.
+    presenter.present();
+
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use crate::errors::*;
+use crate::models::application::modes::TabStopMode;
+use crate::presenters::{current_buffer_status_line_data, mode_label};
+use scribe::Workspace;
+use crate::view::{Colors, StatusLineData, Style, View};
+
+pub fn display(workspace: &mut Workspace, mode: &TabStopMode, view: &mut View, recording: Option<&str>) -> Result<()> {
+    let mut presenter = view.build_presenter()?;
+    let buffer_status = current_buffer_status_line_data(workspace);
+    let buf = workspace.current_buffer().ok_or(BUFFER_MISSING)?;
+    let data = buf.data();
+
+    // Draw the visible set of tokens to the terminal,
+    // highlighting the placeholder being edited and its mirrors.
+    presenter.print_buffer(buf, &data, Some(mode.ranges()), None)?;
+
+    presenter.print_status_line(&[
+        StatusLineData {
+            content: mode_label("SNIPPET", recording),
+            style: Style::Default,
+            colors: Colors::Insert,
+        },
+        buffer_status
+    ]);
+
+    // Render the changes to the screen.
+    presenter.present();
+
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use crate::util::columns;
+use regex::Regex;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Pads the lines with spaces so that their delimiters line up, with the
+/// first delimiter on each line in one column, the second in the next, and
+/// so on. Delimiters are only ever moved right, to the column of the one
+/// displayed furthest along, accounting for tabs and wide characters.
+pub fn align(lines: &[&str], delimiter: &Regex, tab_width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+
+    // Earlier columns are padded first, since that moves the ones following them.
+    for index in 0.. {
+        let delimiters: Vec<Option<(usize, usize)>> = lines
+            .iter()
+            .map(|line| {
+                let (start, _) = delimiter
+                    .find_iter(line)
+                    .filter(|&(start, end)| start != end)
+                    .nth(index)?;
+                let offset = line[..start].graphemes(true).count();
+
+                Some((start, columns::column(line, offset, tab_width)))
+            })
+            .collect();
+
+        let target = match delimiters.iter().flatten().map(|&(_, column)| column).max() {
+            Some(column) => column,
+            None => break,
+        };
+
+        for (line, delimiter) in lines.iter_mut().zip(delimiters) {
+            if let Some((start, column)) = delimiter {
+                line.insert_str(start, &" ".repeat(target - column));
+            }
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+
+    #[test]
+    fn align_pads_lines_up_to_the_furthest_delimiter() {
+        let lines = ["a = 1", "long = 2", "mid=3", "none"];
+
+        assert_eq!(
+            super::align(&lines, &Regex::new("=").unwrap(), 4),
+            vec!["a    = 1", "long = 2", "mid  =3", "none"]
+        );
+    }
+
+    #[test]
+    fn align_treats_each_occurrence_as_a_separate_column() {
+        let lines = ["x: 1, y: 2", "\tlong: 10, z: 3", "日本: 4"];
+
+        assert_eq!(
+            super::align(&lines, &Regex::new(":").unwrap(), 4),
+            vec!["x       : 1, y : 2", "\tlong: 10, z: 3", "日本    : 4"]
+        );
+    }
+}
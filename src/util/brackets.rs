@@ -0,0 +1,242 @@
+use scribe::buffer::Position;
+use scribe::util::LineIterator;
+use syntect::parsing::{ParseState, Scope, ScopeStack, ScopeStackOp, SyntaxDefinition};
+use unicode_segmentation::UnicodeSegmentation;
+
+const PAIRS: [(&str, &str); 3] = [("(", ")"), ("[", "]"), ("{", "}")];
+const IGNORED_SCOPES: [&str; 2] = ["comment", "string"];
+
+/// How many lines away from a bracket its match is looked for.
+pub const MATCH_SEARCH_LINES: usize = 500;
+
+/// The canonical form of a grapheme, if it's a bracket.
+pub fn bracket(grapheme: &str) -> Option<&'static str> {
+    PAIRS.iter().find_map(|&(open, close)| {
+        if grapheme == open {
+            Some(open)
+        } else if grapheme == close {
+            Some(close)
+        } else {
+            None
+        }
+    })
+}
+
+/// Brackets found in code, collected line by line using syntect's
+/// parse events, so that those in strings and comments are ignored.
+pub struct BracketIndex {
+    brackets: Vec<(Position, &'static str)>,
+    ignored_scopes: Vec<Scope>,
+}
+
+impl Default for BracketIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BracketIndex {
+    pub fn new() -> BracketIndex {
+        BracketIndex {
+            brackets: Vec::new(),
+            ignored_scopes: IGNORED_SCOPES
+                .iter()
+                .filter_map(|scope| Scope::new(scope).ok())
+                .collect(),
+        }
+    }
+
+    /// The position of the bracket paired with the one at the specified
+    /// position, looking no further than `MATCH_SEARCH_LINES` lines away from
+    /// it. Only the lines up to that bound are parsed; parsing resumes from the
+    /// closest state preceding the search provided by `cached_state`, if any.
+    pub fn find_matching<F>(data: &str, syntax: Option<&SyntaxDefinition>, position: Position, cached_state: F) -> Option<Position>
+        where F: Fn(usize) -> Option<(usize, ParseState, ScopeStack)>
+    {
+        let line = LineIterator::new(data).nth(position.line)?.1;
+        let grapheme = line.graphemes(true).nth(position.offset)?;
+        bracket(grapheme)?;
+        let forward = PAIRS.iter().any(|&(open, _)| open == grapheme);
+
+        let (first_line, last_line) = if forward {
+            (position.line, position.line + MATCH_SEARCH_LINES)
+        } else {
+            (position.line.saturating_sub(MATCH_SEARCH_LINES), position.line)
+        };
+
+        // Without a syntax definition, there's no state to carry
+        // over from preceding lines, so we can start right away.
+        let (start_line, mut parse_state, mut scopes) = match syntax {
+            Some(syntax) => match cached_state(first_line) {
+                Some((line, state, scopes)) => (line, Some(state), scopes),
+                None => (0, Some(ParseState::new(syntax)), ScopeStack::new()),
+            },
+            None => (first_line, None, ScopeStack::new()),
+        };
+
+        let mut index = BracketIndex::new();
+        let lines = LineIterator::new(data)
+            .skip(start_line)
+            .take_while(|(line_number, _)| *line_number <= last_line);
+        for (line_number, line) in lines {
+            let events = parse_state
+                .as_mut()
+                .map(|state| state.parse_line(line))
+                .unwrap_or_default();
+
+            // Lines preceding the search are only parsed to bring the state up to date.
+            if line_number < first_line {
+                for (_, op) in events {
+                    scopes.apply(&op);
+                }
+            } else {
+                index.add_line(line_number, line, &events, &mut scopes);
+            }
+        }
+
+        index.matching(position)
+    }
+
+    /// Adds the brackets found in a line, using its parse events and the
+    /// scopes preceding it, which are updated to those at the end of the line.
+    pub fn add_line(&mut self, line_number: usize, line: &str, events: &[(usize, ScopeStackOp)], scopes: &mut ScopeStack) {
+        let mut events = events.iter().peekable();
+
+        for (offset, (byte_index, grapheme)) in line.grapheme_indices(true).enumerate() {
+            while let Some((_, op)) = events.peek().filter(|(index, _)| *index <= byte_index) {
+                scopes.apply(op);
+                events.next();
+            }
+
+            if let Some(bracket) = bracket(grapheme) {
+                if !self.ignored(scopes) {
+                    self.brackets.push((Position{ line: line_number, offset }, bracket));
+                }
+            }
+        }
+
+        for (_, op) in events {
+            scopes.apply(op);
+        }
+    }
+
+    /// The position of the bracket paired with the one at the specified
+    /// position, skipping over nested pairs of the same kind.
+    pub fn matching(&self, position: Position) -> Option<Position> {
+        let index = self.brackets.iter().position(|(p, _)| *p == position)?;
+        let bracket = self.brackets[index].1;
+        let &(open, close) = PAIRS
+            .iter()
+            .find(|(open, close)| *open == bracket || *close == bracket)?;
+        let (target, candidates): (&str, Box<dyn Iterator<Item=&(Position, &str)>>) = if bracket == open {
+            (close, Box::new(self.brackets[index + 1..].iter()))
+        } else {
+            (open, Box::new(self.brackets[..index].iter().rev()))
+        };
+
+        let mut depth = 0;
+        for &(position, candidate) in candidates {
+            if candidate == bracket {
+                depth += 1;
+            } else if candidate == target {
+                if depth == 0 {
+                    return Some(position);
+                }
+                depth -= 1;
+            }
+        }
+
+        None
+    }
+
+    fn ignored(&self, scopes: &ScopeStack) -> bool {
+        scopes.as_slice().iter().any(|scope| {
+            self.ignored_scopes.iter().any(|ignored| ignored.is_prefix_of(*scope))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BracketIndex, MATCH_SEARCH_LINES};
+    use scribe::Buffer;
+    use scribe::buffer::Position;
+    use syntect::parsing::{ParseState, ScopeStack, SyntaxSet};
+
+    // The bracket matching the one at the position, without any cached state.
+    fn matching(buffer: &Buffer, position: Position) -> Option<Position> {
+        BracketIndex::find_matching(&buffer.data(), buffer.syntax_definition.as_ref(), position, |_| None)
+    }
+
+    fn rust_buffer(data: &str) -> Buffer {
+        let mut buffer = Buffer::new();
+        buffer.insert(data);
+        buffer.syntax_definition = SyntaxSet::load_defaults_newlines()
+            .find_syntax_by_extension("rs")
+            .cloned();
+
+        buffer
+    }
+
+    #[test]
+    fn matching_skips_nested_pairs_in_either_direction() {
+        let buffer = rust_buffer("fn amp() {\n    if (a) { b(); }\n}\n");
+
+        assert_eq!(matching(&buffer, Position{ line: 0, offset: 9 }), Some(Position{ line: 2, offset: 0 }));
+        assert_eq!(matching(&buffer, Position{ line: 2, offset: 0 }), Some(Position{ line: 0, offset: 9 }));
+        assert_eq!(matching(&buffer, Position{ line: 1, offset: 7 }), Some(Position{ line: 1, offset: 9 }));
+        assert_eq!(matching(&buffer, Position{ line: 1, offset: 4 }), None);
+    }
+
+    #[test]
+    fn matching_ignores_brackets_in_strings_and_comments() {
+        let buffer = rust_buffer("amp(\")\", // )\n    1)\n");
+
+        assert_eq!(matching(&buffer, Position{ line: 0, offset: 3 }), Some(Position{ line: 1, offset: 5 }));
+        assert_eq!(matching(&buffer, Position{ line: 0, offset: 5 }), None);
+    }
+
+    #[test]
+    fn find_matching_is_bounded() {
+        let data = format!("{{{}}}", "\n".repeat(MATCH_SEARCH_LINES));
+        let buffer = rust_buffer(&data);
+        let syntax = buffer.syntax_definition.as_ref();
+        let open = Position{ line: 0, offset: 0 };
+        let close = Position{ line: MATCH_SEARCH_LINES, offset: 0 };
+        assert_eq!(BracketIndex::find_matching(&data, syntax, open, |_| None), Some(close));
+        assert_eq!(BracketIndex::find_matching(&data, syntax, close, |_| None), Some(open));
+
+        let data = format!("{{\n{}}}", "\n".repeat(MATCH_SEARCH_LINES));
+        let close = Position{ line: MATCH_SEARCH_LINES + 1, offset: 0 };
+        assert_eq!(BracketIndex::find_matching(&data, syntax, open, |_| None), None);
+        assert_eq!(BracketIndex::find_matching(&data, syntax, close, |_| None), None);
+    }
+
+    #[test]
+    fn find_matching_resumes_from_cached_state() {
+        let buffer = rust_buffer("amp(\n)\n");
+        let syntax = buffer.syntax_definition.as_ref();
+        let close = Position{ line: 1, offset: 0 };
+        assert_eq!(
+            BracketIndex::find_matching(&buffer.data(), syntax, close, |_| None),
+            Some(Position{ line: 0, offset: 3 })
+        );
+
+        // A cached state left inside of an unterminated string.
+        let mut state = ParseState::new(syntax.unwrap());
+        let mut scopes = ScopeStack::new();
+        for (_, op) in state.parse_line("let amp = \"\n") {
+            scopes.apply(&op);
+        }
+        let cached_state = |_| Some((0, state.clone(), scopes.clone()));
+        assert_eq!(BracketIndex::find_matching(&buffer.data(), syntax, close, cached_state), None);
+    }
+
+    #[test]
+    fn all_brackets_are_indexed_without_a_syntax_definition() {
+        let mut buffer = Buffer::new();
+        buffer.insert("[\"]\"]");
+
+        assert_eq!(matching(&buffer, Position{ line: 0, offset: 0 }), Some(Position{ line: 0, offset: 2 }));
+    }
+}
@@ -0,0 +1,122 @@
+use crate::util::movement_lexer;
+use luthor::token::Category;
+
+const SEPARATORS: [char; 2] = ['_', '-'];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Case {
+    Upper,
+    Lower,
+    Title,
+    Snake,
+    Camel,
+    Pascal,
+    Kebab,
+}
+
+/// Converts text to the specified case. Upper and lower case apply to the
+/// text as a whole; the others convert each identifier in the text (a run
+/// of alphanumeric characters, underscores and hyphens) separately, leaving
+/// the text between them, as well as leading and trailing separators, as-is.
+pub fn convert(text: &str, case: Case) -> String {
+    match case {
+        Case::Upper => return text.to_uppercase(),
+        Case::Lower => return text.to_lowercase(),
+        _ => (),
+    }
+
+    let mut result = String::new();
+    let mut identifier = String::new();
+    for character in text.chars() {
+        if is_identifier_char(character) {
+            identifier.push(character);
+        } else {
+            result.push_str(&convert_identifier(&identifier, case));
+            result.push(character);
+            identifier.clear();
+        }
+    }
+    result.push_str(&convert_identifier(&identifier, case));
+
+    result
+}
+
+/// Splits an identifier into its words, using the movement lexer's
+/// boundaries (e.g. "camelCase" and "snake_case" both have two words).
+pub fn words(identifier: &str) -> Vec<String> {
+    movement_lexer::lex(identifier)
+        .into_iter()
+        .filter(|token| token.category == Category::Text)
+        .map(|token| token.lexeme)
+        .filter(|lexeme| lexeme.chars().any(|c| c.is_alphanumeric()))
+        .collect()
+}
+
+fn convert_identifier(identifier: &str, case: Case) -> String {
+    let trimmed = identifier.trim_matches(&SEPARATORS[..]);
+    if trimmed.is_empty() {
+        return identifier.to_string();
+    }
+    let prefix_length = identifier.len() - identifier.trim_start_matches(&SEPARATORS[..]).len();
+    let prefix = &identifier[..prefix_length];
+    let suffix = &identifier[prefix_length + trimmed.len()..];
+
+    let words = words(trimmed);
+    let converted = match case {
+        Case::Snake => join(&words, "_", str::to_lowercase),
+        Case::Kebab => join(&words, "-", str::to_lowercase),
+        Case::Title => join(&words, " ", capitalize),
+        Case::Pascal => join(&words, "", capitalize),
+        Case::Camel => {
+            words
+                .iter()
+                .enumerate()
+                .map(|(index, word)| if index == 0 { word.to_lowercase() } else { capitalize(word) })
+                .collect()
+        },
+        Case::Upper | Case::Lower => unreachable!(),
+    };
+
+    format!("{}{}{}", prefix, converted, suffix)
+}
+
+fn join(words: &[String], separator: &str, transform: fn(&str) -> String) -> String {
+    words
+        .iter()
+        .map(|word| transform(word))
+        .collect::<Vec<String>>()
+        .join(separator)
+}
+
+fn capitalize(word: &str) -> String {
+    let mut characters = word.chars();
+    match characters.next() {
+        Some(first) => first.to_uppercase().chain(characters.as_str().to_lowercase().chars()).collect(),
+        None => String::new(),
+    }
+}
+
+fn is_identifier_char(character: char) -> bool {
+    character.is_alphanumeric() || SEPARATORS.contains(&character)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Case;
+
+    #[test]
+    fn convert_changes_identifiers_between_naming_conventions() {
+        assert_eq!(super::convert("amp_editor", Case::Camel), "ampEditor");
+        assert_eq!(super::convert("ampEditor", Case::Snake), "amp_editor");
+        assert_eq!(super::convert("amp-editor", Case::Pascal), "AmpEditor");
+        assert_eq!(super::convert("AmpEditor", Case::Kebab), "amp-editor");
+        assert_eq!(super::convert("AMP_EDITOR", Case::Camel), "ampEditor");
+        assert_eq!(super::convert("amp_editor", Case::Title), "Amp Editor");
+    }
+
+    #[test]
+    fn convert_preserves_surrounding_text_and_separators() {
+        assert_eq!(super::convert("_ampEditor(scribeBuffer);", Case::Snake), "_amp_editor(scribe_buffer);");
+        assert_eq!(super::convert("amp editor", Case::Upper), "AMP EDITOR");
+    }
+}
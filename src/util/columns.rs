@@ -0,0 +1,111 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// The number of terminal columns used to display the specified content.
+/// Wide characters use two columns; every other grapheme (including
+/// those made up of zero-width characters) is given at least one.
+pub fn width(content: &str) -> usize {
+    content
+        .graphemes(true)
+        .map(|grapheme| grapheme.width().max(1))
+        .sum()
+}
+
+/// The column at which the grapheme at the specified offset is displayed,
+/// expanding tabs to the next tab stop. Offsets beyond the end of the line
+/// are treated as though the line were padded with spaces to reach them.
+pub fn column(line: &str, offset: usize, tab_width: usize) -> usize {
+    let mut column = 0;
+    let mut length = 0;
+    for grapheme in line.graphemes(true).take(offset) {
+        column += grapheme_width(grapheme, column, tab_width);
+        length += 1;
+    }
+
+    column + offset - length
+}
+
+/// The number of columns used to display the grapheme at the specified offset
+/// (or one, for offsets beyond the end of the line, where the cursor is drawn).
+pub fn width_at(line: &str, offset: usize, tab_width: usize) -> usize {
+    match line.graphemes(true).nth(offset) {
+        Some(grapheme) => grapheme_width(grapheme, column(line, offset, tab_width), tab_width),
+        None => 1,
+    }
+}
+
+/// The start and end offsets of the graphemes displayed (even partially)
+/// within the specified columns. Lines that end before the first of them
+/// produce an empty span at the end of the line.
+pub fn span(line: &str, first_column: usize, end_column: usize, tab_width: usize) -> (usize, usize) {
+    let mut start = None;
+    let mut end = 0;
+    let mut column = 0;
+    for (offset, grapheme) in line.graphemes(true).enumerate() {
+        let next_column = column + grapheme_width(grapheme, column, tab_width);
+        if column >= end_column {
+            break;
+        }
+        if next_column > first_column {
+            start = start.or(Some(offset));
+        }
+        end = offset + 1;
+        column = next_column;
+    }
+
+    match start {
+        Some(start) => (start, end),
+        None => (end, end),
+    }
+}
+
+/// The offset at which content should be inserted to be displayed at the
+/// specified column, along with the number of spaces needed to pad lines
+/// that end before it. Content can't be inserted within a wide character
+/// or tab, so it's placed after one that covers the column.
+pub fn insertion_point(line: &str, target_column: usize, tab_width: usize) -> (usize, usize) {
+    let mut column = 0;
+    for (offset, grapheme) in line.graphemes(true).enumerate() {
+        if column >= target_column {
+            return (offset, 0);
+        }
+        column += grapheme_width(grapheme, column, tab_width);
+    }
+
+    (line.graphemes(true).count(), target_column.saturating_sub(column))
+}
+
+fn grapheme_width(grapheme: &str, column: usize, tab_width: usize) -> usize {
+    if grapheme == "\t" {
+        tab_width - column % tab_width
+    } else {
+        width(grapheme)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn column_accounts_for_tabs_and_wide_characters() {
+        assert_eq!(super::column("a\tb", 2, 4), 4);
+        assert_eq!(super::column("日本語", 2, 4), 4);
+        assert_eq!(super::column("amp", 5, 4), 5);
+        assert_eq!(super::width_at("\tb", 0, 4), 4);
+        assert_eq!(super::width_at("日本", 1, 4), 2);
+    }
+
+    #[test]
+    fn span_includes_graphemes_partially_within_the_columns() {
+        assert_eq!(super::span("amp editor", 2, 5, 4), (2, 5));
+        assert_eq!(super::span("日本語", 1, 3, 4), (0, 2));
+        assert_eq!(super::span("\tamp", 2, 5, 4), (0, 2));
+        assert_eq!(super::span("amp", 5, 7, 4), (3, 3));
+    }
+
+    #[test]
+    fn insertion_point_pads_short_lines() {
+        assert_eq!(super::insertion_point("amp", 1, 4), (1, 0));
+        assert_eq!(super::insertion_point("日本", 1, 4), (1, 0));
+        assert_eq!(super::insertion_point("amp", 5, 4), (3, 2));
+    }
+}
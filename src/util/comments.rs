@@ -0,0 +1,195 @@
+use syntect::parsing::{Scope, SyntaxDefinition};
+use syntect::parsing::syntax_definition::{ContextPtr, ContextReference, MatchOperation, MatchPattern, Pattern};
+
+const PUNCTUATION_SCOPE: &str = "punctuation.definition.comment";
+const LINE_COMMENT_SCOPE: &str = "comment.line";
+const BLOCK_COMMENT_SCOPE: &str = "comment.block";
+
+// A pattern that starts a comment, along with the scopes it applies
+// and, if it pushes a context that ends with one, the closing token.
+struct CommentPattern {
+    start: String,
+    end: Option<String>,
+    scopes: Vec<Scope>,
+}
+
+/// The token that starts a line comment in the syntax. syntect doesn't
+/// expose TextMate comment preferences (e.g. TM_COMMENT_START), so this
+/// is derived from the literal patterns the syntax uses to match comments.
+pub fn line_comment_prefix(syntax: &SyntaxDefinition) -> Option<String> {
+    let line_scope = Scope::new(LINE_COMMENT_SCOPE).ok()?;
+
+    comment_patterns(syntax)
+        .into_iter()
+        .find(|pattern| has_scope(&pattern.scopes, line_scope))
+        .map(|pattern| pattern.start)
+}
+
+/// The tokens that start and end a block comment in the syntax,
+/// derived in the same fashion as its line comment prefix.
+pub fn block_comment_delimiters(syntax: &SyntaxDefinition) -> Option<(String, String)> {
+    let block_scope = Scope::new(BLOCK_COMMENT_SCOPE).ok()?;
+
+    comment_patterns(syntax)
+        .into_iter()
+        .filter(|pattern| has_scope(&pattern.scopes, block_scope))
+        .find_map(|pattern| {
+            let CommentPattern { start, end, .. } = pattern;
+            end.map(|end| (start, end))
+        })
+}
+
+// Patterns whose match is scoped as comment punctuation and is a literal
+// token, visiting contexts in name order so that the results are stable.
+fn comment_patterns(syntax: &SyntaxDefinition) -> Vec<CommentPattern> {
+    let punctuation = match Scope::new(PUNCTUATION_SCOPE) {
+        Ok(scope) => scope,
+        Err(_) => return Vec::new(),
+    };
+    let mut names: Vec<&String> = syntax.contexts.keys().collect();
+    names.sort();
+
+    let mut patterns = Vec::new();
+    for name in names {
+        let context = syntax.contexts[name].borrow();
+        for pattern in context.patterns.iter() {
+            let pattern = match *pattern {
+                Pattern::Match(ref pattern) => pattern,
+                Pattern::Include(_) => continue,
+            };
+            let start = match literal_token(pattern, punctuation) {
+                Some(start) => start,
+                None => continue,
+            };
+
+            let pushed = pushed_contexts(syntax, &pattern.operation);
+            let mut scopes = pattern.scope.clone();
+            for (_, captured_scopes) in pattern.captures.iter().flatten() {
+                scopes.extend(captured_scopes.iter().cloned());
+            }
+            for context in pushed.iter() {
+                let context = context.borrow();
+                scopes.extend(context.meta_scope.iter().cloned());
+                scopes.extend(context.meta_content_scope.iter().cloned());
+            }
+            let end = pushed.iter().find_map(|context| {
+                context.borrow().patterns.iter().find_map(|pattern| match *pattern {
+                    Pattern::Match(ref pattern) if pattern.operation == MatchOperation::Pop => {
+                        literal(&pattern.regex_str)
+                    },
+                    _ => None,
+                })
+            });
+
+            patterns.push(CommentPattern { start, end, scopes });
+        }
+    }
+
+    patterns
+}
+
+// The literal text matched by a pattern scoped as comment punctuation, or by
+// its leading capture group, if that's what's been scoped (e.g. "(#).*$").
+fn literal_token(pattern: &MatchPattern, punctuation: Scope) -> Option<String> {
+    if has_scope(&pattern.scope, punctuation) {
+        return literal(&pattern.regex_str);
+    }
+
+    let captured = pattern.captures.iter().flatten().any(|(group, scopes)| {
+        *group == 1 && has_scope(scopes, punctuation)
+    });
+    if !captured || !pattern.regex_str.starts_with('(') {
+        return None;
+    }
+    let group = &pattern.regex_str[1..];
+    let end = group.find(')')?;
+
+    literal(&group[..end])
+}
+
+// The shortest text matched by a regular expression, provided it's made up
+// of literal characters, anchors, plain groups, and optional elements (which
+// are omitted), so that e.g. "(-*)--\s*>" produces "-->".
+fn literal(regex: &str) -> Option<String> {
+    let mut text = String::new();
+    let mut characters = regex.chars().peekable();
+    while let Some(character) = characters.next() {
+        let element = match character {
+            '(' if characters.peek() == Some(&'?') => return None,
+            '^' | '$' | '(' | ')' => continue,
+            '\\' => {
+                let escaped = characters.next()?;
+                if escaped.is_alphanumeric() { None } else { Some(escaped) }
+            },
+            '.' => None,
+            '[' | ']' | '{' | '}' | '|' | '*' | '+' | '?' => return None,
+            _ => Some(character),
+        };
+
+        match characters.peek() {
+            Some('*') | Some('?') => { characters.next(); },
+            _ => text.push(element?),
+        }
+    }
+
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+// The contexts pushed (or set) by a pattern's operation.
+fn pushed_contexts(syntax: &SyntaxDefinition, operation: &MatchOperation) -> Vec<ContextPtr> {
+    let references = match *operation {
+        MatchOperation::Push(ref references) | MatchOperation::Set(ref references) => references,
+        _ => return Vec::new(),
+    };
+
+    references
+        .iter()
+        .filter_map(|reference| match *reference {
+            ContextReference::Inline(ref context) => Some(context.clone()),
+            ContextReference::Direct(ref context) => context.link.upgrade(),
+            ContextReference::Named(ref name) => syntax.contexts.get(name).cloned(),
+            _ => None,
+        })
+        .collect()
+}
+
+fn has_scope(scopes: &[Scope], prefix: Scope) -> bool {
+    scopes.iter().any(|scope| prefix.is_prefix_of(*scope))
+}
+
+#[cfg(test)]
+mod tests {
+    use syntect::parsing::SyntaxSet;
+
+    #[test]
+    fn comment_tokens_are_derived_from_syntax_definitions() {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let syntax = |name| syntax_set.find_syntax_by_name(name).unwrap();
+
+        assert_eq!(super::line_comment_prefix(syntax("Rust")), Some("//".to_string()));
+        assert_eq!(super::line_comment_prefix(syntax("Ruby")), Some("#".to_string()));
+        assert_eq!(super::line_comment_prefix(syntax("CSS")), None);
+        assert_eq!(
+            super::block_comment_delimiters(syntax("CSS")),
+            Some(("/*".to_string(), "*/".to_string()))
+        );
+        assert_eq!(
+            super::block_comment_delimiters(syntax("HTML")),
+            Some(("<!--".to_string(), "-->".to_string()))
+        );
+    }
+
+    #[test]
+    fn literal_omits_optional_elements_and_rejects_others() {
+        assert_eq!(super::literal(r"/\*"), Some("/*".to_string()));
+        assert_eq!(super::literal(r"\#"), Some("#".to_string()));
+        assert_eq!(super::literal(r"//[!/]"), None);
+        assert_eq!(super::literal(r"\s*#"), Some("#".to_string()));
+        assert_eq!(super::literal(r"(-*)--\s*>"), Some("-->".to_string()));
+        assert_eq!(super::literal(r"\s+#"), None);
+    }
+}
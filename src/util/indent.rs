@@ -0,0 +1,97 @@
+use crate::errors::*;
+use regex::Regex;
+
+/// Patterns used to adjust indentation as code is typed: lines matching the
+/// increase pattern indent those following them, and lines matching the
+/// decrease pattern (e.g. a closing brace) are outdented as they're typed.
+#[derive(Default)]
+pub struct IndentRules {
+    increase: Option<Regex>,
+    decrease: Option<Regex>,
+}
+
+impl IndentRules {
+    pub fn new(increase: Option<&str>, decrease: Option<&str>) -> Result<IndentRules> {
+        let increase = match increase {
+            Some(pattern) => Some(
+                Regex::new(pattern).chain_err(|| "Invalid increase indent pattern")?
+            ),
+            None => None,
+        };
+        let decrease = match decrease {
+            Some(pattern) => Some(
+                Regex::new(pattern).chain_err(|| "Invalid decrease indent pattern")?
+            ),
+            None => None,
+        };
+
+        Ok(IndentRules { increase, decrease })
+    }
+
+    /// Whether lines following the specified one should be indented further.
+    pub fn increases_after(&self, line: &str) -> bool {
+        self.increase.iter().any(|pattern| pattern.is_match(line))
+    }
+
+    /// Whether the specified line closes a block, and should be outdented.
+    pub fn decreases(&self, line: &str) -> bool {
+        self.decrease.iter().any(|pattern| pattern.is_match(line))
+    }
+
+    /// The indentation for a line following the specified one.
+    pub fn indentation_after(&self, line: &str, tab_content: &str) -> String {
+        let mut indentation = leading_whitespace(line).to_string();
+        if self.increases_after(line) {
+            indentation.push_str(tab_content);
+        }
+
+        indentation
+    }
+
+    /// The indentation of a line closing a block, given the line preceding it.
+    /// Lines that have been indented differently than they would have been
+    /// when created are left as-is, so as not to override manual changes.
+    pub fn closing_indentation(&self, line: &str, previous_line: &str, tab_content: &str) -> Option<String> {
+        let expected = self.indentation_after(previous_line, tab_content);
+        if leading_whitespace(line) != expected {
+            return None;
+        }
+
+        expected.strip_suffix(tab_content).map(String::from)
+    }
+}
+
+pub fn leading_whitespace(line: &str) -> &str {
+    let length = line.len() - line.trim_start().len();
+
+    &line[..length]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IndentRules;
+
+    fn rules() -> IndentRules {
+        IndentRules::new(Some(r"[\{\(]\s*$"), Some(r"^\s*[\}\)]")).unwrap()
+    }
+
+    #[test]
+    fn indentation_after_increases_following_block_openers() {
+        assert_eq!(rules().indentation_after("  fn amp() {", "  "), "    ");
+        assert_eq!(rules().indentation_after("  amp();", "  "), "  ");
+        assert_eq!(IndentRules::default().indentation_after("  fn amp() {", "  "), "  ");
+    }
+
+    #[test]
+    fn closing_indentation_outdents_lines_with_their_expected_indentation() {
+        assert_eq!(rules().closing_indentation("    }", "  fn amp() {", "  "), Some("  ".to_string()));
+        assert_eq!(rules().closing_indentation("    }", "    amp();", "  "), Some("  ".to_string()));
+        assert_eq!(rules().closing_indentation("  }", "    amp();", "  "), None);
+        assert_eq!(rules().closing_indentation("}", "amp();", "  "), None);
+    }
+
+    #[test]
+    fn new_rejects_invalid_patterns() {
+        assert!(IndentRules::new(Some("("), None).is_err());
+    }
+}
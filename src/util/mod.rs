@@ -1,8 +1,16 @@
 pub use self::selectable_vec::SelectableVec;
 
+pub mod align;
+pub mod brackets;
+pub mod case;
+pub mod columns;
+pub mod comments;
+pub mod indent;
 pub mod movement_lexer;
+pub mod number;
 mod selectable_vec;
 pub mod reflow;
+pub mod text_object;
 pub mod token;
 
 use crate::errors::*;
@@ -0,0 +1,136 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A decimal, hexadecimal (0x) or binary (0b) literal found on a line,
+/// with its start and end offsets, which are in graphemes.
+#[derive(Debug, PartialEq)]
+pub struct NumberLiteral {
+    pub start: usize,
+    pub end: usize,
+    prefix: String,
+    digits: String,
+    radix: u32,
+    negative: bool,
+}
+
+impl NumberLiteral {
+    /// The literal's content after adding the specified amount, keeping its
+    /// prefix and width. Decimals are only zero-padded if they already were,
+    /// and hexadecimal and binary literals can't be decremented below zero.
+    /// Literals (or results) too large to be represented are left as-is.
+    pub fn add(&self, amount: i128) -> Option<String> {
+        let magnitude = i128::from_str_radix(&self.digits, self.radix).ok()?;
+        let value = if self.negative { -magnitude } else { magnitude };
+        let result = value.checked_add(amount)?;
+        let width = self.digits.len();
+
+        let content = match self.radix {
+            16 => {
+                let digits = format!("{:0width$x}", result.max(0), width = width);
+                if self.digits.chars().any(|c| c.is_ascii_uppercase()) {
+                    format!("{}{}", self.prefix, digits.to_uppercase())
+                } else {
+                    format!("{}{}", self.prefix, digits)
+                }
+            },
+            2 => format!("{}{:0width$b}", self.prefix, result.max(0), width = width),
+            _ => {
+                let sign = if result < 0 { "-" } else { "" };
+                if width > 1 && self.digits.starts_with('0') {
+                    format!("{}{:0width$}", sign, result.unsigned_abs(), width = width)
+                } else {
+                    format!("{}{}", sign, result.unsigned_abs())
+                }
+            },
+        };
+
+        Some(content)
+    }
+}
+
+/// The nearest number literal on the line that includes, or
+/// follows, the specified offset (which is in graphemes).
+pub fn find(line: &str, offset: usize) -> Option<NumberLiteral> {
+    let graphemes: Vec<&str> = line.graphemes(true).collect();
+    let is_digit = |index: usize, radix: u32| {
+        graphemes.get(index).iter().any(|grapheme| {
+            let mut characters = grapheme.chars();
+            match (characters.next(), characters.next()) {
+                (Some(c), None) => c.is_digit(radix),
+                _ => false,
+            }
+        })
+    };
+
+    let mut index = 0;
+    while index < graphemes.len() {
+        if !is_digit(index, 10) {
+            index += 1;
+            continue;
+        }
+
+        // Determine the literal's radix from its prefix, if any.
+        let (radix, digits_start) = match graphemes.get(index + 1) {
+            Some(&"x") | Some(&"X") if graphemes[index] == "0" && is_digit(index + 2, 16) => (16, index + 2),
+            Some(&"b") | Some(&"B") if graphemes[index] == "0" && is_digit(index + 2, 2) => (2, index + 2),
+            _ => (10, index),
+        };
+        let mut end = digits_start;
+        while is_digit(end, radix) {
+            end += 1;
+        }
+
+        // Decimals preceded by a minus sign (that isn't part of a
+        // word or expression, like "a-1") are negative.
+        let negative = radix == 10 &&
+            index > 0 &&
+            graphemes[index - 1] == "-" &&
+            (index < 2 || !graphemes[index - 2].chars().all(|c| c.is_alphanumeric()));
+        let start = if negative { index - 1 } else { index };
+
+        if end > offset {
+            return Some(NumberLiteral {
+                start,
+                end,
+                prefix: graphemes[index..digits_start].concat(),
+                digits: graphemes[digits_start..end].concat(),
+                radix,
+                negative,
+            });
+        }
+        index = end;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn find_returns_the_literal_at_or_after_the_offset() {
+        let literal = super::find("amp 12 34", 5).unwrap();
+        assert_eq!((literal.start, literal.end), (4, 6));
+
+        let literal = super::find("amp 12 34", 6).unwrap();
+        assert_eq!((literal.start, literal.end), (7, 9));
+        assert!(super::find("amp 12", 6).is_none());
+    }
+
+    #[test]
+    fn add_preserves_width_and_padding() {
+        assert_eq!(super::find("007", 0).unwrap().add(3).unwrap(), "010");
+        assert_eq!(super::find("99", 0).unwrap().add(1).unwrap(), "100");
+        assert_eq!(super::find("x = -1", 0).unwrap().add(3).unwrap(), "2");
+        assert_eq!(super::find("a-1", 0).unwrap().add(-2).unwrap(), "-1");
+        assert_eq!(super::find("0x0F", 0).unwrap().add(1).unwrap(), "0x10");
+        assert_eq!(super::find("0xff", 0).unwrap().add(-0x10).unwrap(), "0xef");
+        assert_eq!(super::find("0b0011", 0).unwrap().add(1).unwrap(), "0b0100");
+        assert_eq!(super::find("0b1", 0).unwrap().add(-2).unwrap(), "0b0");
+    }
+
+    #[test]
+    fn add_rejects_literals_and_results_that_overflow() {
+        assert_eq!(super::find("1000000000000000000000000000000000000000", 0).unwrap().add(1), None);
+        assert_eq!(super::find(&i128::MAX.to_string(), 0).unwrap().add(1), None);
+        assert_eq!(super::find(&format!("-{}", i128::MAX), 0).unwrap().add(-1).unwrap(), i128::MIN.to_string());
+    }
+}
@@ -0,0 +1,312 @@
+use regex::Regex;
+use scribe::buffer::{Position, Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The range between the closest pair of brackets enclosing the position,
+/// skipping over nested pairs. The brackets themselves are only included
+/// when selecting around them. A position on either bracket is enclosed.
+pub fn enclosing_pair(data: &str, position: Position, open: &str, close: &str, around: bool) -> Option<Range> {
+    let graphemes = positioned_graphemes(data);
+    let index = graphemes
+        .iter()
+        .position(|(grapheme_position, _)| *grapheme_position >= position)?;
+
+    let mut depth = 0;
+    let start = (0..=index).rev().find(|&i| {
+        let grapheme = graphemes[i].1;
+        if grapheme == close && i != index {
+            depth += 1;
+        } else if grapheme == open {
+            if depth == 0 {
+                return true;
+            }
+            depth -= 1;
+        }
+
+        false
+    })?;
+
+    let mut depth = 0;
+    let end = (start + 1..graphemes.len()).find(|&i| {
+        let grapheme = graphemes[i].1;
+        if grapheme == open {
+            depth += 1;
+        } else if grapheme == close {
+            if depth == 0 {
+                return true;
+            }
+            depth -= 1;
+        }
+
+        false
+    })?;
+
+    Some(pair_range(graphemes[start].0, graphemes[end].0, around))
+}
+
+/// The range between the pair of quotes on the position's line that
+/// enclose it, or failing that, the next pair following it. Quotes
+/// escaped with a backslash are ignored.
+pub fn quoted(data: &str, position: Position, quote: &str, around: bool) -> Option<Range> {
+    let line = data.lines().nth(position.line)?;
+    let graphemes: Vec<&str> = line.graphemes(true).collect();
+    let quotes: Vec<usize> = (0..graphemes.len())
+        .filter(|&i| graphemes[i] == quote && (i == 0 || graphemes[i - 1] != "\\"))
+        .collect();
+
+    let (start, end) = quotes
+        .chunks(2)
+        .filter_map(|pair| match *pair {
+            [start, end] => Some((start, end)),
+            _ => None,
+        })
+        .find(|&(_, end)| position.offset <= end)?;
+
+    Some(pair_range(
+        Position{ line: position.line, offset: start },
+        Position{ line: position.line, offset: end },
+        around
+    ))
+}
+
+/// The range of whole lines in the paragraph that includes the position, where
+/// paragraphs are separated by blank lines (a run of which is also treated as
+/// a paragraph). Selecting around a paragraph includes the blank lines that
+/// follow it, or those preceding it, if it's the last in the buffer.
+pub fn paragraph(data: &str, position: Position, around: bool) -> Option<Range> {
+    let lines: Vec<&str> = data.lines().collect();
+    let is_blank = |line: usize| lines[line].trim().is_empty();
+    if position.line >= lines.len() {
+        return None;
+    }
+
+    let blank = is_blank(position.line);
+    let mut start = (0..position.line)
+        .rev()
+        .take_while(|&line| is_blank(line) == blank)
+        .last()
+        .unwrap_or(position.line);
+    let mut end = (position.line..lines.len())
+        .take_while(|&line| is_blank(line) == blank)
+        .last()
+        .unwrap_or(position.line);
+
+    if around && !blank {
+        let trailing_end = (end + 1..lines.len()).take_while(|&line| is_blank(line)).last();
+        match trailing_end {
+            Some(line) => end = line,
+            None => {
+                start = (0..start)
+                    .rev()
+                    .take_while(|&line| is_blank(line))
+                    .last()
+                    .unwrap_or(start)
+            }
+        }
+    }
+
+    // Include the last line's newline, if there is one.
+    let end_position = if data.split('\n').count() > end + 1 {
+        Position{ line: end + 1, offset: 0 }
+    } else {
+        Position{ line: end, offset: lines[end].graphemes(true).count() }
+    };
+
+    Some(Range::new(Position{ line: start, offset: 0 }, end_position))
+}
+
+/// The range of the token (a run of alphanumeric characters and underscores)
+/// that includes the position. Selecting around a token includes the
+/// whitespace that follows it, or that preceding it, if there's none after.
+pub fn token(data: &str, position: Position, around: bool) -> Option<Range> {
+    let line = data.lines().nth(position.line)?;
+    let graphemes: Vec<&str> = line.graphemes(true).collect();
+    let is_word = |index: &usize| {
+        graphemes[*index].chars().all(|c| c.is_alphanumeric() || c == '_')
+    };
+    let is_whitespace = |index: &usize| graphemes[*index].trim().is_empty();
+
+    if position.offset >= graphemes.len() || !is_word(&position.offset) {
+        return None;
+    }
+
+    let mut start = (0..position.offset)
+        .rev()
+        .take_while(is_word)
+        .last()
+        .unwrap_or(position.offset);
+    let mut end = (position.offset..graphemes.len())
+        .take_while(is_word)
+        .last()
+        .unwrap_or(position.offset) + 1;
+
+    if around {
+        let trailing_whitespace = (end..graphemes.len()).take_while(is_whitespace).count();
+        if trailing_whitespace > 0 {
+            end += trailing_whitespace;
+        } else {
+            start -= (0..start).rev().take_while(is_whitespace).count();
+        }
+    }
+
+    Some(Range::new(
+        Position{ line: position.line, offset: start },
+        Position{ line: position.line, offset: end }
+    ))
+}
+
+/// The range between the opening and closing HTML tags of the innermost
+/// element enclosing the position, which is on one of its tags or between
+/// them. Selecting around an element includes the tags themselves.
+pub fn tag(data: &str, position: Position, around: bool) -> Option<Range> {
+    lazy_static! {
+        static ref TAG: Regex = Regex::new(r"<(/)?([A-Za-z][\w:.-]*)[^<>]*?(/)?>").unwrap();
+    }
+    let index = byte_index(data, position)?;
+
+    // Inner elements are closed before the outer ones
+    // that enclose them, so the first match is innermost.
+    let mut open_tags: Vec<(&str, usize, usize)> = Vec::new();
+    for captures in TAG.captures_iter(data) {
+        let (tag_start, tag_end) = captures.pos(0)?;
+        let name = captures.at(2)?;
+        if captures.at(3).is_some() {
+            continue;
+        }
+
+        if captures.at(1).is_none() {
+            open_tags.push((name, tag_start, tag_end));
+        } else if let Some(open_index) = open_tags.iter().rposition(|(open_name, _, _)| *open_name == name) {
+            let (_, start, inner_start) = open_tags[open_index];
+            open_tags.truncate(open_index);
+
+            if start <= index && index < tag_end {
+                return Some(if around {
+                    Range::new(position_at(data, start), position_at(data, tag_end))
+                } else {
+                    Range::new(position_at(data, inner_start), position_at(data, tag_start))
+                });
+            }
+        }
+    }
+
+    None
+}
+
+// The byte index of the position in the data, if it's within its lines.
+fn byte_index(data: &str, position: Position) -> Option<usize> {
+    let line_start: usize = data
+        .split('\n')
+        .take(position.line)
+        .map(|line| line.len() + 1)
+        .sum();
+    let line = data[line_start.min(data.len())..].split('\n').next()?;
+    let offset: usize = line.graphemes(true).take(position.offset).map(str::len).sum();
+
+    Some(line_start + offset)
+}
+
+// The position of the byte index in the data.
+fn position_at(data: &str, index: usize) -> Position {
+    let preceding = &data[..index];
+    let line_start = preceding.rfind('\n').map(|i| i + 1).unwrap_or(0);
+
+    Position{
+        line: preceding.matches('\n').count(),
+        offset: preceding[line_start..].graphemes(true).count(),
+    }
+}
+
+// Every grapheme in the data, along with its position.
+fn positioned_graphemes(data: &str) -> Vec<(Position, &str)> {
+    data.split('\n')
+        .enumerate()
+        .flat_map(|(line, content)| {
+            content
+                .graphemes(true)
+                .chain(Some("\n"))
+                .enumerate()
+                .map(move |(offset, grapheme)| (Position{ line, offset }, grapheme))
+        })
+        .collect()
+}
+
+// The range between (or around) the single-grapheme delimiters at each position.
+fn pair_range(start: Position, end: Position, around: bool) -> Range {
+    if around {
+        Range::new(start, Position{ line: end.line, offset: end.offset + 1 })
+    } else {
+        Range::new(Position{ line: start.line, offset: start.offset + 1 }, end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use scribe::buffer::{Position, Range};
+
+    fn range(start: (usize, usize), end: (usize, usize)) -> Option<Range> {
+        Some(Range::new(
+            Position{ line: start.0, offset: start.1 },
+            Position{ line: end.0, offset: end.1 }
+        ))
+    }
+
+    #[test]
+    fn enclosing_pair_skips_nested_pairs() {
+        let data = "amp(editor(1), (2)\n  scribe)";
+        let position = Position{ line: 0, offset: 14 };
+
+        assert_eq!(super::enclosing_pair(data, position, "(", ")", false), range((0, 4), (1, 8)));
+        assert_eq!(super::enclosing_pair(data, position, "(", ")", true), range((0, 3), (1, 9)));
+    }
+
+    #[test]
+    fn enclosing_pair_includes_positions_on_either_bracket() {
+        let data = "[amp] [editor]";
+
+        assert_eq!(super::enclosing_pair(data, Position{ line: 0, offset: 6 }, "[", "]", false), range((0, 7), (0, 13)));
+        assert_eq!(super::enclosing_pair(data, Position{ line: 0, offset: 13 }, "[", "]", false), range((0, 7), (0, 13)));
+        assert_eq!(super::enclosing_pair(data, Position{ line: 0, offset: 5 }, "[", "]", false), None);
+    }
+
+    #[test]
+    fn quoted_finds_enclosing_or_following_quotes() {
+        let data = "let a = \"amp \\\"editor\\\"\"; \"scribe\"";
+
+        assert_eq!(super::quoted(data, Position{ line: 0, offset: 10 }, "\"", false), range((0, 9), (0, 23)));
+        assert_eq!(super::quoted(data, Position{ line: 0, offset: 0 }, "\"", true), range((0, 8), (0, 24)));
+        assert_eq!(super::quoted(data, Position{ line: 0, offset: 25 }, "\"", false), range((0, 27), (0, 33)));
+        assert_eq!(super::quoted(data, Position{ line: 0, offset: 0 }, "'", false), None);
+    }
+
+    #[test]
+    fn paragraph_selects_whole_lines_between_blank_lines() {
+        let data = "amp\n\neditor\nscribe\n\n\nluthor";
+
+        assert_eq!(super::paragraph(data, Position{ line: 3, offset: 2 }, false), range((2, 0), (4, 0)));
+        assert_eq!(super::paragraph(data, Position{ line: 3, offset: 2 }, true), range((2, 0), (6, 0)));
+        assert_eq!(super::paragraph(data, Position{ line: 4, offset: 0 }, false), range((4, 0), (6, 0)));
+        assert_eq!(super::paragraph(data, Position{ line: 6, offset: 0 }, true), range((4, 0), (6, 6)));
+    }
+
+    #[test]
+    fn token_includes_surrounding_whitespace_when_selecting_around_it() {
+        let data = "amp editor_1  ";
+
+        assert_eq!(super::token(data, Position{ line: 0, offset: 6 }, false), range((0, 4), (0, 12)));
+        assert_eq!(super::token(data, Position{ line: 0, offset: 6 }, true), range((0, 4), (0, 14)));
+        assert_eq!(super::token("amp editor", Position{ line: 0, offset: 6 }, true), range((0, 3), (0, 10)));
+        assert_eq!(super::token(data, Position{ line: 0, offset: 3 }, false), None);
+    }
+
+    #[test]
+    fn tag_selects_innermost_enclosing_element() {
+        let data = "<div class=\"amp\">\n  <p>editor<br/></p>\n</div>";
+
+        assert_eq!(super::tag(data, Position{ line: 1, offset: 6 }, false), range((1, 5), (1, 16)));
+        assert_eq!(super::tag(data, Position{ line: 1, offset: 6 }, true), range((1, 2), (1, 20)));
+        assert_eq!(super::tag(data, Position{ line: 1, offset: 1 }, false), range((0, 17), (2, 0)));
+        assert_eq!(super::tag(data, Position{ line: 0, offset: 2 }, true), range((0, 0), (2, 6)));
+        assert_eq!(super::tag("amp <p>", Position{ line: 0, offset: 1 }, false), None);
+    }
+}
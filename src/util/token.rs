@@ -1,7 +1,8 @@
 
 use crate::util::movement_lexer;
-use scribe::buffer::{Buffer, Position};
+use scribe::buffer::{Buffer, Position, Range};
 use luthor::token::Category;
+use unicode_segmentation::UnicodeSegmentation;
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum Direction {
@@ -81,3 +82,90 @@ pub fn adjacent_token_position(buffer: &mut Buffer,
 
     None
 }
+
+/// Identifiers in the specified data: runs of alphanumeric characters
+/// and underscores that don't start with a digit, in order of appearance.
+pub fn identifiers(data: &str) -> Vec<&str> {
+    data.split(|c: char| !is_identifier_char(c))
+        .filter(|word| !word.is_empty() && !word.starts_with(|c: char| c.is_ascii_digit()))
+        .collect()
+}
+
+/// The identifier characters immediately preceding the specified
+/// offset (in graphemes) on a line, e.g. a partially typed identifier.
+pub fn identifier_prefix(line: &str, offset: usize) -> String {
+    let graphemes: Vec<&str> = line.graphemes(true).take(offset).collect();
+    let length = graphemes
+        .iter()
+        .rev()
+        .take_while(|grapheme| grapheme.chars().all(is_identifier_char))
+        .count();
+
+    graphemes[graphemes.len() - length..].concat()
+}
+
+/// The range of the identifier (a run of alphanumeric characters
+/// and underscores) that includes the specified position, if any.
+pub fn identifier_at(data: &str, position: Position) -> Option<Range> {
+    let line = data.lines().nth(position.line)?;
+    let graphemes: Vec<&str> = line.graphemes(true).collect();
+    let is_identifier = |grapheme: &&str| grapheme.chars().all(is_identifier_char);
+    if !graphemes.get(position.offset).is_some_and(is_identifier) {
+        return None;
+    }
+
+    let start = position.offset - graphemes[..position.offset]
+        .iter()
+        .rev()
+        .take_while(|grapheme| is_identifier(grapheme))
+        .count();
+    let end = position.offset + graphemes[position.offset..]
+        .iter()
+        .take_while(|grapheme| is_identifier(grapheme))
+        .count();
+
+    Some(Range::new(
+        Position{ line: position.line, offset: start },
+        Position{ line: position.line, offset: end }
+    ))
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use scribe::buffer::{Position, Range};
+
+    #[test]
+    fn identifiers_skips_punctuation_and_numbers() {
+        assert_eq!(
+            super::identifiers("let amp_editor = scribe::new(2, 3rd);"),
+            vec!["let", "amp_editor", "scribe", "new"]
+        );
+    }
+
+    #[test]
+    fn identifier_prefix_returns_identifier_characters_before_the_offset() {
+        assert_eq!(super::identifier_prefix("let amp_ed = 1;", 10), "amp_ed");
+        assert_eq!(super::identifier_prefix("let amp_ed = 1;", 7), "amp");
+        assert_eq!(super::identifier_prefix("amp(", 4), "");
+    }
+
+    #[test]
+    fn identifier_at_returns_the_identifier_including_the_position() {
+        let data = "let amp_editor = 1;\nscribe";
+
+        assert_eq!(
+            super::identifier_at(data, Position{ line: 0, offset: 8 }),
+            Some(Range::new(Position{ line: 0, offset: 4 }, Position{ line: 0, offset: 14 }))
+        );
+        assert_eq!(
+            super::identifier_at(data, Position{ line: 1, offset: 0 }),
+            Some(Range::new(Position{ line: 1, offset: 0 }, Position{ line: 1, offset: 6 }))
+        );
+        assert_eq!(super::identifier_at(data, Position{ line: 0, offset: 15 }), None);
+        assert_eq!(super::identifier_at(data, Position{ line: 1, offset: 6 }), None);
+    }
+}
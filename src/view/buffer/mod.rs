@@ -8,7 +8,7 @@ mod scrollable_region;
 
 pub use self::renderer::BufferRenderer;
 pub use self::render_cache::RenderCache;
-pub use self::render_state::RenderState;
+pub use self::render_state::{RenderState, cached_parse_state};
 pub use self::lexeme_mapper::{LexemeMapper, MappedLexeme};
 pub use self::line_numbers::LineNumbers;
 pub use self::scrollable_region::ScrollableRegion;
\ No newline at end of file
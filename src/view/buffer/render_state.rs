@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use syntect::highlighting::{Highlighter, HighlightState};
 use syntect::parsing::{ParseState, ScopeStack, SyntaxDefinition};
 
@@ -15,3 +16,13 @@ impl RenderState {
         }
     }
 }
+
+/// The parse state and scopes cached for the closest line at or before the
+/// specified one, if any, from which parsing the rest of the buffer can resume.
+pub fn cached_parse_state(cache: &HashMap<usize, RenderState>, line: usize) -> Option<(usize, ParseState, ScopeStack)> {
+    cache
+        .iter()
+        .filter(|(cached_line, _)| **cached_line <= line)
+        .max_by_key(|(cached_line, _)| **cached_line)
+        .map(|(cached_line, state)| (*cached_line, state.parse.clone(), state.highlight.path.clone()))
+}
@@ -1,5 +1,7 @@
 
 use crate::models::application::Preferences;
+use crate::util::brackets::{self, BracketIndex};
+use crate::util::columns;
 use scribe::buffer::{Buffer, Position, Range};
 use scribe::util::LineIterator;
 use crate::view::buffer::{LexemeMapper, MappedLexeme, RenderState};
@@ -21,15 +23,18 @@ use crate::errors::*;
 /// A one-time-use type that encapsulates all of the
 /// details involved in rendering a buffer to the screen.
 pub struct BufferRenderer<'a, 'p> {
+    brackets: BracketIndex,
     buffer: &'a Buffer,
     buffer_position: Position,
     cursor_position: Option<Position>,
+    secondary_cursors: Vec<Position>,
     gutter_width: usize,
     highlights: Option<&'a [Range]>,
     stylist: Highlighter<'a>,
     current_style: ThemeStyle,
     line_numbers: LineNumbers,
     preferences: &'a Preferences,
+    printed_brackets: Vec<(Position, Position, &'static str, Colors)>,
     render_cache: &'a Rc<RefCell<HashMap<usize, RenderState>>>,
     screen_position: Position,
     scroll_offset: usize,
@@ -40,7 +45,7 @@ pub struct BufferRenderer<'a, 'p> {
 
 impl<'a, 'p> BufferRenderer<'a, 'p> {
     pub fn new(buffer: &'a Buffer, highlights: Option<&'a [Range]>,
-    scroll_offset: usize, terminal: &'a dyn Terminal, theme: &'a Theme,
+    secondary_cursors: &'a [Position], scroll_offset: usize, terminal: &'a dyn Terminal, theme: &'a Theme,
     preferences: &'a Preferences,
     render_cache: &'a Rc<RefCell<HashMap<usize, RenderState>>>,
     terminal_buffer: &'a mut TerminalBuffer<'p>) -> BufferRenderer<'a, 'p> {
@@ -52,9 +57,15 @@ impl<'a, 'p> BufferRenderer<'a, 'p> {
         let stylist = Highlighter::new(theme);
         let current_style = stylist.get_default();
 
+        // Sorted, so that they can be looked up efficiently for every cell.
+        let mut secondary_cursors = secondary_cursors.to_vec();
+        secondary_cursors.sort_by_key(|position| (position.line, position.offset));
+
         BufferRenderer{
+            brackets: BracketIndex::new(),
             buffer,
             cursor_position: None,
+            secondary_cursors,
             gutter_width,
             highlights,
             stylist,
@@ -62,6 +73,7 @@ impl<'a, 'p> BufferRenderer<'a, 'p> {
             line_numbers,
             buffer_position: Position{ line: 0, offset: 0 },
             preferences,
+            printed_brackets: Vec::new(),
             render_cache,
             screen_position: Position{ line: 0, offset: 0 },
             scroll_offset,
@@ -75,19 +87,39 @@ impl<'a, 'p> BufferRenderer<'a, 'p> {
         self.buffer_position.line == self.buffer.cursor.line
     }
 
+    fn on_secondary_cursor(&self) -> bool {
+        let position = (self.buffer_position.line, self.buffer_position.offset);
+        self.secondary_cursors
+            .binary_search_by_key(&position, |cursor| (cursor.line, cursor.offset))
+            .is_ok()
+    }
+
     fn print_rest_of_line(&mut self) {
         let on_cursor_line = self.on_cursor_line();
         let guide_offset = self.length_guide_offset();
 
+        // Secondary cursors at the end of the line have no
+        // character to highlight, so draw them on the padding.
+        let secondary_cursor_offset = if self.on_secondary_cursor() {
+            Some(self.screen_position.offset)
+        } else {
+            None
+        };
+
         for offset in self.screen_position.offset..self.terminal.width() {
             let colors = if on_cursor_line || guide_offset.map(|go| go == offset).unwrap_or(false) {
                 Colors::Focused
             } else {
                 Colors::Default
             };
+            let style = if secondary_cursor_offset == Some(offset) {
+                Style::Inverted
+            } else {
+                Style::Default
+            };
 
             self.print(Position{ line: self.screen_position.line, offset },
-                       Style::Default,
+                       style,
                        colors,
                        " ");
         }
@@ -126,6 +158,10 @@ impl<'a, 'p> BufferRenderer<'a, 'p> {
     }
 
     fn current_char_style(&self, token_color: RGBColor) -> (Style, Colors) {
+        if self.on_secondary_cursor() {
+            return (Style::Inverted, Colors::Default)
+        }
+
         let (style, colors) = match self.highlights {
             Some(highlight_ranges) => {
                 for range in highlight_ranges {
@@ -171,11 +207,16 @@ impl<'a, 'p> BufferRenderer<'a, 'p> {
             let token_color = to_rgb_color(self.current_style.foreground);
             let (style, color) = self.current_char_style(token_color);
 
-            if self.preferences.line_wrapping() && self.screen_position.offset == self.terminal.width() {
+            // Wide characters occupy two columns; the terminal buffer
+            // skips the cell following them when they're presented.
+            let width = columns::width(character);
+
+            if self.preferences.line_wrapping() && self.screen_position.offset + width > self.terminal.width() {
                 self.screen_position.line += 1;
                 self.screen_position.offset = self.gutter_width;
+                self.track_bracket(character, color);
                 self.print(self.screen_position, style, color, character.to_string());
-                self.screen_position.offset += 1;
+                self.screen_position.offset += width;
                 self.buffer_position.offset += 1;
             } else if character == "\t" {
                 // Calculate the next tab stop using the tab-aware offset,
@@ -196,8 +237,9 @@ impl<'a, 'p> BufferRenderer<'a, 'p> {
                 }
                 self.buffer_position.offset += 1;
             } else {
+                self.track_bracket(character, color);
                 self.print(self.screen_position, style, color, character.to_string());
-                self.screen_position.offset += 1;
+                self.screen_position.offset += width;
                 self.buffer_position.offset += 1;
             }
 
@@ -205,6 +247,38 @@ impl<'a, 'p> BufferRenderer<'a, 'p> {
         }
     }
 
+    // Remember where brackets are printed, so that the one
+    // matching the bracket at the cursor can be highlighted.
+    fn track_bracket(&mut self, character: &str, colors: Colors) {
+        if let Some(bracket) = brackets::bracket(character) {
+            self.printed_brackets.push((self.buffer_position, self.screen_position, bracket, colors));
+        }
+    }
+
+    // Reprints the bracket matching the one at the cursor (if both are
+    // visible), which isn't known until everything between them has been
+    // printed. Brackets are only matched using the lines parsed to render
+    // the visible content, so nothing is highlighted for a cursor that's
+    // scrolled out of view.
+    fn highlight_matching_bracket(&mut self) {
+        if self.cursor_position.is_none() || self.printed_brackets.is_empty() {
+            return;
+        }
+
+        let matching_position = match self.brackets.matching(*self.buffer.cursor) {
+            Some(position) => position,
+            None => return,
+        };
+        let printed_bracket = self.printed_brackets
+            .iter()
+            .find(|(position, _, _, _)| *position == matching_position)
+            .cloned();
+
+        if let Some((_, screen_position, bracket, colors)) = printed_bracket {
+            self.print(screen_position, Style::Inverted, colors, bracket);
+        }
+    }
+
     fn before_visible_content(&mut self) -> bool {
         self.buffer_position.line < self.scroll_offset
     }
@@ -240,6 +314,8 @@ impl<'a, 'p> BufferRenderer<'a, 'p> {
                 }
 
                 let events = state.parse.parse_line(line);
+                let mut scopes = state.highlight.path.clone();
+                self.brackets.add_line(line_no, line, &events, &mut scopes);
                 let styled_lexemes = HighlightIterator::new(
                     &mut state.highlight,
                     &events,
@@ -290,6 +366,7 @@ impl<'a, 'p> BufferRenderer<'a, 'p> {
 
         // One last call to this for the last line.
         self.print_rest_of_line();
+        self.highlight_matching_bracket();
 
         // Return the cursor location. If it occurred somewhere in the buffer, it
         // will be shown at the right location. If not, it will be None and will
@@ -396,6 +473,7 @@ mod tests {
     use std::rc::Rc;
     use super::{BufferRenderer, LexemeMapper, MappedLexeme};
     use syntect::highlighting::ThemeSet;
+    use crate::view::Style;
     use crate::view::terminal::*;
     use crate::yaml::yaml::YamlLoader;
 
@@ -419,6 +497,7 @@ mod tests {
         BufferRenderer::new(
             workspace.current_buffer().unwrap(),
             None,
+            &[],
             0,
             &**terminal,
             &theme_set.themes["base16-ocean.dark"],
@@ -450,6 +529,7 @@ mod tests {
         BufferRenderer::new(
             workspace.current_buffer().unwrap(),
             None,
+            &[],
             0,
             &**terminal,
             &theme_set.themes["base16-ocean.dark"],
@@ -488,6 +568,7 @@ mod tests {
         BufferRenderer::new(
             workspace.current_buffer().unwrap(),
             None,
+            &[],
             0,
             &**terminal,
             &theme_set.themes["base16-ocean.dark"],
@@ -523,6 +604,7 @@ mod tests {
         BufferRenderer::new(
             workspace.current_buffer().unwrap(),
             None,
+            &[],
             0,
             &**terminal,
             &theme_set.themes["base16-ocean.dark"],
@@ -565,6 +647,7 @@ mod tests {
         BufferRenderer::new(
             workspace.current_buffer().unwrap(),
             None,
+            &[],
             0,
             &**terminal,
             &theme_set.themes["base16-ocean.dark"],
@@ -599,6 +682,7 @@ mod tests {
         let cursor_position = BufferRenderer::new(
             workspace.current_buffer().unwrap(),
             None,
+            &[],
             0,
             &**terminal,
             &theme_set.themes["base16-ocean.dark"],
@@ -610,6 +694,157 @@ mod tests {
         assert_eq!(cursor_position, Some(Position{ line: 0, offset: 4 }));
     }
 
+    #[test]
+    fn render_advances_two_columns_for_wide_characters() {
+        // Set up a workspace and buffer; the workspace will
+        // handle setting up the buffer's syntax definition.
+        let mut workspace = Workspace::new(Path::new(".")).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("日本amp");
+        buffer.cursor.move_to(Position{ line: 0, offset: 2 });
+        workspace.add_buffer(buffer);
+
+        let data = workspace.current_buffer().unwrap().data();
+        let lines = LineIterator::new(&data);
+        let terminal = build_terminal().unwrap();
+        let mut terminal_buffer = TerminalBuffer::new(terminal.width(), terminal.height());
+        let theme_set = ThemeSet::load_defaults();
+        let preferences = Preferences::new(None);
+
+        let cursor_position = BufferRenderer::new(
+            workspace.current_buffer().unwrap(),
+            None,
+            &[],
+            0,
+            &**terminal,
+            &theme_set.themes["base16-ocean.dark"],
+            &preferences,
+            &Rc::new(RefCell::new(HashMap::new())),
+            &mut terminal_buffer
+        ).render(lines, None).unwrap();
+
+        assert_eq!(cursor_position, Some(Position{ line: 0, offset: 8 }));
+        let expected_content = " 1  日本am\n    p";
+        assert_eq!(
+            &terminal_buffer.content()[0..expected_content.len()],
+            expected_content
+        );
+    }
+
+    #[test]
+    fn render_inverts_secondary_cursor_cells_including_those_at_the_end_of_a_line() {
+        // Set up a workspace and buffer; the workspace will
+        // handle setting up the buffer's syntax definition.
+        let mut workspace = Workspace::new(Path::new(".")).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("amp");
+        workspace.add_buffer(buffer);
+
+        let data = workspace.current_buffer().unwrap().data();
+        let lines = LineIterator::new(&data);
+        let terminal = build_terminal().unwrap();
+        let mut terminal_buffer = TerminalBuffer::new(terminal.width(), terminal.height());
+        let theme_set = ThemeSet::load_defaults();
+        let preferences = Preferences::new(None);
+        let secondary_cursors = [Position{ line: 0, offset: 1 }, Position{ line: 0, offset: 3 }];
+
+        BufferRenderer::new(
+            workspace.current_buffer().unwrap(),
+            None,
+            &secondary_cursors,
+            0,
+            &**terminal,
+            &theme_set.themes["base16-ocean.dark"],
+            &preferences,
+            &Rc::new(RefCell::new(HashMap::new())),
+            &mut terminal_buffer
+        ).render(lines, None).unwrap();
+
+        // Skip past the line number gutter.
+        let inverted_offsets: Vec<usize> = terminal_buffer
+            .iter()
+            .filter(|(position, cell)| position.line == 0 && cell.style == Style::Inverted)
+            .map(|(position, _)| position.offset - 4)
+            .collect();
+        assert_eq!(inverted_offsets, vec![1, 3]);
+    }
+
+    #[test]
+    fn render_inverts_the_bracket_matching_the_one_at_the_cursor() {
+        let mut workspace = Workspace::new(Path::new(".")).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("(a())");
+        buffer.cursor.move_to(Position{ line: 0, offset: 0 });
+        workspace.add_buffer(buffer);
+
+        let data = workspace.current_buffer().unwrap().data();
+        let lines = LineIterator::new(&data);
+        let terminal = build_terminal().unwrap();
+        let mut terminal_buffer = TerminalBuffer::new(terminal.width(), terminal.height());
+        let theme_set = ThemeSet::load_defaults();
+        let preferences = Preferences::new(None);
+
+        BufferRenderer::new(
+            workspace.current_buffer().unwrap(),
+            None,
+            &[],
+            0,
+            &**terminal,
+            &theme_set.themes["base16-ocean.dark"],
+            &preferences,
+            &Rc::new(RefCell::new(HashMap::new())),
+            &mut terminal_buffer
+        ).render(lines, None).unwrap();
+
+        // Skip past the line number gutter.
+        let inverted_offsets: Vec<usize> = terminal_buffer
+            .iter()
+            .filter(|(position, cell)| position.line == 0 && cell.style == Style::Inverted)
+            .map(|(position, _)| position.offset - 4)
+            .collect();
+        assert_eq!(inverted_offsets, vec![4]);
+    }
+
+    #[test]
+    fn render_does_not_invert_brackets_matching_one_at_a_cursor_out_of_view() {
+        let mut workspace = Workspace::new(Path::new(".")).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert(format!("(\n{})\n", "a\n".repeat(150)));
+        buffer.cursor.move_to(Position{ line: 0, offset: 0 });
+        workspace.add_buffer(buffer);
+
+        let data = workspace.current_buffer().unwrap().data();
+        let terminal = build_terminal().unwrap();
+        let theme_set = ThemeSet::load_defaults();
+        let preferences = Preferences::new(None);
+        let render_cache = Rc::new(RefCell::new(HashMap::new()));
+
+        // The second render resumes from cached state past the cursor,
+        // rather than parsing the lines it's on, as the first does.
+        for _ in 0..2 {
+            let mut terminal_buffer = TerminalBuffer::new(terminal.width(), terminal.height());
+            BufferRenderer::new(
+                workspace.current_buffer().unwrap(),
+                None,
+                &[],
+                151,
+                &**terminal,
+                &theme_set.themes["base16-ocean.dark"],
+                &preferences,
+                &render_cache,
+                &mut terminal_buffer
+            ).render(LineIterator::new(&data), None).unwrap();
+
+            let inverted_cells: Vec<String> = terminal_buffer
+                .iter()
+                .filter(|(position, cell)| position.line == 0 && cell.style == Style::Inverted)
+                .map(|(_, cell)| cell.content.to_string())
+                .collect();
+            assert!(inverted_cells.is_empty());
+        }
+        assert!(render_cache.borrow().contains_key(&100));
+    }
+
     #[test]
     fn render_caches_state_using_correct_frequency_excluding_first_line() {
         // Set up a workspace and buffer; the workspace will
@@ -633,6 +868,7 @@ mod tests {
         BufferRenderer::new(
             workspace.current_buffer().unwrap(),
             None,
+            &[],
             495,
             &**terminal,
             &theme_set.themes["base16-ocean.dark"],
@@ -668,6 +904,7 @@ mod tests {
         BufferRenderer::new(
             workspace.current_buffer().unwrap(),
             None,
+            &[],
             95,
             &**terminal,
             &theme_set.themes["base16-ocean.dark"],
@@ -690,6 +927,7 @@ mod tests {
         BufferRenderer::new(
             workspace.current_buffer().unwrap(),
             None,
+            &[],
             495,
             &**terminal,
             &theme_set.themes["base16-ocean.dark"],
@@ -728,6 +966,7 @@ mod tests {
         BufferRenderer::new(
             workspace.current_buffer().unwrap(),
             None,
+            &[],
             95,
             &**terminal,
             &theme_set.themes["base16-ocean.dark"],
@@ -749,6 +988,7 @@ mod tests {
         BufferRenderer::new(
             workspace.current_buffer().unwrap(),
             None,
+            &[],
             200,
             &**terminal,
             &theme_set.themes["base16-ocean.dark"],
@@ -1,7 +1,7 @@
 
 use std::sync::Arc;
 use scribe::buffer::Buffer;
-use unicode_segmentation::UnicodeSegmentation;
+use crate::util::columns;
 use crate::view::buffer::LineNumbers;
 use crate::view::terminal::Terminal;
 
@@ -93,9 +93,9 @@ impl ScrollableRegion {
             .skip(start)
             .take(line_count)
             .map(|line| {
-                let grapheme_count = line.graphemes(true).count().max(1) as f32;
+                let line_width = columns::width(line).max(1) as f32;
                 let buffer_content_width = (self.terminal.width() - gutter_width) as f32;
-                let wrapped_line_count = grapheme_count / buffer_content_width;
+                let wrapped_line_count = line_width / buffer_content_width;
 
                 wrapped_line_count.ceil() as usize
             })
@@ -18,14 +18,15 @@ pub use self::terminal::*;
 use crate::errors::*;
 use crate::input::Key;
 use crate::models::application::{Event, Preferences};
-use self::buffer::{RenderCache, RenderState};
+use crate::util::brackets::BracketIndex;
+use self::buffer::{RenderCache, RenderState, cached_parse_state};
 use self::buffer::ScrollableRegion;
 use self::event_listener::EventListener;
-use scribe::buffer::Buffer;
+use scribe::buffer::{Buffer, Position};
 use std::cmp;
 use std::collections::HashMap;
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::ops::Drop;
 use std::sync::mpsc::{self, Sender, SyncSender};
 use std::sync::Arc;
@@ -38,6 +39,8 @@ pub struct View {
     terminal: Arc<Box<dyn Terminal + Sync + Send + 'static>>,
     scrollable_regions: HashMap<usize, ScrollableRegion>,
     render_caches: HashMap<usize, Rc<RefCell<HashMap<usize, RenderState>>>>,
+    revisions: HashMap<usize, Rc<Cell<usize>>>,
+    changed_lines: HashMap<usize, Rc<Cell<Option<usize>>>>,
     pub theme_set: ThemeSet,
     preferences: Rc<RefCell<Preferences>>,
     pub last_key: Option<Key>,
@@ -60,6 +63,8 @@ impl View {
             preferences,
             scrollable_regions: HashMap::new(),
             render_caches: HashMap::new(),
+            revisions: HashMap::new(),
+            changed_lines: HashMap::new(),
             theme_set,
             event_channel,
             event_listener_killswitch: killswitch_tx
@@ -121,6 +126,8 @@ impl View {
     pub fn forget_buffer(&mut self, buffer: &Buffer) -> Result<()> {
         self.scrollable_regions.remove(&buffer_key(buffer)?);
         self.render_caches.remove(&buffer_key(buffer)?);
+        self.revisions.remove(&buffer_key(buffer)?);
+        self.changed_lines.remove(&buffer_key(buffer)?);
 
         Ok(())
     }
@@ -144,6 +151,38 @@ impl View {
         Ok(cache)
     }
 
+    /// A counter that's bumped whenever the buffer's content changes (including
+    /// undo, redo, and reloads), which can be used to cheaply determine if it has
+    /// changed since it was last checked. Buffers that haven't been initialized
+    /// by the view don't have one.
+    pub fn buffer_revision(&self, buffer: &Buffer) -> Option<usize> {
+        self.revisions
+            .get(&buffer_key(buffer).ok()?)
+            .map(|revision| revision.get())
+    }
+
+    /// The first line changed in the buffer since this was last called, if
+    /// any. Lines before it are unchanged, so this can be used to limit how
+    /// much of the buffer is compared with an earlier copy of its content.
+    pub fn take_changed_line(&self, buffer: &Buffer) -> Option<usize> {
+        self.changed_lines
+            .get(&buffer_key(buffer).ok()?)
+            .and_then(|changed_line| changed_line.take())
+    }
+
+    /// The position of the bracket matching the one at the buffer's cursor.
+    /// Parsing resumes from the buffer's cached render state, where possible.
+    pub fn matching_bracket(&self, buffer: &Buffer) -> Option<Position> {
+        let cache = self.get_render_cache(buffer).ok();
+
+        BracketIndex::find_matching(
+            &buffer.data(),
+            buffer.syntax_definition.as_ref(),
+            *buffer.cursor,
+            |line| cache.and_then(|cache| cached_parse_state(&cache.borrow(), line))
+        )
+    }
+
     pub fn suspend(&mut self) {
         let _ = self.event_listener_killswitch.send(());
         self.terminal.suspend();
@@ -165,10 +204,26 @@ impl View {
             render_cache.clone()
         );
 
-        // Wire up the buffer's change callback to invalidate the render cache.
+        // Buffers can be initialized more than once (e.g. when re-opened), so
+        // an existing revision and changed line are kept, rather than starting over.
+        let revision = self.revisions
+            .entry(buffer_key(buffer)?)
+            .or_insert_with(|| Rc::new(Cell::new(0)))
+            .clone();
+        let changed_line = self.changed_lines
+            .entry(buffer_key(buffer)?)
+            .or_insert_with(|| Rc::new(Cell::new(None)))
+            .clone();
+
+        // Wire up the buffer's change callback to invalidate the render
+        // cache, bump the buffer's revision, and track the first changed line.
         buffer.change_callback = Some(
             Box::new(move |change_position| {
                 render_cache.borrow_mut().invalidate_from(change_position.line);
+                revision.set(revision.get() + 1);
+                changed_line.set(Some(
+                    changed_line.get().map_or(change_position.line, |line| line.min(change_position.line))
+                ));
             })
         );
 
@@ -252,6 +307,46 @@ mod tests {
         assert!(view.render_caches.get(&buffer.id.unwrap()).is_some());
     }
 
+    #[test]
+    fn initialize_buffer_sets_change_callback_to_bump_revision() {
+        let preferences = Rc::new(RefCell::new(Preferences::new(None)));
+        let (tx, _) = mpsc::channel();
+        let mut view = View::new(preferences, tx).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.id = Some(1);
+
+        assert_eq!(view.buffer_revision(&buffer), None);
+        view.initialize_buffer(&mut buffer).unwrap();
+        assert_eq!(view.buffer_revision(&buffer), Some(0));
+
+        buffer.insert("amp");
+        buffer.undo();
+        assert_eq!(view.buffer_revision(&buffer), Some(2));
+
+        // Re-initializing the buffer keeps its revision.
+        view.initialize_buffer(&mut buffer).unwrap();
+        assert_eq!(view.buffer_revision(&buffer), Some(2));
+    }
+
+    #[test]
+    fn initialize_buffer_sets_change_callback_to_track_the_first_changed_line() {
+        let preferences = Rc::new(RefCell::new(Preferences::new(None)));
+        let (tx, _) = mpsc::channel();
+        let mut view = View::new(preferences, tx).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.id = Some(1);
+        buffer.insert("amp\neditor\n");
+        view.initialize_buffer(&mut buffer).unwrap();
+        assert_eq!(view.take_changed_line(&buffer), None);
+
+        buffer.cursor.move_to(Position{ line: 1, offset: 0 });
+        buffer.insert("the ");
+        buffer.cursor.move_to(Position{ line: 2, offset: 0 });
+        buffer.insert("scribe");
+        assert_eq!(view.take_changed_line(&buffer), Some(1));
+        assert_eq!(view.take_changed_line(&buffer), None);
+    }
+
     #[test]
     fn initialize_buffer_sets_change_callback_to_clear_render_cache() {
         let preferences = Rc::new(RefCell::new(Preferences::new(None)));
@@ -6,6 +6,7 @@ use crate::view::StatusLineData;
 use crate::view::style::Style;
 use crate::view::terminal::{Cell, TerminalBuffer};
 use crate::view::View;
+use crate::util::columns;
 use scribe::buffer::{Buffer, Position, Range};
 use scribe::util::LineIterator;
 use std::borrow::Cow;
@@ -13,6 +14,7 @@ use syntect::highlighting::Theme;
 
 pub struct Presenter<'p> {
     cursor_position: Option<Position>,
+    secondary_cursors: Vec<Position>,
     terminal_buffer: TerminalBuffer<'p>,
     theme: Theme,
     pub view: &'p mut View,
@@ -31,6 +33,7 @@ impl<'p> Presenter<'p> {
 
         Ok(Presenter{
             cursor_position: None,
+            secondary_cursors: Vec::new(),
             terminal_buffer: TerminalBuffer::new(
                 view.terminal.width(),
                 view.terminal.height(),
@@ -56,6 +59,12 @@ impl<'p> Presenter<'p> {
         self.cursor_position = position;
     }
 
+    /// Additional buffer cursor positions to be drawn by print_buffer;
+    /// unlike the terminal cursor, these are relative to the buffer.
+    pub fn set_secondary_cursors(&mut self, cursors: Vec<Position>) {
+        self.secondary_cursors = cursors;
+    }
+
     pub fn present(&mut self) {
         for (position, cell) in self.terminal_buffer.iter() {
             self.view.terminal.print(
@@ -76,6 +85,7 @@ impl<'p> Presenter<'p> {
         self.cursor_position = BufferRenderer::new(
             buffer,
             highlights,
+            &self.secondary_cursors,
             scroll_offset,
             &**self.view.terminal,
             &self.theme,
@@ -132,6 +142,43 @@ impl<'p> Presenter<'p> {
         });
     }
 
+    /// Draws a list of entries next to the cursor, below it if there's room
+    /// (and above it otherwise), starting the specified number of columns to
+    /// its left. The cursor's position is determined by print_buffer, which
+    /// must be called first; nothing is drawn if the cursor isn't visible.
+    pub fn print_popup(&mut self, entries: &[String], selected_index: usize, columns_before_cursor: usize) {
+        let cursor = match self.cursor_position {
+            Some(position) => position,
+            None => return,
+        };
+        let content_width = entries.iter().map(|entry| columns::width(entry)).max().unwrap_or(0);
+        let status_line = self.view.terminal.height() - 1;
+        let first_line = if cursor.line + entries.len() < status_line {
+            cursor.line + 1
+        } else {
+            cursor.line.saturating_sub(entries.len())
+        };
+        let offset = cursor.offset
+            .saturating_sub(columns_before_cursor)
+            .min(self.view.terminal.width().saturating_sub(content_width + 2));
+
+        for (index, entry) in entries.iter().enumerate() {
+            let (style, colors) = if index == selected_index {
+                (Style::Bold, Colors::Insert)
+            } else {
+                (Style::Default, Colors::Focused)
+            };
+            let padding = " ".repeat(content_width - columns::width(entry));
+
+            self.print(
+                &Position{ line: first_line + index, offset },
+                style,
+                colors,
+                format!(" {}{} ", entry, padding)
+            );
+        }
+    }
+
     pub fn print<C>(&mut self, position: &Position, style: Style, colors: Colors, content: C)
         where C: Into<Cow<'p, str>>
     {
@@ -1,7 +1,7 @@
 
 use crate::view::terminal::Cell;
 use scribe::buffer::Position;
-use unicode_segmentation::UnicodeSegmentation;
+use crate::util::columns;
 
 /// Iterates over the provided cells, yielding slices for each line.
 pub struct TerminalBufferIterator<'c> {
@@ -27,7 +27,7 @@ impl<'c> Iterator for TerminalBufferIterator<'c> {
                 offset: self.index % self.width
             };
             let cell = &self.cells[self.index];
-            self.index += cell.content.graphemes(true).count().max(1);
+            self.index += columns::width(&cell.content).max(1);
 
             Some((position, cell))
         } else {
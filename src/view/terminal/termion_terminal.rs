@@ -20,7 +20,7 @@ use std::ops::Drop;
 use std::sync::Mutex;
 use std::time::Duration;
 use crate::view::{Colors, Style};
-use unicode_segmentation::UnicodeSegmentation;
+use crate::util::columns;
 use signal_hook::iterator::Signals;
 
 use self::termion::event::Key as TermionKey;
@@ -157,6 +157,7 @@ impl Terminal for TermionTerminal {
                         TermionKey::Esc => Some(Event::Key(Key::Esc)),
                         TermionKey::Char('\n') => Some(Event::Key(Key::Enter)),
                         TermionKey::Char('\t') => Some(Event::Key(Key::Tab)),
+                        TermionKey::BackTab => Some(Event::Key(Key::BackTab)),
                         TermionKey::Char(c) => Some(Event::Key(Key::Char(c))),
                         TermionKey::Ctrl(c) => Some(Event::Key(Key::Ctrl(c))),
                         _ => None,
@@ -247,7 +248,7 @@ impl Terminal for TermionTerminal {
                     *current_position = Some(
                         *target_position + Distance{
                             lines: 0,
-                            offset: content.graphemes(true).count()
+                            offset: columns::width(content)
                         }
                     );
                 }